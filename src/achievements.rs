@@ -0,0 +1,356 @@
+use std::{path::Path, time::Duration};
+
+use crate::{
+    stats::{Stats, KEPT_FIND_TIMES},
+    util::json::{load_override, parse_file, read_file, Fields, FileError},
+};
+
+/// File next to the game that, if present, replaces the bundled achievements.
+const ACHIEVEMENTS_OVERRIDE_PATH: &str = "achievements.json";
+const ACHIEVEMENTS_WHAT: &str = "achievements file";
+
+/// Something to unlock by playing.
+#[derive(Debug, Clone)]
+pub struct Achievement {
+    /// Stays the same across versions, so unlocks can be remembered.
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// Everything that has to be true for it to unlock.
+    pub when: Vec<Condition>,
+}
+
+/// Something about the [`Stats`] that has to be true for an achievement to unlock.
+#[derive(Debug, Clone)]
+pub enum Condition {
+    /// Found at least this many times.
+    Finds(u32),
+    /// Hid at least this many times.
+    Hides(u32),
+    /// Missed at least this many times.
+    Misses(u32),
+    /// Found this many times in a row without a miss.
+    Streak(u32),
+    /// Found in at least this many different programs.
+    Apps(u32),
+    /// Found at least once in less than this.
+    FastestFind(Duration),
+    /// Found at least `count` times in less than `under` each, out of the last
+    /// [`KEPT_FIND_TIMES`] finds.
+    FastFinds { under: Duration, count: u32 },
+}
+
+impl Condition {
+    pub fn met(&self, stats: &Stats) -> bool {
+        match self {
+            Self::Finds(n) => stats.finds >= *n,
+            Self::Hides(n) => stats.hides >= *n,
+            Self::Misses(n) => stats.misses >= *n,
+            Self::Streak(n) => stats.longest_streak >= *n,
+            Self::Apps(n) => stats.app_finds.len() as u32 >= *n,
+            Self::FastestFind(under) => stats.fastest_find.is_some_and(|t| t < *under),
+            Self::FastFinds { under, count } => {
+                stats.find_times.iter().filter(|t| *t < under).count() as u32 >= *count
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Achievements(pub Vec<Achievement>);
+
+impl Achievements {
+    /// The achievements that ship with the game.
+    pub fn bundled() -> Self {
+        Self::parse(include_str!("assets/achievements.json"))
+            .unwrap_or_else(|e| panic!("bundled achievements are broken: {}", e))
+    }
+
+    /// Loads the achievements override file if there is one, and falls back to the bundled
+    /// achievements (after reporting what's wrong) if it can't be used.
+    pub fn load() -> Self {
+        load_override(
+            ACHIEVEMENTS_OVERRIDE_PATH,
+            Self::load_file,
+            "the bundled achievements",
+            Self::bundled,
+        )
+    }
+
+    pub fn load_file(path: &Path) -> Result<Self, FileError> {
+        Self::parse(&read_file(path, ACHIEVEMENTS_WHAT)?)
+    }
+
+    pub fn parse(src: &str) -> Result<Self, FileError> {
+        let json = parse_file(src, ACHIEVEMENTS_WHAT)?;
+        let mut errors = vec![];
+        let mut achievements = vec![];
+
+        match json.get("achievements").map(|it| (it, it.as_array())) {
+            Some((_, Some(list))) => {
+                for (i, def) in list.iter().enumerate() {
+                    let path = format!("achievements[{}]", i);
+                    let mut fields = Fields::new(def, path.clone(), &mut errors);
+                    let achievement = Achievement::parse(&mut fields);
+                    fields.finish();
+                    if let Some(achievement) = achievement {
+                        if achievements
+                            .iter()
+                            .any(|a: &Achievement| a.id == achievement.id)
+                        {
+                            errors
+                                .push(format!("{}.id: \"{}\" is used twice", path, achievement.id));
+                        }
+                        achievements.push(achievement);
+                    }
+                }
+            }
+            Some((it, None)) => errors.push(format!(
+                "achievements: expected an array, found {}",
+                it.type_name()
+            )),
+            None => errors.push("achievements: missing".to_string()),
+        }
+
+        FileError::check(ACHIEVEMENTS_WHAT, errors)?;
+        Ok(Self(achievements))
+    }
+
+    /// The ones in `ids`, in the order they're defined in.
+    pub fn with_ids(&self, ids: &[String]) -> Vec<&Achievement> {
+        self.0.iter().filter(|a| ids.contains(&a.id)).collect()
+    }
+    /// The ones that `stats` qualify for that aren't in `unlocked` yet.
+    pub fn newly_unlocked<'a>(
+        &'a self,
+        stats: &'a Stats,
+        unlocked: &'a [String],
+    ) -> impl Iterator<Item = &'a Achievement> {
+        self.0.iter().filter(move |a| {
+            !unlocked.contains(&a.id) && a.when.iter().all(|condition| condition.met(stats))
+        })
+    }
+}
+
+impl Achievement {
+    fn parse(fields: &mut Fields) -> Option<Self> {
+        let id = fields.string("id");
+        let name = fields.string("name");
+        let description = fields.string("description");
+        let when = fields.nested("when").and_then(|mut when| {
+            let conditions = Condition::parse_all(&mut when);
+            when.finish();
+            conditions
+        });
+        fields.required("when")?;
+        if when.as_ref().is_some_and(Vec::is_empty) {
+            fields.error("when", "needs at least one condition");
+            return None;
+        }
+        Some(Self {
+            id: id?,
+            name: name?,
+            description: description?,
+            when: when?,
+        })
+    }
+}
+
+impl Condition {
+    /// Every condition in `fields`, or `None` if any of them are broken.
+    fn parse_all(fields: &mut Fields) -> Option<Vec<Self>> {
+        if fields.is_broken() {
+            return None;
+        }
+        let mut when = vec![];
+        let mut ok = true;
+        let mut count = |fields: &mut Fields, key, condition: fn(u32) -> Self| {
+            if fields.optional(key).is_some() {
+                match fields.whole_number(key) {
+                    Some(n) => when.push(condition(n as u32)),
+                    None => ok = false,
+                }
+            }
+        };
+        count(fields, "finds", Self::Finds);
+        count(fields, "hides", Self::Hides);
+        count(fields, "misses", Self::Misses);
+        count(fields, "streak", Self::Streak);
+        count(fields, "apps", Self::Apps);
+        if fields.optional("fastest_find").is_some() {
+            match millis(fields, "fastest_find") {
+                Some(under) => when.push(Self::FastestFind(under)),
+                None => ok = false,
+            }
+        }
+        if let Some(mut fast_finds) = fields.nested("fast_finds") {
+            let under = millis(&mut fast_finds, "under");
+            let count = fast_finds.whole_number("count").filter(|&n| {
+                let kept = n as usize <= KEPT_FIND_TIMES;
+                if !kept {
+                    fast_finds.error(
+                        "count",
+                        format!(
+                            "can't be more than the {} finds that are kept",
+                            KEPT_FIND_TIMES
+                        ),
+                    );
+                }
+                kept
+            });
+            fast_finds.finish();
+            match under.zip(count) {
+                Some((under, count)) => when.push(Self::FastFinds {
+                    under,
+                    count: count as u32,
+                }),
+                None => ok = false,
+            }
+        }
+        ok.then_some(when)
+    }
+}
+
+/// A number of milliseconds above 0.
+fn millis(fields: &mut Fields, key: &'static str) -> Option<Duration> {
+    let duration = fields.duration(key)?;
+    if duration.is_zero() {
+        fields.error(key, "expected a number of milliseconds above 0");
+        return None;
+    }
+    Some(duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::json::{FileErrorKind, Json};
+
+    use super::*;
+
+    fn conditions(src: &str) -> Result<Vec<Condition>, Vec<String>> {
+        let json = Json::parse(src).unwrap();
+        let mut errors = vec![];
+        let mut fields = Fields::new(&json, "when".to_string(), &mut errors);
+        let conditions = Condition::parse_all(&mut fields);
+        fields.finish();
+        match conditions {
+            Some(conditions) if errors.is_empty() => Ok(conditions),
+            _ => Err(errors),
+        }
+    }
+    fn met(condition: &str, stats: &Stats) -> bool {
+        let conditions = conditions(condition).unwrap();
+        assert_eq!(conditions.len(), 1);
+        conditions[0].met(stats)
+    }
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn counts() {
+        let mut stats = Stats::default();
+        stats.hid();
+        stats.found(ms(800), Some("notepad.exe"));
+        stats.found(ms(900), Some("explorer.exe"));
+        stats.missed();
+        stats.found(ms(3000), Some("notepad.exe"));
+
+        assert!(met(r#"{"finds": 3}"#, &stats));
+        assert!(!met(r#"{"finds": 4}"#, &stats));
+        assert!(met(r#"{"hides": 1}"#, &stats));
+        assert!(met(r#"{"misses": 1}"#, &stats));
+        assert!(!met(r#"{"misses": 2}"#, &stats));
+        assert!(met(r#"{"apps": 2}"#, &stats));
+        assert!(!met(r#"{"apps": 3}"#, &stats));
+        // the streak before the miss still counts
+        assert!(met(r#"{"streak": 2}"#, &stats));
+        assert!(!met(r#"{"streak": 3}"#, &stats));
+    }
+
+    #[test]
+    fn find_times() {
+        let mut stats = Stats::default();
+        assert!(!met(r#"{"fastest_find": 1000}"#, &stats));
+        for t in [800, 900, 3000] {
+            stats.found(ms(t), None);
+        }
+        assert!(met(r#"{"fastest_find": 1000}"#, &stats));
+        assert!(!met(r#"{"fastest_find": 800}"#, &stats));
+        assert!(met(
+            r#"{"fast_finds": {"under": 1000, "count": 2}}"#,
+            &stats
+        ));
+        assert!(!met(
+            r#"{"fast_finds": {"under": 900, "count": 2}}"#,
+            &stats
+        ));
+    }
+
+    #[test]
+    fn every_condition_has_to_hold() {
+        let achievements = Achievements::parse(
+            r#"{"achievements": [{
+                "id": "both", "name": "Both", "description": "Find and miss",
+                "when": {"finds": 1, "misses": 1}
+            }]}"#,
+        )
+        .unwrap();
+        let mut stats = Stats::default();
+        stats.found(ms(500), None);
+        assert_eq!(achievements.newly_unlocked(&stats, &[]).count(), 0);
+        stats.missed();
+        assert_eq!(achievements.newly_unlocked(&stats, &[]).count(), 1);
+        assert_eq!(
+            achievements
+                .newly_unlocked(&stats, &["both".to_string()])
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn reports_bad_conditions() {
+        assert_eq!(
+            conditions(
+                r#"{"finds": 0, "fastest_find": 0, "fast_finds": {"under": 1000, "count": 1001},
+                    "luck": 7}"#
+            )
+            .unwrap_err(),
+            [
+                "when.finds: expected a whole number of at least 1",
+                "when.fastest_find: expected a number of milliseconds above 0",
+                "when.fast_finds.count: can't be more than the 1000 finds that are kept",
+                "when.luck: unknown field",
+            ]
+        );
+        assert_eq!(
+            conditions(r#"{"fast_finds": {"count": 2}}"#).unwrap_err(),
+            ["when.fast_finds.under: missing"]
+        );
+    }
+
+    #[test]
+    fn reports_bad_achievements() {
+        let e = Achievements::parse(
+            r#"{"achievements": [
+                {"id": "a", "name": "A", "description": "", "when": {"finds": 1}},
+                {"id": "a", "name": "A again", "description": "", "when": {}},
+                {"id": "b", "name": "B", "description": "", "when": {"finds": 2}},
+                {"id": "b", "name": "B again", "description": "", "when": {"finds": 3}}
+            ]}"#,
+        )
+        .unwrap_err();
+        let FileErrorKind::Invalid(problems) = e.kind else {
+            panic!("expected problems, got {}", e);
+        };
+        assert_eq!(
+            problems,
+            [
+                "achievements[1].when: needs at least one condition",
+                "achievements[3].id: \"b\" is used twice",
+            ]
+        );
+        assert!(Achievements::bundled().0.len() > 1);
+    }
+}
@@ -1,3 +1,11 @@
+pub mod behavior;
 pub mod creature;
+pub mod dialogue;
+pub mod difficulty;
 pub mod ext_window;
 pub mod hiding;
+pub mod needs;
+pub mod petting;
+pub mod roam;
+pub mod schedule;
+pub mod system;
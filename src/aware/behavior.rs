@@ -0,0 +1,991 @@
+use std::{collections::HashMap, path::Path, time::Duration};
+
+use rand::Rng;
+
+use crate::util::json::{
+    load_override, millis, parse_file, read_file, Fields, FileError, Json, MAX_DURATION_MS,
+};
+
+use super::{
+    hiding::Facing,
+    needs::{NeedRanges, Needs},
+};
+
+/// File next to the game that, if present, replaces the bundled behavior.
+const BEHAVIOR_OVERRIDE_PATH: &str = "behavior.json";
+const BEHAVIOR_WHAT: &str = "behavior file";
+
+/// The creature's state machine: which states she can be in, what she does in them, and what
+/// makes her move on to the next one.
+#[derive(Debug, Clone)]
+pub struct Behavior {
+    pub initial: String,
+    pub states: HashMap<String, StateDef>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StateDef {
+    pub kind: StateKind,
+    /// How long until the `timeout` event fires, or never if `None`.
+    pub duration: Option<TimeRange>,
+    /// Where to go when each event happens. Events not listed are ignored.
+    pub on: HashMap<Event, Vec<Transition>>,
+}
+
+/// A state to go to, as long as her needs are right for it.
+#[derive(Debug, Clone)]
+pub struct Transition {
+    pub to: String,
+    pub when: NeedRanges,
+}
+
+/// What the creature does while in a state. Each kind has its own movement and rendering.
+#[derive(Debug, Clone)]
+pub enum StateKind {
+    /// Stand around.
+    Idle {
+        animation: Choice<String>,
+        /// Shown instead while she's looking towards the cursor.
+        look: Option<FacingAnimations>,
+    },
+    /// Sleep where she stands until something wakes her up.
+    Sleep { animation: Choice<String> },
+    /// Stand around and say something.
+    Talk { animation: Choice<String> },
+    /// Jump along an arc to somewhere.
+    Jump {
+        target: JumpTarget,
+        animation: Choice<String>,
+    },
+    /// Hide in a window and peek out every now and then.
+    Hide {
+        first_peek: TimeRange,
+        peek_duration: TimeRange,
+        hidden_duration: TimeRange,
+        peek_animations: FacingAnimations,
+    },
+    /// Scurry over to a new hiding spot.
+    Sneak {
+        /// In pixels per second.
+        speed: f64,
+        min_duration: Duration,
+        /// How many of the topmost windows she may pick her new spot from.
+        candidates: usize,
+        animation_left: String,
+        animation_right: String,
+    },
+    /// Jump out of a hiding spot after being found.
+    Shocked { animation: Choice<String> },
+    /// Wander around on top of windows and along the bottom of the screen.
+    Roam {
+        /// In pixels per second.
+        walk_speed: f64,
+        /// How long she stands still between moves.
+        pause: TimeRange,
+        /// How likely she is to jump to another platform instead of walking, from 0 to 1.
+        jump_chance: f64,
+        /// Furthest she jumps, in pixels.
+        jump_reach: f64,
+        jump_time: Duration,
+        /// How fast she falls when there's nothing under her, in pixels per second per second.
+        gravity: f64,
+        animations: RoamAnimations,
+    },
+    /// Dangle from the cursor while being dragged around.
+    Held { animation: Choice<String> },
+    /// Fly through the air after being let go, bouncing off the edges of the screen.
+    Thrown {
+        /// In pixels per second per second.
+        gravity: f64,
+        /// How much of her speed she keeps when bouncing off an edge, from 0 to 1.
+        bounce: f64,
+        /// How quickly she slows down while sliding along the bottom, in pixels per second per
+        /// second.
+        friction: f64,
+        animation: Choice<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Event {
+    /// The state's duration ran out.
+    Timeout,
+    /// She was clicked on (with the left button, and let go of without dragging her).
+    Click,
+    /// She was clicked on while peeking.
+    Found,
+    /// Someone clicked near her hiding spot, or on her while she wasn't peeking, and she decided
+    /// to move somewhere else instead of taunting them.
+    Miss,
+    /// "Say something" was picked from her right-click menu.
+    Chat,
+    /// The cursor was moved back and forth over her enough times.
+    Petted,
+    /// Files were dropped on her.
+    Fed,
+    /// She has been hiding in the same spot for too long.
+    Relocate,
+    /// The animation she's playing reached its end. Only happens for animations that don't loop.
+    AnimationEnd,
+    /// She was pressed on and dragged.
+    Grab,
+    /// She was let go of after being dragged.
+    Throw,
+    /// She came to rest after being thrown.
+    Land,
+    /// It's evening on her schedule, time to start yawning.
+    Evening,
+    /// It's bedtime on her schedule.
+    Night,
+    /// It's morning on her schedule, time to get up.
+    Morning,
+}
+impl Event {
+    pub const ALL: [Event; 15] = [
+        Self::Timeout,
+        Self::Click,
+        Self::Found,
+        Self::Miss,
+        Self::Chat,
+        Self::Petted,
+        Self::Fed,
+        Self::Relocate,
+        Self::AnimationEnd,
+        Self::Grab,
+        Self::Throw,
+        Self::Land,
+        Self::Evening,
+        Self::Night,
+        Self::Morning,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Timeout => "timeout",
+            Self::Click => "click",
+            Self::Found => "found",
+            Self::Miss => "miss",
+            Self::Chat => "chat",
+            Self::Petted => "petted",
+            Self::Fed => "fed",
+            Self::Relocate => "relocate",
+            Self::AnimationEnd => "animation_end",
+            Self::Grab => "grab",
+            Self::Throw => "throw",
+            Self::Land => "land",
+            Self::Evening => "evening",
+            Self::Night => "night",
+            Self::Morning => "morning",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum JumpTarget {
+    /// Straight up off the top of the screen.
+    Offscreen,
+    /// The center of the screen.
+    Home,
+}
+
+/// A duration picked uniformly at random between `min` and `max` (inclusive).
+#[derive(Debug, Clone, Copy)]
+pub struct TimeRange {
+    pub min: Duration,
+    pub max: Duration,
+}
+impl TimeRange {
+    pub fn sample(self) -> Duration {
+        if self.min >= self.max {
+            return self.min;
+        }
+        rand::thread_rng().gen_range(self.min..=self.max)
+    }
+}
+
+/// A weighted random choice between options.
+#[derive(Debug, Clone)]
+pub struct Choice<T>(Vec<(T, f64)>);
+impl<T> Choice<T> {
+    pub fn pick(&self) -> &T {
+        let total = self.0.iter().map(|(_, w)| w).sum::<f64>();
+        let mut x = rand::thread_rng().gen_range(0.0..total.max(f64::MIN_POSITIVE));
+        for (option, weight) in &self.0 {
+            if x < *weight {
+                return option;
+            }
+            x -= weight;
+        }
+        &self.0.last().unwrap().0
+    }
+    pub fn options(&self) -> impl Iterator<Item = &T> {
+        self.0.iter().map(|(option, _)| option)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FacingAnimations {
+    pub left: String,
+    pub right: String,
+    pub up: String,
+    pub down: String,
+}
+impl FacingAnimations {
+    fn all(&self) -> [&str; 4] {
+        [&self.left, &self.right, &self.up, &self.down]
+    }
+    pub fn get(&self, facing: Facing) -> &str {
+        match facing {
+            Facing::Left => &self.left,
+            Facing::Right => &self.right,
+            Facing::Up => &self.up,
+            Facing::Down => &self.down,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RoamAnimations {
+    pub stand: String,
+    /// Shown instead of `stand` while she's looking towards the cursor.
+    pub look: Option<FacingAnimations>,
+    pub walk_left: String,
+    pub walk_right: String,
+    pub jump: String,
+    pub fall: String,
+}
+
+impl Behavior {
+    /// The behavior that ships with the game.
+    pub fn bundled(animations: &[&str]) -> Self {
+        Self::parse(include_str!("../assets/behavior.json"), animations)
+            .unwrap_or_else(|e| panic!("bundled behavior is broken: {}", e))
+    }
+
+    /// Loads the behavior override file if there is one, and falls back to the bundled behavior
+    /// (after reporting what's wrong) if it can't be used.
+    pub fn load(animations: &[&str]) -> Self {
+        load_override(
+            BEHAVIOR_OVERRIDE_PATH,
+            |path| Self::load_file(path, animations),
+            "the bundled behavior",
+            || Self::bundled(animations),
+        )
+    }
+
+    /// Names of every animation some state uses.
+    pub fn animations_used(&self) -> Vec<&str> {
+        let mut used = vec![];
+        for def in self.states.values() {
+            match &def.kind {
+                StateKind::Idle { animation, look } => {
+                    used.extend(animation.options().map(String::as_str));
+                    used.extend(look.iter().flat_map(FacingAnimations::all));
+                }
+                StateKind::Sleep { animation }
+                | StateKind::Talk { animation }
+                | StateKind::Jump { animation, .. }
+                | StateKind::Shocked { animation }
+                | StateKind::Held { animation }
+                | StateKind::Thrown { animation, .. } => {
+                    used.extend(animation.options().map(String::as_str))
+                }
+                StateKind::Hide {
+                    peek_animations, ..
+                } => used.extend(peek_animations.all()),
+                StateKind::Sneak {
+                    animation_left,
+                    animation_right,
+                    ..
+                } => used.extend([animation_left.as_str(), animation_right.as_str()]),
+                StateKind::Roam { animations, .. } => {
+                    used.extend([
+                        animations.stand.as_str(),
+                        &animations.walk_left,
+                        &animations.walk_right,
+                        &animations.jump,
+                        &animations.fall,
+                    ]);
+                    used.extend(animations.look.iter().flat_map(FacingAnimations::all));
+                }
+            }
+        }
+        used.sort();
+        used.dedup();
+        used
+    }
+
+    pub fn load_file(path: &Path, animations: &[&str]) -> Result<Self, FileError> {
+        Self::parse(&read_file(path, BEHAVIOR_WHAT)?, animations)
+    }
+
+    /// Parses and validates a behavior file. `animations` are the animation names states are
+    /// allowed to refer to.
+    pub fn parse(src: &str, animations: &[&str]) -> Result<Self, FileError> {
+        let json = parse_file(src, BEHAVIOR_WHAT)?;
+        let mut errors = vec![];
+
+        let mut root = Fields::new(&json, String::new(), &mut errors);
+        let initial = root.string("initial");
+        let states_json = root.required("states");
+        root.finish();
+
+        let mut states = HashMap::new();
+        // States that exist but are broken still count as existing, so that every mistake isn't
+        // also reported again everywhere the state is referred to.
+        let mut declared = vec![];
+        match states_json.map(|it| (it, it.as_object())) {
+            Some((_, Some(entries))) => {
+                for (name, def) in entries {
+                    declared.push(name.as_str());
+                    let path = format!("states.{}", name);
+                    if let Some(def) = StateDef::parse(def, path, animations, &mut errors) {
+                        states.insert(name.clone(), def);
+                    }
+                }
+            }
+            Some((it, None)) => errors.push(format!(
+                "states: expected an object of states, found {}",
+                it.type_name()
+            )),
+            None => {}
+        }
+
+        // Check that everything that refers to a state refers to one that exists.
+        if let Some(initial) = &initial {
+            if !declared.contains(&initial.as_str()) {
+                errors.push(format!("initial: no state named \"{}\"", initial));
+            }
+        }
+        for name in &declared {
+            let Some(def) = states.get(*name) else {
+                continue;
+            };
+            for event in Event::ALL {
+                for transition in def.on.get(&event).into_iter().flatten() {
+                    if !declared.contains(&transition.to.as_str()) {
+                        errors.push(format!(
+                            "states.{}.on.{}: no state named \"{}\"",
+                            name,
+                            event.name(),
+                            transition.to
+                        ));
+                    }
+                }
+            }
+            if def.duration.is_some() && !def.on.contains_key(&Event::Timeout) {
+                errors.push(format!(
+                    "states.{}: has a duration but nothing to do on \"timeout\"",
+                    name
+                ));
+            }
+            let needs_duration =
+                matches!(def.kind, StateKind::Jump { .. } | StateKind::Shocked { .. });
+            if needs_duration && def.duration.is_none() {
+                errors.push(format!(
+                    "states.{}: jumping and shocked states need a duration",
+                    name
+                ));
+            }
+            if matches!(def.kind, StateKind::Sneak { .. }) && !def.on.contains_key(&Event::Timeout)
+            {
+                errors.push(format!(
+                    "states.{}: sneaking states need somewhere to go on \"timeout\"",
+                    name
+                ));
+            }
+            if matches!(def.kind, StateKind::Hide { .. }) && !def.on.contains_key(&Event::Found) {
+                errors.push(format!(
+                    "states.{}: hiding states need somewhere to go when \"found\"",
+                    name
+                ));
+            }
+            if matches!(def.kind, StateKind::Sleep { .. }) && !def.on.contains_key(&Event::Morning)
+            {
+                errors.push(format!(
+                    "states.{}: sleeping states need somewhere to go in the \"morning\"",
+                    name
+                ));
+            }
+            if matches!(def.kind, StateKind::Held { .. }) && !def.on.contains_key(&Event::Throw) {
+                errors.push(format!(
+                    "states.{}: held states need somewhere to go on \"throw\"",
+                    name
+                ));
+            }
+            if matches!(def.kind, StateKind::Thrown { .. }) && !def.on.contains_key(&Event::Land) {
+                errors.push(format!(
+                    "states.{}: thrown states need somewhere to go on \"land\"",
+                    name
+                ));
+            }
+        }
+
+        FileError::check(BEHAVIOR_WHAT, errors)?;
+        Ok(Self {
+            initial: initial.unwrap(),
+            states,
+        })
+    }
+}
+
+impl StateDef {
+    /// Where to go when `event` happens, feeling `needs`: the first transition for it whose
+    /// conditions hold. The last transition for an event never has conditions, so there's
+    /// always one if the event is listed at all.
+    pub fn next(&self, event: Event, needs: &Needs) -> Option<&str> {
+        self.on
+            .get(&event)?
+            .iter()
+            .find(|transition| transition.when.hold(needs))
+            .map(|transition| transition.to.as_str())
+    }
+
+    fn parse(
+        json: &Json,
+        path: String,
+        animations: &[&str],
+        errors: &mut Vec<String>,
+    ) -> Option<Self> {
+        let mut fields = Fields::new(json, path, errors);
+        if fields.is_broken() {
+            return None;
+        }
+        // Everything gets read before bailing out so that all the problems get reported at once.
+        let kind = StateKind::parse(&mut fields, animations);
+        let duration = match fields.optional("duration") {
+            Some(_) => fields.time_range("duration").map(Some),
+            None => Some(None),
+        };
+        let on = fields.transitions("on");
+        fields.finish();
+
+        Some(Self {
+            kind: kind?,
+            duration: duration?,
+            on: on?,
+        })
+    }
+}
+
+impl StateKind {
+    /// Every field of the kind gets read even if an earlier one is broken, so that none of them
+    /// get reported as unknown.
+    fn parse(fields: &mut Fields, animations: &[&str]) -> Option<Self> {
+        Some(match fields.string("kind")?.as_str() {
+            "idle" => {
+                let animation = fields.animation_choice("animation", animations);
+                let look = fields.optional_facing_animations("look_animations", animations);
+                Self::Idle {
+                    animation: animation?,
+                    look: look?,
+                }
+            }
+            "sleep" => Self::Sleep {
+                animation: fields.animation_choice("animation", animations)?,
+            },
+            "talk" => Self::Talk {
+                animation: fields.animation_choice("animation", animations)?,
+            },
+            "jump" => {
+                let target = fields.string("target");
+                let animation = fields.animation_choice("animation", animations);
+                let target = match target?.as_str() {
+                    "offscreen" => JumpTarget::Offscreen,
+                    "home" => JumpTarget::Home,
+                    other => {
+                        fields.error(
+                            "target",
+                            format!(
+                                "unknown jump target \"{}\", expected \"offscreen\" or \"home\"",
+                                other
+                            ),
+                        );
+                        return None;
+                    }
+                };
+                Self::Jump {
+                    target,
+                    animation: animation?,
+                }
+            }
+            "hide" => {
+                let first_peek = fields.time_range("first_peek");
+                let peek_duration = fields.time_range("peek_duration");
+                let hidden_duration = fields.time_range("hidden_duration");
+                let peek_animations = fields.facing_animations("peek_animations", animations);
+                Self::Hide {
+                    first_peek: first_peek?,
+                    peek_duration: peek_duration?,
+                    hidden_duration: hidden_duration?,
+                    peek_animations: peek_animations?,
+                }
+            }
+            "sneak" => {
+                let speed = fields.positive_number("speed");
+                let min_duration = fields.duration("min_duration");
+                let candidates = fields.whole_number("candidates");
+                let animation_left = fields.animation("animation_left", animations);
+                let animation_right = fields.animation("animation_right", animations);
+                Self::Sneak {
+                    speed: speed?,
+                    min_duration: min_duration?,
+                    candidates: candidates? as usize,
+                    animation_left: animation_left?,
+                    animation_right: animation_right?,
+                }
+            }
+            "shocked" => Self::Shocked {
+                animation: fields.animation_choice("animation", animations)?,
+            },
+            "roam" => {
+                let walk_speed = fields.positive_number("walk_speed");
+                let pause = fields.time_range("pause");
+                let jump_chance = fields.fraction("jump_chance");
+                let jump_reach = fields.positive_number("jump_reach");
+                let jump_time = fields.duration("jump_time");
+                let gravity = fields.positive_number("gravity");
+                let animations = fields.roam_animations("animations", animations);
+                Self::Roam {
+                    walk_speed: walk_speed?,
+                    pause: pause?,
+                    jump_chance: jump_chance?,
+                    jump_reach: jump_reach?,
+                    jump_time: jump_time?,
+                    gravity: gravity?,
+                    animations: animations?,
+                }
+            }
+            "held" => Self::Held {
+                animation: fields.animation_choice("animation", animations)?,
+            },
+            "thrown" => {
+                let gravity = fields.positive_number("gravity");
+                let bounce = fields.fraction("bounce");
+                let friction = fields.positive_number("friction");
+                let animation = fields.animation_choice("animation", animations);
+                Self::Thrown {
+                    gravity: gravity?,
+                    bounce: bounce?,
+                    friction: friction?,
+                    animation: animation?,
+                }
+            }
+            other => {
+                fields.error(
+                    "kind",
+                    format!(
+                        "unknown kind \"{}\", expected one of idle, sleep, talk, jump, hide, \
+                         sneak, shocked, roam, held, thrown",
+                        other
+                    ),
+                );
+                return None;
+            }
+        })
+    }
+}
+
+impl<'j, 'e> Fields<'j, 'e> {
+    /// Either a number of milliseconds or `{ "min": ms, "max": ms }`.
+    fn time_range(&mut self, key: &'static str) -> Option<TimeRange> {
+        let value = self.required(key)?;
+        let ms = |v: Option<&Json>| v.and_then(Json::as_f64).and_then(millis);
+        let range = match value {
+            Json::Number(_) => ms(Some(value)).map(|d| TimeRange { min: d, max: d }),
+            Json::Object(_) => ms(value.get("min"))
+                .zip(ms(value.get("max")))
+                .map(|(min, max)| TimeRange { min, max }),
+            _ => None,
+        };
+        match range {
+            Some(range) if range.min <= range.max => Some(range),
+            Some(_) => {
+                self.error(key, "min is greater than max");
+                None
+            }
+            None => {
+                self.error(
+                    key,
+                    format!(
+                        "expected milliseconds or {{ \"min\": milliseconds, \"max\": milliseconds }}, \
+                         up to {}",
+                        MAX_DURATION_MS
+                    ),
+                );
+                None
+            }
+        }
+    }
+    fn animation(&mut self, key: &'static str, animations: &[&str]) -> Option<String> {
+        let name = self.string(key)?;
+        self.check_animation(key, name, animations)
+    }
+    fn check_animation(&mut self, key: &str, name: String, animations: &[&str]) -> Option<String> {
+        if animations.contains(&name.as_str()) {
+            Some(name)
+        } else {
+            self.error(
+                key,
+                format!(
+                    "unknown animation \"{}\", expected one of {}",
+                    name,
+                    animations.join(", ")
+                ),
+            );
+            None
+        }
+    }
+    /// Either an animation name or `{ "name": weight, ... }`.
+    fn animation_choice(
+        &mut self,
+        key: &'static str,
+        animations: &[&str],
+    ) -> Option<Choice<String>> {
+        let value = self.required(key)?;
+        match value {
+            Json::String(name) => self
+                .check_animation(key, name.clone(), animations)
+                .map(|name| Choice(vec![(name, 1.0)])),
+            Json::Object(entries) if !entries.is_empty() => {
+                let mut options = vec![];
+                for (name, weight) in entries {
+                    let key = format!("{}.{}", key, name);
+                    let name = self.check_animation(&key, name.clone(), animations)?;
+                    match weight.as_f64() {
+                        Some(w) if w > 0.0 => options.push((name, w)),
+                        _ => {
+                            self.error(&key, "expected a weight greater than 0");
+                            return None;
+                        }
+                    }
+                }
+                Some(Choice(options))
+            }
+            _ => {
+                self.error(
+                    key,
+                    "expected an animation name or { \"animation name\": weight, ... }",
+                );
+                None
+            }
+        }
+    }
+    fn facing_animations(
+        &mut self,
+        key: &'static str,
+        animations: &[&str],
+    ) -> Option<FacingAnimations> {
+        let value = self.required(key)?;
+        let mut fields = self.within(value, key);
+        if fields.is_broken() {
+            return None;
+        }
+        let left = fields.animation("left", animations);
+        let right = fields.animation("right", animations);
+        let up = fields.animation("up", animations);
+        let down = fields.animation("down", animations);
+        fields.finish();
+        Some(FacingAnimations {
+            left: left?,
+            right: right?,
+            up: up?,
+            down: down?,
+        })
+    }
+    fn optional_facing_animations(
+        &mut self,
+        key: &'static str,
+        animations: &[&str],
+    ) -> Option<Option<FacingAnimations>> {
+        match self.optional(key) {
+            Some(_) => self.facing_animations(key, animations).map(Some),
+            None => Some(None),
+        }
+    }
+    fn roam_animations(
+        &mut self,
+        key: &'static str,
+        animations: &[&str],
+    ) -> Option<RoamAnimations> {
+        let value = self.required(key)?;
+        let mut fields = self.within(value, key);
+        if fields.is_broken() {
+            return None;
+        }
+        let stand = fields.animation("stand", animations);
+        let look = fields.optional_facing_animations("look", animations);
+        let walk_left = fields.animation("walk_left", animations);
+        let walk_right = fields.animation("walk_right", animations);
+        let jump = fields.animation("jump", animations);
+        let fall = fields.animation("fall", animations);
+        fields.finish();
+        Some(RoamAnimations {
+            stand: stand?,
+            look: look?,
+            walk_left: walk_left?,
+            walk_right: walk_right?,
+            jump: jump?,
+            fall: fall?,
+        })
+    }
+    /// `{ "event": target, ... }`, where each target is a state name, or an array of
+    /// `{ "to": state name, "when": { need: { "min": n, "max": n }, ... } }` to try in order that
+    /// ends with a state name for when none of them fit.
+    fn transitions(&mut self, key: &'static str) -> Option<HashMap<Event, Vec<Transition>>> {
+        let Some(value) = self.optional(key) else {
+            return Some(HashMap::new());
+        };
+        let Some(entries) = value.as_object() else {
+            self.error(
+                key,
+                format!(
+                    "expected an object of transitions, found {}",
+                    value.type_name()
+                ),
+            );
+            return None;
+        };
+        let mut on = HashMap::new();
+        for (event_name, target) in entries {
+            let key = format!("{}.{}", key, event_name);
+            let Some(event) = Event::ALL.into_iter().find(|e| e.name() == event_name) else {
+                let names = Event::ALL.map(Event::name).join(", ");
+                self.error(
+                    &key,
+                    format!(
+                        "unknown event \"{}\", expected one of {}",
+                        event_name, names
+                    ),
+                );
+                continue;
+            };
+            let transitions = match target {
+                Json::String(to) => Some(vec![Transition {
+                    to: to.clone(),
+                    when: NeedRanges::default(),
+                }]),
+                Json::Array(options) if matches!(options.last(), Some(Json::String(_))) => options
+                    .iter()
+                    .enumerate()
+                    .map(|(i, option)| self.transition(option, format!("{}[{}]", key, i)))
+                    .collect::<Option<Vec<_>>>(),
+                _ => {
+                    self.error(
+                        &key,
+                        "expected the name of a state, or an array of { \"to\": state name, \
+                         \"when\": conditions } ending with the name of a state",
+                    );
+                    None
+                }
+            };
+            if let Some(transitions) = transitions {
+                on.insert(event, transitions);
+            }
+        }
+        Some(on)
+    }
+    fn transition(&mut self, json: &'j Json, key: String) -> Option<Transition> {
+        if let Json::String(to) = json {
+            return Some(Transition {
+                to: to.clone(),
+                when: NeedRanges::default(),
+            });
+        }
+        let mut fields = self.within(json, &key);
+        if fields.is_broken() {
+            return None;
+        }
+        let to = fields.string("to");
+        let when = match fields.nested("when") {
+            Some(mut when) if !when.is_broken() => {
+                let ranges = NeedRanges::parse(&mut when);
+                when.finish();
+                ranges
+            }
+            Some(_) => None,
+            None => Some(NeedRanges::default()),
+        };
+        fields.finish();
+        Some(Transition {
+            to: to?,
+            when: when?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::json::FileErrorKind;
+
+    use super::*;
+
+    const ANIMATIONS: [&str; 2] = ["idle", "wave"];
+
+    fn problems(src: &str) -> Vec<String> {
+        match Behavior::parse(src, &ANIMATIONS) {
+            Err(FileError {
+                kind: FileErrorKind::Invalid(problems),
+                ..
+            }) => problems,
+            other => panic!("expected problems, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_states() {
+        let behavior = Behavior::parse(
+            r#"{
+                "initial": "idle",
+                "states": {
+                    "idle": {
+                        "kind": "idle",
+                        "animation": { "idle": 1, "wave": 3 },
+                        "duration": { "min": 1000, "max": 2000 },
+                        "on": {
+                            "timeout": [{ "to": "nap", "when": { "energy": { "max": 0.3 } } }, "idle"]
+                        }
+                    },
+                    "nap": { "kind": "sleep", "animation": "idle", "on": { "morning": "idle" } }
+                }
+            }"#,
+            &ANIMATIONS,
+        )
+        .unwrap();
+        assert_eq!(behavior.initial, "idle");
+        assert_eq!(behavior.animations_used(), ANIMATIONS);
+
+        let idle = &behavior.states["idle"];
+        let duration = idle.duration.unwrap();
+        assert_eq!(duration.min, Duration::from_millis(1000));
+        assert_eq!(duration.max, Duration::from_millis(2000));
+        let tired = Needs {
+            energy: 0.2,
+            ..Needs::default()
+        };
+        assert_eq!(idle.next(Event::Timeout, &tired), Some("nap"));
+        assert_eq!(idle.next(Event::Timeout, &Needs::default()), Some("idle"));
+        assert_eq!(idle.next(Event::Click, &Needs::default()), None);
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let problems = problems(
+            r#"{
+                "initial": "start",
+                "states": {
+                    "idle": {
+                        "kind": "idle",
+                        "animation": "dance",
+                        "colour": "red",
+                        "on": { "clik": "idle", "click": "idle" }
+                    },
+                    "jump": { "kind": "jump", "target": "moon", "animation": "idle" },
+                    "hide": { "animation": "idle" },
+                    "rest": {
+                        "kind": "talk",
+                        "animation": "wave",
+                        "on": { "timeout": [{ "to": "idle", "when": { "energy": { "min": 2 } } }, "idle"] }
+                    }
+                }
+            }"#,
+        );
+        assert_eq!(
+            problems,
+            [
+                "states.idle.animation: unknown animation \"dance\", expected one of idle, wave"
+                    .to_string(),
+                format!(
+                    "states.idle.on.clik: unknown event \"clik\", expected one of {}",
+                    Event::ALL.map(Event::name).join(", ")
+                ),
+                "states.idle.colour: unknown field".to_string(),
+                "states.jump.target: unknown jump target \"moon\", expected \"offscreen\" or \"home\""
+                    .to_string(),
+                "states.hide.kind: missing".to_string(),
+                "states.hide.animation: unknown field".to_string(),
+                "states.rest.on.timeout[0].when.energy: expected { \"min\": number, \"max\": number }, \
+                 from 0 to 1"
+                    .to_string(),
+                "initial: no state named \"start\"".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_numbers() {
+        let problems = problems(
+            r#"{
+                "initial": "idle",
+                "states": {
+                    "idle": {
+                        "kind": "idle",
+                        "animation": "idle",
+                        "duration": { "min": 2000, "max": 1000 },
+                        "on": { "timeout": "sneak" }
+                    },
+                    "sneak": {
+                        "kind": "sneak",
+                        "speed": 0,
+                        "min_duration": -5,
+                        "candidates": 1.5,
+                        "animation_left": "idle",
+                        "animation_right": "wave",
+                        "duration": 100000000,
+                        "on": { "timeout": "idle" }
+                    }
+                }
+            }"#,
+        );
+        assert_eq!(
+            problems,
+            [
+                "states.idle.duration: min is greater than max",
+                "states.sneak.speed: expected a number greater than 0",
+                "states.sneak.min_duration: expected milliseconds, up to 86400000",
+                "states.sneak.candidates: expected a whole number of at least 1",
+                "states.sneak.duration: expected milliseconds or { \"min\": milliseconds, \
+                 \"max\": milliseconds }, up to 86400000",
+            ]
+        );
+    }
+
+    #[test]
+    fn states_need_somewhere_to_go() {
+        let problems = problems(
+            r#"{
+                "initial": "idle",
+                "states": {
+                    "idle": {
+                        "kind": "idle",
+                        "animation": "idle",
+                        "duration": 1000,
+                        "on": { "click": "idle", "grab": "held" }
+                    },
+                    "jump": { "kind": "jump", "target": "home", "animation": "idle" },
+                    "nap": { "kind": "sleep", "animation": "idle" }
+                }
+            }"#,
+        );
+        assert_eq!(
+            problems,
+            [
+                "states.idle.on.grab: no state named \"held\"",
+                "states.idle: has a duration but nothing to do on \"timeout\"",
+                "states.jump: jumping and shocked states need a duration",
+                "states.nap: sleeping states need somewhere to go in the \"morning\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn not_an_object() {
+        assert_eq!(
+            problems("[]"),
+            ["<root>: expected an object, found an array"]
+        );
+        let e = Behavior::parse("{", &ANIMATIONS).unwrap_err();
+        assert!(matches!(e.kind, FileErrorKind::Parse(_)));
+        assert!(e.to_string().starts_with("behavior file is not valid JSON"));
+    }
+}
@@ -1,343 +1,1518 @@
-use std::time::{Duration, SystemTime};
+use std::{
+    collections::{HashMap, VecDeque},
+    f64::consts::PI,
+    path::Path,
+    time::{Duration, SystemTime},
+};
 
-use rand::Rng;
+use rand::{
+    seq::{IteratorRandom, SliceRandom},
+    Rng,
+};
 
 use crate::{
-    include_imageasset,
+    config::Config,
     render::{
-        renderer::{Anchor, RelativeTo, RenderableId, Renderer},
-        sprite::Sprite,
+        animation::{AnimationEvent, Animator},
+        bubble::SpeechBubble,
+        emote::Emote,
+        renderer::{RelativeTo, RenderWindowId, RenderableId, Renderer},
+        sprite_sheet::{SheetAsset, SpriteSheet, SHEET_WHAT},
+    },
+    round::Mode,
+    save::CreatureSave,
+    util::{
+        json::{load_override, FileError},
+        RectI, Vec2I,
     },
-    util::{RectI, Vec2I},
 };
 
 use super::{
+    behavior::{Behavior, Event, JumpTarget, StateKind},
+    dialogue::{Context, LastEvent, Voice},
+    difficulty::{Adaptive, Difficulty},
     ext_window::{iter_window_candidates, ExtWindowInfo},
-    hiding::{find_hiding_spot_in_window, Facing},
+    hiding::{EdgeMap, Facing},
+    needs::{Interaction, Needs},
+    petting::{PettingSettings, Strokes},
+    roam::{find_platforms, Platform},
+    schedule::{Clock, Schedule},
+    system::beep,
 };
 
+/// Clip shown while she's completely out of sight.
+const HIDDEN_CLIP: &str = "hidden";
+/// Sprite sheet that gets used instead of the bundled one if it's next to the game.
+const SHEET_OVERRIDE_PATH: &str = "creature.json";
+
+/// The sprite sheet that ships with the game.
+fn bundled_sheet() -> SheetAsset {
+    SheetAsset::decode(
+        include_bytes!("../assets/creature/creature.png"),
+        include_str!("../assets/creature/creature.json"),
+    )
+    .unwrap_or_else(|e| panic!("bundled sprite sheet is broken: {}", e))
+}
+
+/// Loads the sprite sheet override if there is one, and falls back to the bundled sheet (after
+/// reporting what's wrong) if it can't be used. The sheet's tags are the animations that states in
+/// the behavior file can use.
+pub fn load_sheet() -> SheetAsset {
+    load_override(
+        SHEET_OVERRIDE_PATH,
+        |path| SheetAsset::load_file(path).and_then(|sheet| check_sheet(sheet, &[])),
+        "the bundled sprite sheet",
+        bundled_sheet,
+    )
+}
+
+/// Loads a sprite sheet for one creature to wear instead of the default one. It has to have every
+/// animation `behavior` uses, since the behavior was checked against the default sheet. Returns
+/// `None` (after reporting what's wrong) if it can't be used.
+pub fn load_skin(path: &Path, behavior: &Behavior) -> Option<SheetAsset> {
+    let sheet = SheetAsset::load_file(path)
+        .and_then(|sheet| check_sheet(sheet, &behavior.animations_used()));
+    match sheet {
+        Ok(sheet) => Some(sheet),
+        Err(e) => {
+            eprintln!("[{}] {}", path.display(), e);
+            eprintln!("using the default sprite sheet for this creature instead");
+            None
+        }
+    }
+}
+
+/// Makes sure `sheet` has the tags the game itself needs, along with `animations`.
+fn check_sheet(sheet: SheetAsset, animations: &[&str]) -> Result<SheetAsset, FileError> {
+    let mut errors = vec![];
+    if !sheet.meta.clips.contains_key(HIDDEN_CLIP) {
+        errors.push(format!(
+            "meta.frameTags: needs a \"{}\" tag for when she's out of sight",
+            HIDDEN_CLIP
+        ));
+    }
+    for animation in animations {
+        if !sheet.meta.clips.contains_key(*animation) {
+            errors.push(format!(
+                "meta.frameTags: needs a \"{}\" tag, the behavior file uses it",
+                animation
+            ));
+        }
+    }
+    FileError::check(SHEET_WHAT, errors)?;
+    Ok(sheet)
+}
+
+/// Something that happened to her that the game keeps count of.
+#[derive(Debug, Clone)]
+pub enum Report {
+    /// She went into hiding (not counting sneaking off to a new spot).
+    Hid,
+    /// She was found `time` after going into hiding, in the program called `app`, after dropping
+    /// `hints` levels of hints.
+    Found {
+        time: Duration,
+        app: Option<String>,
+        hints: u32,
+    },
+    /// Someone clicked near her hiding spot, or on her while she wasn't peeking.
+    Missed,
+}
+
+/// What one creature needs to know about another to get along with her.
+#[derive(Debug, Clone, Copy)]
+pub struct Neighbor {
+    pub pos: Vec2I,
+    /// Window she's hiding in (or sneaking over to) and where in it, relative to the window.
+    pub hiding: Option<(isize, Vec2I)>,
+    /// Whether she's out roaming, and so can be chased.
+    pub roaming: bool,
+}
+
 pub struct Creature {
-    /// Sprite textures:
-    /// [
-    ///     0: idle
-    ///     1: idle+arms_raised
-    ///     2: idle+talk
-    ///     3: idle+talk+arms_raised
-    ///     4: jump
-    ///     5: peek_left
-    ///     6: peek_right
-    ///     7: peek_up
-    ///     8: hidden
-    ///     9: shocked
-    /// ]
-    sprite: RenderableId<Sprite<10>>,
-    /// Text textures:
-    /// [
-    ///     0: hidden
-    ///     1: "click me"
-    ///     2: 0 catch taunt
-    ///     3: 1+ catch taunt
-    /// ]
-    text_sprite: RenderableId<Sprite<4>>,
+    sprite: RenderableId<SpriteSheet>,
+    /// What she's saying, in a speech bubble over (or under) her head.
+    bubble: RenderableId<SpeechBubble>,
+    /// Hearts that float up out of her head when she's petted.
+    emote: RenderableId<Emote>,
+    /// Window she's drawn in, which the glyph cache of `bubble` belongs to.
+    window: RenderWindowId,
+    voice: Voice,
+    mode: Mode,
 
+    behavior: Behavior,
+    /// Name of the current state in `behavior`.
+    state_name: String,
+    /// How many states she's been in (see [`Creature::changes`]).
+    changes: u64,
     state: CreatureState,
+    state_t_begin: SystemTime,
+    /// How long until the current state times out, if it does.
+    state_duration: Option<Duration>,
+    animator: Animator,
 
     last_pos: Vec2I,
-    last_end_pos: Vec2I,
 
     screen_center: Vec2I,
 
     catch_count: u32,
-    message_state: u32,
+    /// The last thing that happened to her that she hasn't talked about yet.
+    last_event: LastEvent,
+    /// When she last said each line of her dialogue, by id.
+    said: HashMap<String, SystemTime>,
+    /// Title of the window she was last found in.
+    found_in: Option<String>,
+    needs: Needs,
+    /// What time she thinks it is.
+    clock: Clock,
+    /// When she yawns, goes to bed and gets up, unless she stays up around the clock.
+    schedule: Option<Schedule>,
+
+    /// How hard she is to find right now.
+    difficulty: Difficulty,
+    /// The difficulty she started out with, before any adapting.
+    base_difficulty: Difficulty,
+    adaptive: Adaptive,
+    /// When she went into hiding after last being found, for timing how long finding her takes.
+    round_t_begin: Option<SystemTime>,
+    /// Most obvious hint she's dropped since going into hiding (see [`HINT_ARROW`] and co).
+    hints_used: u32,
+    /// How far the cursor was from her spot the last time she said warmer or colder, and when.
+    warmth: Option<(f64, SystemTime)>,
+    /// Warmer or colder, whichever she last said since going into hiding. It keeps showing until
+    /// she's found.
+    warmth_line: Option<String>,
+    /// What she said the last time someone missed her, and when. It shows for [`TAUNT_TIME`].
+    taunt: Option<(String, SystemTime)>,
+
+    /// Where the left mouse button went down on her, while it's held.
+    press: Option<Press>,
+    /// How moving the cursor over her has to go to count as petting, unless she can't be petted.
+    petting: Option<PettingSettings>,
+    /// The cursor's strokes over her so far.
+    strokes: Strokes,
+    /// Whether she was just petted and the hearts haven't been sent up yet.
+    hearts_pending: bool,
+    /// Edges she bounces off of when thrown.
+    work_areas: Vec<RectI>,
+    /// Size of the frame she was last drawn with.
+    last_dims: Vec2I,
+    /// The part of her that was showing when she was last drawn.
+    last_bounds: RectI,
+    /// Where the cursor is on screen, as of the last update and the one before it.
+    cursor: Vec2I,
+    cursor_prev: Vec2I,
+    /// When the cursor last moved.
+    cursor_still_since: SystemTime,
+    /// Whether she already got curious about the cursor since it stopped moving.
+    curiosity_spent: bool,
+    /// What she can stand on while roaming, as of `platforms_t`.
+    platforms: Vec<Platform>,
+    platforms_t: SystemTime,
+    /// The other creatures, as of the last update.
+    neighbors: Vec<Neighbor>,
+    /// What happened since the game last asked.
+    reports: Vec<Report>,
+    /// Things she's been told to say and hasn't yet, oldest first.
+    to_say: VecDeque<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Press {
+    /// Absolute cursor position when the button went down.
+    at: Vec2I,
+    /// Her position relative to the cursor.
+    offset: Vec2I,
 }
 
 impl Creature {
-    pub fn new(renderer: &mut Renderer) -> Self {
-        Self {
-            sprite: renderer.add_renderable(Sprite::new([
-                include_imageasset!("../assets/creature/creature_idle.png"),
-                include_imageasset!("../assets/creature/creature_idle_armsraised.png"),
-                include_imageasset!("../assets/creature/creature_idle_talk.png"),
-                include_imageasset!("../assets/creature/creature_idle_talk_armsraised.png"),
-                include_imageasset!("../assets/creature/creature_jump.png"),
-                include_imageasset!("../assets/creature/creature_peek_left.png"),
-                include_imageasset!("../assets/creature/creature_peek_right.png"),
-                include_imageasset!("../assets/creature/creature_peek_up.png"),
-                include_imageasset!("../assets/empty.png"),
-                include_imageasset!("../assets/creature/creature_shocked.png"),
-            ])),
-            text_sprite: renderer.add_renderable(Sprite::new([
-                include_imageasset!("../assets/empty.png"),
-                include_imageasset!("../assets/text/click_me.png"),
-                include_imageasset!("../assets/text/0.png"),
-                include_imageasset!("../assets/text/1.png"),
-            ])),
-            screen_center: renderer.center_pos(),
-            last_pos: renderer.center_pos(),
-            last_end_pos: renderer.center_pos(),
+    /// Creates a creature drawn in `window`, that comes back to `home` when she jumps home.
+    pub fn new(
+        renderer: &mut Renderer,
+        window: RenderWindowId,
+        behavior: Behavior,
+        sheet: &SheetAsset,
+        home: Vec2I,
+        config: &Config,
+        voice: Voice,
+    ) -> Self {
+        let initial = behavior.initial.clone();
+        // textures belong to the context of the window they're drawn in
+        window.make_current(renderer);
+        let mut creature = Self {
+            sprite: renderer.add_renderable(window, SpriteSheet::new(sheet)),
+            bubble: renderer.add_renderable(window, SpeechBubble::new(voice.fonts.clone())),
+            emote: renderer.add_renderable(window, Emote::new()),
+            window,
+            voice,
+            mode: config.mode,
+            behavior,
+            state_name: String::new(),
+            changes: 0,
             state: CreatureState::Idle {
-                pos: Some(renderer.center_pos()),
-                arms_raised: true,
-                try_talk_at: SystemTime::now()
-                    .checked_add(Duration::from_millis(1000))
-                    .unwrap(),
+                pos: home,
+                animation: HIDDEN_CLIP.to_string(),
             },
+            state_t_begin: SystemTime::now(),
+            state_duration: None,
+            animator: Animator::new(sheet.meta.clips.clone(), HIDDEN_CLIP),
+            screen_center: home,
+            last_pos: home,
             catch_count: 0,
-            message_state: 0,
-        }
+            last_event: LastEvent::Start,
+            needs: Needs::default(),
+            clock: config.clock,
+            schedule: config
+                .schedule
+                .map(|times| Schedule::new(times, config.clock.now())),
+            said: HashMap::new(),
+            found_in: None,
+            base_difficulty: config.difficulty.clone(),
+            difficulty: config.difficulty.clone(),
+            adaptive: Adaptive::default(),
+            round_t_begin: None,
+            hints_used: 0,
+            warmth: None,
+            warmth_line: None,
+            taunt: None,
+            press: None,
+            petting: config.petting,
+            strokes: Strokes::default(),
+            hearts_pending: false,
+            work_areas: renderer.work_areas(),
+            last_dims: Vec2I::new(0, 0),
+            last_bounds: RectI {
+                pos: home,
+                dim: Vec2I::new(0, 0),
+            },
+            cursor: Vec2I::new(0, 0),
+            cursor_prev: Vec2I::new(0, 0),
+            cursor_still_since: SystemTime::now(),
+            curiosity_spent: false,
+            platforms: vec![],
+            platforms_t: SystemTime::UNIX_EPOCH,
+            neighbors: vec![],
+            reports: vec![],
+            to_say: VecDeque::new(),
+        };
+        creature.enter(initial);
+        creature
     }
 
+    /// Same as clicking on her while she's out in the open.
     pub fn hide(&mut self) {
-        let top_window = iter_window_candidates().next().unwrap();
-        let (p, f) = find_hiding_spot_in_window(&top_window);
-
-        self.state = CreatureState::Jumping {
-            from: Some(self.last_pos),
-            to: Vec2I {
-                x: self.last_pos.x,
-                y: -100,
-            },
-            t_begin: SystemTime::now(),
-            duration: Duration::from_millis(800),
-            following_state: Box::new(CreatureState::Hiding {
-                target_window: top_window,
-                pos: p,
-                facing: f,
-                peek: false,
-                peek_end_t: SystemTime::now()
-                    .checked_add(Duration::from_millis(
-                        rand::thread_rng().gen_range(5000..=10000),
-                    ))
-                    .unwrap(),
-            }),
-        }
-    }
-    pub fn click(&mut self) {
-        match self.state {
+        self.fire(Event::Click);
+    }
+    /// She was clicked on without being dragged. Clicks on her while she's hiding count when the
+    /// button goes down instead (see [`Creature::press`]), and clicks while she's jumping or
+    /// sneaking around don't do anything unless her state says so.
+    fn click(&mut self) {
+        let asleep = self.asleep();
+        if self.fire(Event::Click) && asleep {
+            self.last_event = LastEvent::Woken;
+        }
+    }
+    /// "Say something" was picked from her right-click menu.
+    pub fn chat(&mut self) {
+        self.fire(Event::Chat);
+    }
+    /// Files were dropped on her and she has `line` to say about them, which she does right away
+    /// if her state goes somewhere when she's fed.
+    pub fn feed(&mut self, line: &str) {
+        self.say(line);
+        self.fire(Event::Fed);
+    }
+    /// Whether her current state does anything when `event` happens.
+    pub fn listens_for(&self, event: Event) -> bool {
+        self.behavior.states[&self.state_name]
+            .on
+            .contains_key(&event)
+    }
+    pub fn asleep(&self) -> bool {
+        matches!(
+            self.behavior.states[&self.state_name].kind,
+            StateKind::Sleep { .. }
+        )
+    }
+    /// The left mouse button went down on her, with the cursor at `cursor` (absolute). That
+    /// catches her if she's peeking, and is a miss if she's already ducking back.
+    pub fn press(&mut self, cursor: Vec2I) {
+        if let CreatureState::Hiding { peek, .. } = self.state {
+            if peek {
+                self.found();
+            } else {
+                self.missed();
+            }
+            return;
+        }
+        self.press = Some(Press {
+            at: cursor,
+            offset: self.last_pos - cursor,
+        });
+    }
+    /// The cursor moved to `cursor` (absolute).
+    pub fn cursor_moved(&mut self, cursor: Vec2I) {
+        let Some(press) = self.press else {
+            self.stroke(cursor);
+            return;
+        };
+        if !matches!(self.state, CreatureState::Held { .. }) {
+            let moved = cursor - press.at;
+            if moved.x.abs().max(moved.y.abs()) < DRAG_THRESHOLD || !self.fire(Event::Grab) {
+                return;
+            }
+        }
+        if let CreatureState::Held { pos, trail } = &mut self.state {
+            *pos = cursor + press.offset;
+            trail.push_back((SystemTime::now(), cursor));
+            while trail.len() > 2
+                && trail
+                    .front()
+                    .is_some_and(|(t, _)| t.elapsed().unwrap_or(Duration::ZERO) > THROW_SAMPLE_TIME)
+            {
+                trail.pop_front();
+            }
+        }
+    }
+    /// The cursor moved to `cursor` (absolute) without a button held. Moving it back and forth over
+    /// her enough pets her, if her state lets her be petted.
+    fn stroke(&mut self, cursor: Vec2I) {
+        let Some(settings) = self.petting else {
+            return;
+        };
+        if !self.last_bounds.contains(cursor) || !self.listens_for(Event::Petted) {
+            self.strokes.reset();
+            return;
+        }
+        if self.strokes.moved(cursor, &settings, SystemTime::now()) && self.fire(Event::Petted) {
+            self.last_event = LastEvent::Petted;
+            self.needs.react(Interaction::Petted);
+            self.hearts_pending = true;
+        }
+    }
+    /// Keeps track of the cursor, wherever it is on screen (absolute).
+    pub fn notice_cursor(&mut self, cursor: Vec2I) {
+        self.cursor_prev = self.cursor;
+        self.cursor = cursor;
+        let moved = cursor - self.cursor_prev;
+        if moved.x.abs().max(moved.y.abs()) > CURSOR_STILL_SLACK {
+            self.cursor_still_since = SystemTime::now();
+            self.curiosity_spent = false;
+        }
+    }
+    /// Whether the cursor is coming at `spot` while close to it.
+    fn cursor_approaching(&self, spot: Vec2I) -> bool {
+        let dist = distance(self.cursor, spot);
+        self.difficulty
+            .flinch_distance
+            .is_some_and(|d| dist < d && dist < distance(self.cursor_prev, spot))
+    }
+    /// Whether the cursor has been sitting still near `spot` for long enough that she wants a look.
+    fn curious_about(&self, spot: Vec2I) -> bool {
+        let still_for = self.cursor_still_since.elapsed().unwrap_or(Duration::ZERO);
+        !self.curiosity_spent
+            && distance(self.cursor, spot) <= self.difficulty.curious_distance
+            && self
+                .difficulty
+                .curious_after
+                .is_some_and(|t| still_for >= t)
+    }
+    /// Which way she'd look to see the cursor, or `None` if she's looking straight at it (or
+    /// doesn't care).
+    fn cursor_facing(&self) -> Option<Facing> {
+        if !self.difficulty.look_at_cursor {
+            return None;
+        }
+        let eyes = self.last_pos - Vec2I::new(0, self.last_dims.y * 7 / 20);
+        let d = self.cursor - eyes;
+        if distance(self.cursor, eyes) < LOOK_DEADZONE {
+            None
+        } else if d.x.abs() > d.y.abs() {
+            Some(if d.x < 0 { Facing::Left } else { Facing::Right })
+        } else {
+            Some(if d.y < 0 { Facing::Up } else { Facing::Down })
+        }
+    }
+
+    /// The left mouse button was let go of.
+    pub fn release(&mut self) {
+        if self.press.take().is_none() {
+            return;
+        }
+        if matches!(self.state, CreatureState::Held { .. }) {
+            if self.fire(Event::Throw) {
+                self.last_event = LastEvent::Thrown;
+                self.needs.react(Interaction::Thrown);
+            }
+        } else {
+            self.click();
+        }
+    }
+    /// The left mouse button went down at `cursor` (absolute), somewhere other than on her. That's
+    /// a miss if it's close enough to where she's hiding.
+    pub fn press_elsewhere(&mut self, cursor: Vec2I) {
+        if let CreatureState::Hiding {
+            target_window, pos, ..
+        } = &self.state
+        {
+            if distance(cursor, target_window.rect().pos + *pos) <= self.difficulty.miss_distance {
+                self.missed();
+            }
+        }
+    }
+    /// Someone tried to catch her and missed. She either sneaks off somewhere else or, if she
+    /// stays put, taunts them.
+    fn missed(&mut self) {
+        self.reports.push(Report::Missed);
+        let mut rng = rand::thread_rng();
+        if rng.gen_bool(self.difficulty.relocate_on_miss.clamp(0.0, 1.0)) && self.fire(Event::Miss)
+        {
+            return;
+        }
+        let taunt = self
+            .voice
+            .messages
+            .get(TAUNTS.choose(&mut rng).unwrap(), &[]);
+        self.taunt = Some((taunt, SystemTime::now()));
+    }
+    fn found(&mut self) {
+        let (app, window_name) = match &self.state {
+            CreatureState::Hiding { target_window, .. } => (
+                target_window.app_name(),
+                Some(target_window.name().to_string()),
+            ),
+            _ => (None, None),
+        };
+        if self.fire(Event::Found) {
+            self.catch_count += 1;
+            self.last_event = LastEvent::Found;
+            self.found_in = window_name;
+            self.needs.react(Interaction::Found);
+            if let Some(t) = self.round_t_begin.take() {
+                let time = t.elapsed().unwrap_or(Duration::ZERO);
+                self.reports.push(Report::Found {
+                    time,
+                    app,
+                    hints: self.hints_used,
+                });
+                if self.base_difficulty.adaptive {
+                    self.adaptive.record(time);
+                    self.difficulty = self.base_difficulty.adapted(self.adaptive.level());
+                }
+            }
+        }
+    }
+
+    /// Goes up whenever what [`Creature::save`] gives might have changed, other than where she is,
+    /// how she feels and when that was. Everything else only changes along with her state.
+    pub fn changes(&self) -> u64 {
+        self.changes
+    }
+    /// What gets remembered about her until the next run.
+    pub fn save(&self) -> CreatureSave {
+        CreatureSave {
+            catch_count: self.catch_count,
+            last_event: Some(self.last_event.name().to_string()),
+            recent_finds: self.adaptive.recent().collect(),
+            state: Some(self.state_name.clone()),
+            pos: Some(self.last_pos),
+            needs: Some(self.needs),
+            last_seen: Some(SystemTime::now()),
+            last_day: Some(self.clock.now().day),
+        }
+    }
+    /// Picks up where she left off last run. She only goes back to states she can start in
+    /// anywhere; otherwise she stays in the initial state. On the first run of the day she always
+    /// starts over, to say hi.
+    pub fn restore(&mut self, save: &CreatureSave) {
+        self.catch_count = save.catch_count;
+        self.needs = save.needs.unwrap_or_default();
+        if let Some(away) = save.last_seen.and_then(|t| t.elapsed().ok()) {
+            self.needs.update_away(away);
+        }
+        self.last_event = save
+            .last_event
+            .as_deref()
+            .and_then(LastEvent::from_name)
+            .unwrap_or(LastEvent::Start);
+        let new_day = save.last_day.is_some_and(|day| day < self.clock.now().day);
+        if new_day {
+            self.last_event = LastEvent::NewDay;
+        }
+        for t in &save.recent_finds {
+            self.adaptive.record(*t);
+        }
+        if self.base_difficulty.adaptive {
+            self.difficulty = self.base_difficulty.adapted(self.adaptive.level());
+        }
+
+        let resumable = save.state.as_ref().filter(|name| {
+            !new_day
+                && self.behavior.states.get(*name).is_some_and(|def| {
+                    matches!(
+                        def.kind,
+                        StateKind::Idle { .. }
+                            | StateKind::Sleep { .. }
+                            | StateKind::Roam { .. }
+                            | StateKind::Hide { .. }
+                    )
+                })
+        });
+        if let Some(name) = resumable.cloned() {
+            // somewhere that's still on screen, that is
+            if let Some(pos) = save
+                .pos
+                .filter(|p| self.work_areas.iter().any(|a| a.contains(*p)))
+            {
+                self.last_pos = pos;
+            }
+            self.enter(name);
+        }
+    }
+
+    /// Everything that happened since this was last called, oldest first.
+    pub fn take_reports(&mut self) -> Vec<Report> {
+        std::mem::take(&mut self.reports)
+    }
+    /// Has her say `text` the next time she talks, instead of her usual lines.
+    pub fn say(&mut self, text: &str) {
+        self.to_say.push_back(text.to_string());
+    }
+    /// Has her announce the achievement called `name` the next time she talks.
+    pub fn announce(&mut self, name: &str) {
+        let line = self.voice.messages.get("achievement", &[("name", &name)]);
+        self.say(&line);
+    }
+
+    /// What the other creatures get to know about her.
+    pub fn as_neighbor(&self) -> Neighbor {
+        let hiding = match &self.state {
             CreatureState::Hiding {
-                peek: true,
-                facing,
-                pos,
-                ..
-            } => self.found(pos, facing),
-            CreatureState::Idle { .. } | CreatureState::Talking { .. } => self.hide(),
-            _ => {}
-        }
-    }
-    fn found(&mut self, pos: Vec2I, facing: Facing) {
-        self.catch_count += 1;
-        self.message_state = 0;
-        self.state = CreatureState::Shocked {
-            from: pos,
-            to: facing,
-            t_begin: SystemTime::now(),
-            following_state: Box::new(CreatureState::Jumping {
-                from: None,
-                to: self.screen_center,
-                t_begin: SystemTime::now().checked_add(SHOCKED_TIME).unwrap(),
-                duration: Duration::from_millis(500),
-                following_state: Box::new(CreatureState::Idle {
-                    pos: None,
-                    arms_raised: false,
-                    try_talk_at: SystemTime::now()
-                        .checked_add(SHOCKED_TIME)
-                        .unwrap()
-                        .checked_add(Duration::from_millis(300))
-                        .unwrap(),
-                }),
-            }),
-        }
-    }
-
-    fn start_talking(&mut self) {
-        let id = match (self.catch_count, self.message_state) {
-            (0, 0) => {
-                self.message_state = 1;
-                2
-            }
-            (0, 1) => 1,
-            (_, 0) => {
-                self.message_state = 1;
-                3
-            }
-            (_, 1) => 1,
-            (_, _) => unreachable!(),
+                target_window, pos, ..
+            }
+            | CreatureState::Sneaking {
+                target_window, pos, ..
+            } => Some((target_window.hwnd(), *pos)),
+            _ => None,
         };
-        self.state = CreatureState::Talking {
+        Neighbor {
             pos: self.last_pos,
-            arms_raised: rand::thread_rng().gen_bool(0.65),
-            t_begin_talking: SystemTime::now(),
-            duration: Duration::from_millis(3000),
-            id,
+            hiding,
+            roaming: matches!(self.state, CreatureState::Roaming { .. }),
+        }
+    }
+    pub fn set_neighbors(&mut self, neighbors: Vec<Neighbor>) {
+        self.neighbors = neighbors;
+    }
+    /// Picks which of the top `take` candidate windows to hide in, going for one a neighbor is
+    /// already hiding in every now and then.
+    fn pick_hiding_window(&self, take: usize, random: bool) -> Option<ExtWindowInfo> {
+        let mut rng = rand::thread_rng();
+        let candidates: Vec<ExtWindowInfo> = iter_window_candidates().take(take).collect();
+        if rng.gen_bool(HIDE_TOGETHER_CHANCE) {
+            let together = candidates
+                .iter()
+                .filter(|w| {
+                    self.neighbors
+                        .iter()
+                        .any(|n| n.hiding.is_some_and(|(hwnd, _)| hwnd == w.hwnd()))
+                })
+                .choose(&mut rng);
+            if let Some(w) = together {
+                return Some(w.clone());
+            }
+        }
+        if random {
+            candidates.into_iter().choose(&mut rng)
+        } else {
+            candidates.into_iter().next()
+        }
+    }
+    /// A hiding spot in `window` that isn't right on top of a neighbor's, if one turns up. Out of
+    /// the first [`Difficulty::spot_picks`] such spots, she goes with the one furthest from the
+    /// cursor.
+    fn free_spot_in(&self, window: &ExtWindowInfo) -> (Vec2I, Facing) {
+        let taken: Vec<Vec2I> = self
+            .neighbors
+            .iter()
+            .filter_map(|n| n.hiding)
+            .filter(|(hwnd, _)| *hwnd == window.hwnd())
+            .map(|(_, pos)| pos)
+            .collect();
+        let picks = self.difficulty.spot_picks.max(1);
+        let mut best: Option<(bool, f64, (Vec2I, Facing))> = None;
+        let mut free_found = 0;
+        let edges = EdgeMap::of(window);
+        for _ in 0..SPOT_TRIES.max(picks) {
+            let spot = edges.hiding_spot();
+            let free = taken
+                .iter()
+                .all(|p| distance(*p, spot.0) >= MIN_SPOT_SPACING);
+            let from_cursor = distance(self.cursor, window.rect().pos + spot.0);
+            if best.is_none_or(|(best_free, best_from_cursor, _)| {
+                (free, from_cursor) > (best_free, best_from_cursor)
+            }) {
+                best = Some((free, from_cursor, spot));
+            }
+            free_found += free as usize;
+            if free_found >= picks {
+                break;
+            }
+        }
+        best.unwrap().2
+    }
+
+    /// Moves on to the next state if the current state has a transition for `event`. Returns
+    /// whether it did.
+    fn fire(&mut self, event: Event) -> bool {
+        let def = &self.behavior.states[&self.state_name];
+        match def.next(event, &self.needs) {
+            Some(next) => {
+                self.enter(next.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+    fn enter(&mut self, name: String) {
+        self.changes += 1;
+        let def = self.behavior.states[&name].clone();
+        let mut duration = def.duration.map(|d| d.sample());
+
+        let state = match &def.kind {
+            StateKind::Idle { animation, .. } => {
+                let animation = animation.pick().clone();
+                self.animator.replay(&animation);
+                CreatureState::Idle {
+                    pos: self.last_pos,
+                    animation,
+                }
+            }
+            StateKind::Sleep { animation } => {
+                let animation = animation.pick().clone();
+                self.animator.replay(&animation);
+                CreatureState::Idle {
+                    pos: self.last_pos,
+                    animation,
+                }
+            }
+            StateKind::Talk { animation } => {
+                self.animator.replay(animation.pick());
+                let mut lines = self.pick_lines();
+                CreatureState::Talking {
+                    pos: self.last_pos,
+                    line: lines.pop_front().unwrap_or_default(),
+                    rest: lines,
+                }
+            }
+            StateKind::Jump { target, animation } => {
+                self.animator.replay(animation.pick());
+                CreatureState::Jumping {
+                    from: self.last_pos,
+                    to: match target {
+                        JumpTarget::Offscreen => Vec2I {
+                            x: self.last_pos.x,
+                            y: -100,
+                        },
+                        JumpTarget::Home => self.screen_center,
+                    },
+                }
+            }
+            StateKind::Hide { first_peek, .. } => {
+                if !matches!(self.state, CreatureState::Sneaking { .. }) {
+                    self.round_t_begin = Some(SystemTime::now());
+                    self.hints_used = 0;
+                    self.warmth = None;
+                    self.warmth_line = None;
+                    self.reports.push(Report::Hid);
+                }
+                let (target_window, pos, facing) = match &self.state {
+                    // arriving at the spot she was sneaking to
+                    CreatureState::Sneaking {
+                        target_window,
+                        pos,
+                        facing,
+                        ..
+                    } => (target_window.clone(), *pos, *facing),
+                    _ => {
+                        let window = self.pick_hiding_window(HIDE_CANDIDATES, false).unwrap();
+                        let (p, f) = self.free_spot_in(&window);
+                        (window, p, f)
+                    }
+                };
+                let first_peek = first_peek
+                    .sample()
+                    .mul_f64(self.difficulty.hidden_time_scale);
+                Self::hiding_state(target_window, pos, facing, first_peek)
+            }
+            StateKind::Sneak {
+                speed,
+                min_duration,
+                candidates,
+                animation_left,
+                animation_right,
+            } => {
+                let (from, current_window) = match &self.state {
+                    CreatureState::Hiding {
+                        target_window, pos, ..
+                    } => (target_window.rect().pos + *pos, Some(target_window.clone())),
+                    _ => (self.last_pos, None),
+                };
+                let new_window = self
+                    .pick_hiding_window(*candidates, true)
+                    .or(current_window)
+                    .unwrap();
+                let (p, f) = self.free_spot_in(&new_window);
+                let to = new_window.rect().pos + p;
+
+                let dist = ((to.x - from.x) as f64).hypot((to.y - from.y) as f64);
+                duration = Some(Duration::from_secs_f64(dist / speed).max(*min_duration));
+                self.animator.replay(if !self.difficulty.visible_sneak {
+                    HIDDEN_CLIP
+                } else if to.x < from.x {
+                    animation_left
+                } else {
+                    animation_right
+                });
+
+                CreatureState::Sneaking {
+                    from,
+                    to,
+                    target_window: new_window,
+                    pos: p,
+                    facing: f,
+                }
+            }
+            StateKind::Shocked { animation } => {
+                self.animator.replay(animation.pick());
+                match &self.state {
+                    CreatureState::Hiding {
+                        target_window,
+                        pos,
+                        facing,
+                        ..
+                    } => CreatureState::Shocked {
+                        from: target_window.rect().pos + *pos,
+                        to: *facing,
+                    },
+                    _ => CreatureState::Shocked {
+                        from: self.last_pos,
+                        to: Facing::Up,
+                    },
+                }
+            }
+            StateKind::Roam { animations, .. } => {
+                self.animator.replay(&animations.fall);
+                CreatureState::Roaming {
+                    pos: (self.last_pos.x as f64, self.last_pos.y as f64),
+                    stride: Stride::Fall { vel: 0.0 },
+                    standing_on: None,
+                }
+            }
+            StateKind::Held { animation } => {
+                self.animator.replay(animation.pick());
+                CreatureState::Held {
+                    pos: self.last_pos,
+                    trail: VecDeque::new(),
+                }
+            }
+            StateKind::Thrown { animation, .. } => {
+                self.animator.replay(animation.pick());
+                let vel = match &self.state {
+                    CreatureState::Held { trail, .. } => throw_velocity(trail),
+                    _ => (0.0, 0.0),
+                };
+                let area = self
+                    .work_areas
+                    .iter()
+                    .find(|a| a.contains(self.last_pos))
+                    .or(self.work_areas.first())
+                    .copied()
+                    .unwrap();
+                CreatureState::Thrown {
+                    pos: (self.last_pos.x as f64, self.last_pos.y as f64),
+                    vel,
+                    area,
+                }
+            }
+        };
+
+        self.state = state;
+        self.state_name = name;
+        self.state_t_begin = SystemTime::now();
+        self.state_duration = duration;
+    }
+    fn hiding_state(
+        target_window: ExtWindowInfo,
+        pos: Vec2I,
+        facing: Facing,
+        first_peek_delay: Duration,
+    ) -> CreatureState {
+        CreatureState::Hiding {
+            camo_color: target_window.average_color_around(pos, CAMO_SAMPLE_RADIUS),
+            target_window,
+            pos,
+            facing,
+            peek: false,
+            peek_end_t: from_now(first_peek_delay),
+            peek_t_begin: SystemTime::now(),
+            unanswered_peeks: 0,
+        }
+    }
+
+    /// What she says next, one speech bubble per entry: whatever she's been told to say, or
+    /// otherwise a line from her dialogue that fits what's going on.
+    fn pick_lines(&mut self) -> VecDeque<String> {
+        if let Some(text) = self.to_say.pop_front() {
+            return VecDeque::from([text]);
+        }
+        let ctx = Context {
+            catch_count: self.catch_count,
+            hour: self.clock.now().hour(),
+            mode: self.mode,
+            last_event: self.last_event,
+            window_name: self.found_in.as_deref(),
+            needs: self.needs,
+            now: SystemTime::now(),
         };
+        self.last_event = LastEvent::Nothing;
+        let Some(line) = self
+            .voice
+            .dialogue
+            .pick(&ctx, &self.said, &mut rand::thread_rng())
+        else {
+            return VecDeque::new();
+        };
+        self.said.insert(line.id.clone(), ctx.now);
+        line.render(&ctx, &self.voice.messages).into()
     }
 
-    pub fn update(&mut self) {
-        match &mut self.state {
+    pub fn update(&mut self, dt: Duration) {
+        let resting = matches!(
+            self.state,
+            CreatureState::Idle { .. }
+                | CreatureState::Talking { .. }
+                | CreatureState::Hiding { .. }
+        );
+        self.needs.update(dt, resting);
+
+        // whatever time of day it is, if she's in a state that cares
+        let now = self.clock.now();
+        if let Some(event) = self.schedule.as_mut().and_then(|s| s.due(now)) {
+            let asleep = self.asleep();
+            if self.fire(event) {
+                if let Some(schedule) = &mut self.schedule {
+                    schedule.handled(now);
+                }
+                match event {
+                    Event::Evening => self.last_event = LastEvent::Yawned,
+                    Event::Morning if asleep => self.last_event = LastEvent::NewDay,
+                    _ => {}
+                }
+                return;
+            }
+        }
+
+        if let Some(AnimationEvent::Finished(_)) = self.animator.update(dt) {
+            if self.fire(Event::AnimationEnd) {
+                return;
+            }
+        }
+        if self.fly(dt) {
+            self.fire(Event::Land);
+            return;
+        }
+        self.roam(dt);
+
+        if let (
+            CreatureState::Idle { animation, .. },
+            StateKind::Idle {
+                look: Some(look), ..
+            },
+        ) = (&self.state, &self.behavior.states[&self.state_name].kind)
+        {
+            self.animator.play(match self.cursor_facing() {
+                Some(facing) => look.get(facing),
+                None => animation,
+            });
+        }
+
+        let elapsed = self.state_t_begin.elapsed().unwrap_or(Duration::ZERO);
+        if self
+            .state_duration
+            .is_some_and(|duration| elapsed > duration)
+        {
+            // the next speech bubble of what she's saying, if there's more
+            if let CreatureState::Talking { line, rest, .. } = &mut self.state {
+                if let Some(next) = rest.pop_front() {
+                    *line = next;
+                    self.state_t_begin = SystemTime::now();
+                    return;
+                }
+            }
+            if !self.fire(Event::Timeout) {
+                self.state_duration = None;
+            }
+            return;
+        }
+
+        let spot = match &self.state {
             CreatureState::Hiding {
-                peek, peek_end_t, ..
-            } => {
+                target_window, pos, ..
+            } => Some(target_window.rect().pos + *pos),
+            _ => None,
+        };
+        let flinch = spot.is_some_and(|spot| self.cursor_approaching(spot));
+        let curious = spot.is_some_and(|spot| self.curious_about(spot));
+        let hint = self.hint_level();
+        self.hints_used = self.hints_used.max(hint);
+        if let Some(spot) = spot.filter(|_| hint >= HINT_WARMTH) {
+            self.say_warmth(spot);
+        }
+        let (peek_scale, hidden_scale) = (
+            self.difficulty.peek_time_scale
+                * if hint >= HINT_LONGER_PEEKS {
+                    HINT_PEEK_SCALE
+                } else {
+                    1.0
+                },
+            self.difficulty.hidden_time_scale,
+        );
+        let mut peeked = false;
+        if let CreatureState::Hiding {
+            peek,
+            peek_end_t,
+            peek_t_begin,
+            unanswered_peeks,
+            ..
+        } = &mut self.state
+        {
+            if let StateKind::Hide {
+                peek_duration,
+                hidden_duration,
+                ..
+            } = &self.behavior.states[&self.state_name].kind
+            {
                 if SystemTime::now() > *peek_end_t {
+                    if *peek {
+                        *unanswered_peeks += 1;
+                    }
                     *peek = !*peek;
-                    *peek_end_t = SystemTime::now()
-                        .checked_add(Duration::from_millis(if *peek {
-                            rand::thread_rng().gen_range(250..=750)
-                        } else {
-                            rand::thread_rng().gen_range(2000..=10000)
-                            // rand::thread_rng().gen_range(50..=150)
-                        }))
-                        .unwrap();
+                    peeked = *peek;
+                    *peek_t_begin = SystemTime::now();
+                    *peek_end_t = from_now(if *peek {
+                        peek_duration.sample().mul_f64(peek_scale)
+                    } else {
+                        hidden_duration.sample().mul_f64(hidden_scale)
+                    });
                 }
-            }
-            CreatureState::Idle { try_talk_at, .. } => {
-                if SystemTime::now() > *try_talk_at {
-                    self.start_talking();
+                if *peek && flinch {
+                    // ducking back from the cursor isn't an unanswered peek, she was noticed
+                    *peek = false;
+                    *peek_t_begin = SystemTime::now();
+                    *peek_end_t = from_now(hidden_duration.sample().mul_f64(hidden_scale));
+                } else if !*peek && curious {
+                    *peek = true;
+                    peeked = true;
+                    *peek_t_begin = SystemTime::now();
+                    *peek_end_t = from_now(peek_duration.sample().mul_f64(peek_scale));
+                    self.curiosity_spent = true;
                 }
             }
-            CreatureState::Talking {
-                t_begin_talking,
-                duration,
+            if !*peek
+                && (*unanswered_peeks >= self.difficulty.relocate_after_peeks
+                    || elapsed > self.difficulty.relocate_after)
+            {
+                self.fire(Event::Relocate);
+            }
+        }
+        if peeked && hint >= HINT_SOUND {
+            beep();
+        }
+    }
+
+    /// How obvious she's being about where she is, from 0 (not at all) up to [`HINT_ARROW`],
+    /// going up the longer she stays hidden.
+    fn hint_level(&self) -> u32 {
+        if !matches!(
+            self.state,
+            CreatureState::Hiding { .. } | CreatureState::Sneaking { .. }
+        ) {
+            return 0;
+        }
+        let (Some(after), Some(t)) = (self.difficulty.hint_after, self.round_t_begin) else {
+            return 0;
+        };
+        let hidden_for = t.elapsed().unwrap_or(Duration::ZERO);
+        if hidden_for < after {
+            return 0;
+        }
+        let steps =
+            (hidden_for - after).as_secs_f64() / self.difficulty.hint_step.as_secs_f64().max(0.001);
+        (1 + steps as u32).min(HINT_ARROW)
+    }
+    /// Tells the cursor whether it's getting closer to `spot`, every [`WARMTH_INTERVAL`].
+    fn say_warmth(&mut self, spot: Vec2I) {
+        let dist = distance(self.cursor, spot);
+        let Some((last_dist, t)) = self.warmth else {
+            self.warmth = Some((dist, SystemTime::now()));
+            return;
+        };
+        if t.elapsed().unwrap_or(Duration::ZERO) < WARMTH_INTERVAL {
+            return;
+        }
+        if dist < last_dist - WARMTH_SLACK {
+            self.warmth_line = Some(self.voice.messages.get("warmer", &[]));
+        } else if dist > last_dist + WARMTH_SLACK {
+            self.warmth_line = Some(self.voice.messages.get("colder", &[]));
+        } else {
+            // keep whatever she said last showing
+            self.warmth = Some((last_dist, SystemTime::now()));
+            return;
+        }
+        self.warmth = Some((dist, SystemTime::now()));
+    }
+    /// Her taunt after the last miss, if she's still hiding and saying it.
+    fn saying_taunt(&self) -> Option<&str> {
+        let (taunt, t) = self.taunt.as_ref()?;
+        (matches!(self.state, CreatureState::Hiding { .. })
+            && t.elapsed().unwrap_or(Duration::ZERO) < TAUNT_TIME)
+            .then_some(taunt.as_str())
+    }
+    /// Warmer or colder, if she's telling the cursor which right now.
+    fn saying_warmth(&self) -> Option<&str> {
+        self.warmth_line
+            .as_deref()
+            .filter(|_| self.hint_level() >= HINT_WARMTH)
+    }
+    /// Where her hiding spot is, once she's hidden long enough to point it out with an arrow.
+    pub fn hint_arrow(&self) -> Option<Vec2I> {
+        match &self.state {
+            CreatureState::Hiding {
+                target_window, pos, ..
+            } if self.hint_level() >= HINT_ARROW => Some(target_window.rect().pos + *pos),
+            _ => None,
+        }
+    }
+    /// Moves her along while she's been thrown. Returns whether she has come to rest.
+    fn fly(&mut self, dt: Duration) -> bool {
+        let (gravity, bounce, friction) = match &self.behavior.states[&self.state_name].kind {
+            StateKind::Thrown {
+                gravity,
+                bounce,
+                friction,
                 ..
-            } => {
-                if t_begin_talking.elapsed().unwrap_or(Duration::ZERO) > *duration {
-                    self.state = CreatureState::Idle {
-                        pos: None,
-                        arms_raised: false,
-                        try_talk_at: SystemTime::now()
-                            .checked_add(Duration::from_millis(
-                                rand::thread_rng().gen_range(3000..6000),
-                            ))
-                            .unwrap(),
+            } => (*gravity, *bounce, *friction),
+            _ => return false,
+        };
+        let CreatureState::Thrown { pos, vel, area } = &mut self.state else {
+            return false;
+        };
+        let dt = dt.as_secs_f64();
+        let work_areas = &self.work_areas;
+        // Edges that lead onto another monitor don't count as walls.
+        let open = |x: f64, y: f64| {
+            let p = Vec2I::new(x as i32, y as i32);
+            work_areas.iter().any(|a| a.contains(p))
+        };
+
+        vel.1 += gravity * dt;
+        pos.0 += vel.0 * dt;
+        pos.1 += vel.1 * dt;
+        if let Some(now_in) = work_areas
+            .iter()
+            .find(|a| a.contains(Vec2I::new(pos.0 as i32, pos.1 as i32)))
+        {
+            *area = *now_in;
+        }
+
+        let half = (self.last_dims.x as f64 / 2.0, self.last_dims.y as f64 / 2.0);
+        let (left, top) = (area.pos.x as f64, area.pos.y as f64);
+        let (right, bottom) = (left + area.dim.x as f64, top + area.dim.y as f64);
+        if pos.0 - half.0 < left && !open(left - 1.0, pos.1) {
+            pos.0 = left + half.0;
+            vel.0 = vel.0.abs() * bounce;
+        } else if pos.0 + half.0 > right && !open(right, pos.1) {
+            pos.0 = right - half.0;
+            vel.0 = -vel.0.abs() * bounce;
+        }
+        if pos.1 - half.1 < top && !open(pos.0, top - 1.0) {
+            pos.1 = top + half.1;
+            vel.1 = vel.1.abs() * bounce;
+        }
+        let on_floor = pos.1 + half.1 >= bottom && !open(pos.0, bottom);
+        if on_floor {
+            pos.1 = bottom - half.1;
+            vel.1 = -vel.1.abs() * bounce;
+            if vel.1.abs() < REST_SPEED {
+                vel.1 = 0.0;
+            }
+            vel.0 = vel.0.signum() * (vel.0.abs() - friction * dt).max(0.0);
+        }
+        on_floor && vel.1 == 0.0 && vel.0.abs() < REST_SPEED
+    }
+
+    /// Moves her along while she's roaming.
+    fn roam(&mut self, dt: Duration) {
+        let StateKind::Roam {
+            walk_speed,
+            pause,
+            jump_chance,
+            jump_reach,
+            jump_time,
+            gravity,
+            animations,
+        } = &self.behavior.states[&self.state_name].kind
+        else {
+            return;
+        };
+        let facing = self.cursor_facing();
+        let curious_x = self.curious_about(self.last_pos).then_some(self.cursor.x);
+        let CreatureState::Roaming {
+            pos,
+            stride,
+            standing_on,
+        } = &mut self.state
+        else {
+            return;
+        };
+        let now = SystemTime::now();
+        if now
+            .duration_since(self.platforms_t)
+            .unwrap_or(Duration::ZERO)
+            > PLATFORM_REFRESH
+        {
+            self.platforms = find_platforms(&self.work_areas, self.last_dims.y);
+            self.platforms_t = now;
+            if let Some(on) = *standing_on {
+                *standing_on = self
+                    .platforms
+                    .iter()
+                    .find(|p| p.hwnd == on.hwnd && p.y == on.y && p.holds(pos.0 as i32))
+                    .copied();
+                // the window under her moved or closed
+                if standing_on.is_none() {
+                    *stride = Stride::Fall { vel: 0.0 };
+                }
+            }
+        }
+
+        let feet = self.last_dims.y as f64 / 2.0;
+        let margin = self.last_dims.x / 4;
+        let mut rng = rand::thread_rng();
+        // somewhere on `p` she can comfortably stand, as close to `x` as `reach` allows
+        let spot_on = |p: &Platform, x: f64, reach: f64, rng: &mut rand::rngs::ThreadRng| {
+            let (lo, hi) = if p.x1 - p.x0 > 2 * margin {
+                (p.x0 + margin, p.x1 - margin)
+            } else {
+                ((p.x0 + p.x1) / 2, (p.x0 + p.x1) / 2)
+            };
+            let lo = lo.max((x - reach) as i32).min(hi);
+            let hi = hi.min((x + reach) as i32).max(lo);
+            rng.gen_range(lo..=hi) as f64
+        };
+        let dt = dt.as_secs_f64();
+        match stride {
+            Stride::Fall { vel } => {
+                let old_feet = pos.1 + feet;
+                *vel += gravity * dt;
+                pos.1 += *vel * dt;
+                let new_feet = pos.1 + feet;
+                let x = pos.0 as i32;
+                let landing = self
+                    .platforms
+                    .iter()
+                    .filter(|p| p.holds(x) && (old_feet..=new_feet).contains(&(p.y as f64)))
+                    .min_by_key(|p| p.y)
+                    .or_else(|| {
+                        // nothing left below her, so she's off the edge of every monitor
+                        let anything_below = self
+                            .platforms
+                            .iter()
+                            .any(|p| p.holds(x) && p.y as f64 >= old_feet);
+                        if anything_below {
+                            None
+                        } else {
+                            self.platforms
+                                .iter()
+                                .filter(|p| p.hwnd.is_none())
+                                .min_by_key(|p| p.distance_to(x))
+                        }
+                    });
+                if let Some(p) = landing {
+                    pos.0 = pos.0.clamp(p.x0 as f64, (p.x1 - 1) as f64);
+                    pos.1 = p.y as f64 - feet;
+                    *standing_on = Some(*p);
+                    *stride = Stride::Pause {
+                        until: now + pause.sample(),
                     };
-                    self.last_end_pos = self.last_pos;
                 }
             }
-            CreatureState::Jumping {
-                t_begin,
-                duration,
-                following_state,
-                ..
-            } => {
-                if t_begin.elapsed().unwrap_or(Duration::ZERO) > *duration {
-                    self.state = (&**following_state).clone();
-                    self.last_end_pos = self.last_pos;
+            Stride::Pause { until } if now < *until && curious_x.is_none() => {}
+            Stride::Pause { .. } => {
+                if let (Some(on), Some(x)) = (*standing_on, curious_x) {
+                    // go over for a closer look
+                    *stride = Stride::Walk {
+                        to_x: spot_on(&on, x as f64, 0.0, &mut rng),
+                    };
+                    self.curiosity_spent = true;
+                } else if let Some(on) = *standing_on {
+                    let x = pos.0 as i32;
+                    let reachable: Vec<&Platform> = self
+                        .platforms
+                        .iter()
+                        .filter(|p| {
+                            **p != on
+                                && p.distance_to(x) as f64 <= *jump_reach
+                                && ((p.y - on.y) as f64).abs() <= *jump_reach
+                        })
+                        .collect();
+                    // every now and then she goes after another creature that's out roaming
+                    let chased = if rng.gen_bool(CHASE_CHANCE) {
+                        self.neighbors.iter().filter(|n| n.roaming).choose(&mut rng)
+                    } else {
+                        None
+                    };
+                    let (goal, goal_x, reach) = if let Some(n) = chased {
+                        let their_feet = n.pos.y as f64 + feet;
+                        let goal = reachable
+                            .iter()
+                            .copied()
+                            .chain([&on])
+                            .min_by_key(|p| {
+                                p.distance_to(n.pos.x) + (p.y as f64 - their_feet).abs() as i32
+                            })
+                            .copied();
+                        // stop next to her rather than on top of her
+                        let short =
+                            (self.last_dims.x / 2) as f64 * (n.pos.x as f64 - pos.0).signum();
+                        (goal, n.pos.x as f64 - short, 0.0)
+                    } else if rng.gen_bool(*jump_chance) {
+                        (
+                            reachable.choose(&mut rng).copied().copied(),
+                            pos.0,
+                            *jump_reach,
+                        )
+                    } else {
+                        (None, pos.0, f64::INFINITY)
+                    };
+                    *stride = match goal {
+                        Some(p) if p != on => Stride::Jump {
+                            from: Vec2I::new(x, pos.1 as i32),
+                            to: Vec2I::new(
+                                spot_on(
+                                    &p,
+                                    goal_x.clamp(pos.0 - *jump_reach, pos.0 + *jump_reach),
+                                    reach,
+                                    &mut rng,
+                                ) as i32,
+                                (p.y as f64 - feet) as i32,
+                            ),
+                            t_begin: now,
+                            onto: p,
+                        },
+                        _ => Stride::Walk {
+                            to_x: spot_on(&on, goal_x, reach, &mut rng),
+                        },
+                    };
+                } else {
+                    *stride = Stride::Fall { vel: 0.0 };
+                }
+            }
+            Stride::Walk { to_x } => {
+                let step = walk_speed * dt;
+                if (*to_x - pos.0).abs() <= step {
+                    pos.0 = *to_x;
+                    *stride = Stride::Pause {
+                        until: now + pause.sample(),
+                    };
+                } else {
+                    pos.0 += step * (*to_x - pos.0).signum();
                 }
             }
-            CreatureState::Shocked {
+            Stride::Jump {
+                from,
+                to,
                 t_begin,
-                following_state,
-                ..
+                onto,
             } => {
-                if t_begin.elapsed().unwrap_or(Duration::ZERO) > SHOCKED_TIME {
-                    self.state = (&**following_state).clone();
-                    self.last_end_pos = self.last_pos;
+                let u = now
+                    .duration_since(*t_begin)
+                    .unwrap_or(Duration::ZERO)
+                    .as_secs_f64()
+                    / jump_time.as_secs_f64().max(0.001);
+                let at = jump_arc(*from, *to, u.min(1.0), ROAM_JUMPPOWER);
+                *pos = (at.x as f64, at.y as f64);
+                if u >= 1.0 {
+                    let onto = *onto;
+                    // the platform might have gone away mid-jump
+                    *standing_on = self
+                        .platforms
+                        .iter()
+                        .find(|p| p.hwnd == onto.hwnd && p.y == onto.y && p.holds(at.x))
+                        .copied();
+                    *stride = match standing_on {
+                        Some(_) => Stride::Pause {
+                            until: now + pause.sample(),
+                        },
+                        None => Stride::Fall { vel: 0.0 },
+                    };
                 }
             }
         }
+
+        self.animator.play(match stride {
+            Stride::Fall { .. } => &animations.fall,
+            Stride::Pause { .. } => match (&animations.look, facing) {
+                (Some(look), Some(facing)) => look.get(facing),
+                _ => &animations.stand,
+            },
+            Stride::Walk { to_x } if *to_x < pos.0 => &animations.walk_left,
+            Stride::Walk { .. } => &animations.walk_right,
+            Stride::Jump { .. } => &animations.jump,
+        });
     }
+
     pub fn update_for_render(&mut self, renderer: &mut Renderer) -> RectI {
-        {
-            let text_sprite = self.text_sprite.get_mut(renderer).unwrap();
-            text_sprite.pos.1 = RelativeTo::Absolute;
-            text_sprite.pos.2 = Anchor::BottomCenter;
-            text_sprite.set_current_tex_index(0);
+        let line = match &self.state {
+            CreatureState::Talking { line, .. } => line.clone(),
+            _ => self
+                .saying_taunt()
+                .or(self.saying_warmth())
+                .unwrap_or_default()
+                .to_string(),
+        };
+        if self.bubble.get(renderer).unwrap().text() != line {
+            // new glyphs go into the glyph cache, which belongs to her window
+            self.window.make_current(renderer);
+            self.bubble.get_mut(renderer).unwrap().set_text(&line);
         }
         {
             let sprite = self.sprite.get_mut(renderer).unwrap();
             sprite.pos.1 = RelativeTo::Absolute;
-            sprite.pos.2 = Anchor::Center;
+            sprite.clip = None;
+            sprite.occluders.clear();
+            sprite.tint = [0.0; 4];
         }
 
+        let elapsed = self.state_t_begin.elapsed().unwrap_or(Duration::ZERO);
+        // how far through the state she is, for states with a set duration
+        let progress = self.state_duration.map_or(1.0, |duration| {
+            (elapsed.as_secs_f64() / duration.as_secs_f64().max(0.001)).min(1.0)
+        });
+        let def = &self.behavior.states[&self.state_name];
+        let hint = self.hint_level();
+
         let sprite = self.sprite.get_mut(renderer).unwrap();
+        sprite.set_current_frame(self.animator.current_frame());
         match &self.state {
             CreatureState::Hiding {
                 target_window,
                 pos,
                 facing,
                 peek,
+                peek_t_begin,
+                camo_color: [r, g, b],
                 ..
             } => {
-                sprite.pos.0 = target_window.rect().pos + *pos;
-                sprite.set_current_tex_index(if *peek {
-                    match facing {
-                        Facing::Left => 5,  // peek left
-                        Facing::Right => 6, // peek right
-                        _ => 7,             // peek up
-                    }
+                let wiggle = if hint >= HINT_WIGGLE {
+                    ((elapsed.as_secs_f64() * WIGGLE_RATE * 2.0 * PI).sin() * WIGGLE_AMOUNT) as i32
                 } else {
-                    8 // hidden
-                })
-            }
-            CreatureState::Idle {
-                pos, arms_raised, ..
-            } => {
-                if let Some(pos) = *pos {
-                    sprite.pos.0 = pos;
+                    0
+                };
+                // along the edge, so she stays just as far out from behind it
+                let edge = target_window.rect().pos
+                    + *pos
+                    + match facing {
+                        Facing::Left | Facing::Right => Vec2I::new(0, wiggle),
+                        _ => Vec2I::new(wiggle, 0),
+                    };
+                sprite.occluders = target_window.rects_above();
+                sprite.tint = [*r, *g, *b, self.difficulty.camouflage];
+                let slide = (peek_t_begin
+                    .elapsed()
+                    .unwrap_or(Duration::ZERO)
+                    .as_secs_f64()
+                    / self.difficulty.peek_slide_time.as_secs_f64().max(0.001))
+                .min(1.0);
+                let reveal = self.difficulty.peek_reveal * if *peek { slide } else { 1.0 - slide };
+
+                if reveal <= 0.0 {
+                    sprite.pos.0 = edge;
+                    self.animator.play(HIDDEN_CLIP);
+                    sprite.set_current_frame(self.animator.current_frame());
+                } else {
+                    if let StateKind::Hide {
+                        peek_animations, ..
+                    } = &def.kind
+                    {
+                        self.animator.play(peek_animations.get(*facing));
+                    }
+                    sprite.set_current_frame(self.animator.current_frame());
+                    // The peek sprites are drawn with the edge running through their pivot, so
+                    // sliding by the part of the sprite on the open side of it hides her
+                    // completely behind it.
+                    let dim = sprite.current_dims();
+                    let pivot = sprite.current_pivot();
+                    let hidden = 1.0 - reveal;
+                    let (offset, clip) = match facing {
+                        Facing::Left => (
+                            Vec2I::new((hidden * pivot.x as f64) as i32, 0),
+                            RectI {
+                                pos: edge - pivot,
+                                dim: Vec2I::new(pivot.x, dim.y),
+                            },
+                        ),
+                        Facing::Right => (
+                            Vec2I::new(-(hidden * (dim.x - pivot.x) as f64) as i32, 0),
+                            RectI {
+                                pos: Vec2I::new(edge.x, edge.y - pivot.y),
+                                dim: Vec2I::new(dim.x - pivot.x, dim.y),
+                            },
+                        ),
+                        _ => (
+                            Vec2I::new(0, (hidden * pivot.y as f64) as i32),
+                            RectI {
+                                pos: edge - pivot,
+                                dim: Vec2I::new(dim.x, pivot.y),
+                            },
+                        ),
+                    };
+                    sprite.pos.0 = edge + offset;
+                    sprite.clip = Some(clip);
                 }
-                sprite.set_current_tex_index(match arms_raised {
-                    false => 0,
-                    true => 1,
-                });
             }
-            CreatureState::Talking {
-                pos,
-                arms_raised,
-                t_begin_talking,
-                id,
-                ..
-            } => {
+            CreatureState::Idle { pos, .. } => {
                 sprite.pos.0 = *pos;
-                let mouth_open = t_begin_talking.elapsed().unwrap().as_millis() % 600 > 300;
-                sprite.set_current_tex_index(match (arms_raised, mouth_open) {
-                    (false, false) => 0,
-                    (true, false) => 1,
-                    (false, true) => 2,
-                    (true, true) => 3,
-                });
-
-                let text_sprite = self.text_sprite.get_mut(renderer).unwrap();
-                text_sprite.set_current_tex_index(*id);
             }
-            CreatureState::Jumping {
-                from,
-                to,
-                t_begin,
-                duration,
-                ..
-            } => {
-                let from = from.unwrap_or(self.last_end_pos);
-                let u = (t_begin.elapsed().unwrap_or(Duration::ZERO).as_secs_f64()
-                    / duration.as_secs_f64())
-                .min(1.0);
-                let v = 1.0 - u;
-                const JUMPPOWER: f64 = 200.0;
-
-                let p0 = (from.x as f64, from.y as f64);
-                let p3 = (to.x as f64, to.y as f64);
-                let p1 = (p0.0, p0.1 - JUMPPOWER * 3.0);
-                let p2 = (p3.0, p3.1 - JUMPPOWER * 3.0);
-
-                let x = (p0.0 * (v * v * v)
-                    + 3.0 * p1.0 * (u * v * v)
-                    + 3.0 * p2.0 * (u * u * v)
-                    + p3.0 * (u * u * u)) as i32;
-                let y = (p0.1 * (v * v * v)
-                    + 3.0 * p1.1 * (u * v * v)
-                    + 3.0 * p2.1 * (u * u * v)
-                    + p3.1 * (u * u * u)) as i32;
+            CreatureState::Talking { pos, .. } => {
+                sprite.pos.0 = *pos;
+            }
+            CreatureState::Jumping { from, to } => {
+                sprite.pos.0 = jump_arc(*from, *to, progress, JUMPPOWER);
+            }
+            CreatureState::Sneaking { from, to, .. } => {
+                let u = progress;
+                const HOP_HEIGHT: f64 = 12.0;
+                const HOP_TIME: f64 = 0.15;
+                let hop = (elapsed.as_secs_f64() * PI / HOP_TIME).sin().abs();
 
                 sprite.pos.0 = Vec2I {
-                    x: x as i32,
-                    y: y as i32,
+                    x: (from.x as f64 * (1.0 - u) + to.x as f64 * u) as i32,
+                    y: (from.y as f64 * (1.0 - u) + to.y as f64 * u - hop * HOP_HEIGHT) as i32,
                 };
-                sprite.set_current_tex_index(4);
             }
-            CreatureState::Shocked {
-                from, to, t_begin, ..
-            } => {
-                let t = (t_begin.elapsed().unwrap_or(Duration::ZERO).as_secs_f64()
-                    / SHOCKED_TIME.as_secs_f64())
-                .min(1.0);
+            CreatureState::Roaming { pos, .. } => {
+                sprite.pos.0 = Vec2I::new(pos.0 as i32, pos.1 as i32);
+            }
+            CreatureState::Held { pos, .. } => {
+                sprite.pos.0 = *pos;
+            }
+            CreatureState::Thrown { pos, .. } => {
+                sprite.pos.0 = Vec2I::new(pos.0 as i32, pos.1 as i32);
+            }
+            CreatureState::Shocked { from, to } => {
+                let t = progress;
                 let l = (if to.is_horizontal() {
                     sprite.current_dims().x
                 } else {
@@ -354,7 +1529,6 @@ impl Creature {
                     x: ((tx * l * dx) + (0.5 * l * dx)) as i32,
                     y: -((ty * l) + (0.5 * tx * l * dy) + 0.5 * l) as i32,
                 } + *from;
-                sprite.set_current_tex_index(9);
             }
         }
 
@@ -363,29 +1537,149 @@ impl Creature {
             let pos = sprite.pos.0;
             self.last_pos = pos;
             let dim = sprite.current_dims();
+            self.last_dims = dim;
+            let bounds = sprite.current_bounds();
+            let clip = sprite.clip;
             drop(sprite);
-            let text_sprite = self.text_sprite.get_mut(renderer).unwrap();
-            text_sprite.pos.0 = self.last_pos
-                + Vec2I {
-                    x: 0,
-                    y: -dim.y * 12 / 20,
-                };
-            let text_dim = text_sprite.current_dims();
-            let text_visible = text_dim.x > 10;
-            let mut rect = RectI {
-                pos: (pos - dim / 2),
-                dim,
-            };
-            if text_visible {
-                rect.extend_lr((text_dim.x - dim.x) / 2 + 5);
-                rect.extend_up(dim.y / 10 + text_dim.y + 5);
+            // her screen, so the bubble doesn't go off the edge of it
+            let area = self
+                .work_areas
+                .iter()
+                .find(|area| area.contains(pos))
+                .or(self.work_areas.first())
+                .copied();
+            let bubble = self.bubble.get_mut(renderer).unwrap();
+            // Keep the window to the revealed part when she's hiding, so it doesn't block clicks
+            // on whatever she's hiding behind.
+            let visible = clip.unwrap_or(bounds);
+            self.last_bounds = visible;
+            bubble.place(visible, area, RelativeTo::Absolute);
+            let bubble_bounds = bubble.current_bounds();
+            let emote = self.emote.get_mut(renderer).unwrap();
+            if std::mem::take(&mut self.hearts_pending) {
+                emote.start();
             }
-            rect
+            emote.place(visible, RelativeTo::Absolute);
+            [bubble_bounds, emote.current_bounds()]
+                .into_iter()
+                .flatten()
+                .fold(visible, RectI::union)
         }
     }
 }
 
-const SHOCKED_TIME: Duration = Duration::from_millis(500);
+/// How many of the topmost windows she considers when she has to hide on the spot.
+const HIDE_CANDIDATES: usize = 3;
+/// Chance that she hides in a window another creature is already hiding in, if there is one.
+const HIDE_TOGETHER_CHANCE: f64 = 0.5;
+/// How close her hiding spot can be to another creature's, in pixels.
+const MIN_SPOT_SPACING: f64 = 80.0;
+/// How many spots she looks at before settling for one that's too close to another creature's.
+const SPOT_TRIES: usize = 5;
+/// Chance that she goes after another roaming creature when deciding where to go next.
+const CHASE_CHANCE: f64 = 0.3;
+
+/// Hint levels, each one added on top of the ones before it once she's been hidden long enough.
+/// First her peeks get longer,
+const HINT_LONGER_PEEKS: u32 = 1;
+/// then she wiggles while peeking,
+const HINT_WIGGLE: u32 = 2;
+/// then she makes a sound every time she peeks,
+const HINT_SOUND: u32 = 3;
+/// then she says whether the cursor is getting warmer or colder,
+const HINT_WARMTH: u32 = 4;
+/// and finally an arrow at the edge of the screen points her out.
+const HINT_ARROW: u32 = 5;
+/// How much longer her peeks get as a hint.
+const HINT_PEEK_SCALE: f64 = 2.0;
+/// How far she moves back and forth when wiggling, in pixels.
+const WIGGLE_AMOUNT: f64 = 3.0;
+/// How many times a second she wiggles back and forth.
+const WIGGLE_RATE: f64 = 6.0;
+/// Messages she picks from to taunt whoever just missed her.
+const TAUNTS: [&str; 3] = ["taunt_nope", "taunt_close", "taunt_missed"];
+/// How long a taunt stays up.
+const TAUNT_TIME: Duration = Duration::from_millis(1500);
+/// How often she says whether the cursor is getting warmer or colder.
+const WARMTH_INTERVAL: Duration = Duration::from_millis(1500);
+/// How much closer or further the cursor has to get to count as warmer or colder, in pixels.
+const WARMTH_SLACK: f64 = 30.0;
+
+/// Half the size of the area around her hiding spot that camouflage takes its color from.
+const CAMO_SAMPLE_RADIUS: i32 = 40;
+/// How far the cursor has to move while pressed on her before she gets picked up, in pixels.
+const DRAG_THRESHOLD: i32 = 6;
+/// How far back cursor movement counts towards how fast she gets thrown.
+const THROW_SAMPLE_TIME: Duration = Duration::from_millis(80);
+/// Fastest she can be thrown, in pixels per second.
+const MAX_THROW_SPEED: f64 = 5000.0;
+/// Below this speed (in pixels per second) she stops bouncing and sliding.
+const REST_SPEED: f64 = 40.0;
+
+/// How far the cursor can move between updates and still count as sitting still, in pixels.
+const CURSOR_STILL_SLACK: i32 = 2;
+/// She looks straight ahead while the cursor is within this many pixels of her eyes.
+const LOOK_DEADZONE: f64 = 40.0;
+
+fn distance(a: Vec2I, b: Vec2I) -> f64 {
+    ((a.x - b.x) as f64).hypot((a.y - b.y) as f64)
+}
+
+/// `duration` from now, or just now if that's further than the clock goes.
+fn from_now(duration: Duration) -> SystemTime {
+    let now = SystemTime::now();
+    now.checked_add(duration).unwrap_or(now)
+}
+
+/// How high the jump arcs bend, roughly half of how high she goes above the higher end.
+const JUMPPOWER: f64 = 200.0;
+/// Same as [`JUMPPOWER`] for the little jumps between platforms while roaming.
+const ROAM_JUMPPOWER: f64 = 40.0;
+/// How often the platforms she can roam on get looked for again.
+const PLATFORM_REFRESH: Duration = Duration::from_millis(250);
+
+/// Where she is `u` (from 0 to 1) of the way through a jump from `from` to `to`.
+fn jump_arc(from: Vec2I, to: Vec2I, u: f64, power: f64) -> Vec2I {
+    let v = 1.0 - u;
+
+    let p0 = (from.x as f64, from.y as f64);
+    let p3 = (to.x as f64, to.y as f64);
+    let p1 = (p0.0, p0.1 - power * 3.0);
+    let p2 = (p3.0, p3.1 - power * 3.0);
+
+    let x = (p0.0 * (v * v * v)
+        + 3.0 * p1.0 * (u * v * v)
+        + 3.0 * p2.0 * (u * u * v)
+        + p3.0 * (u * u * u)) as i32;
+    let y = (p0.1 * (v * v * v)
+        + 3.0 * p1.1 * (u * v * v)
+        + 3.0 * p2.1 * (u * u * v)
+        + p3.1 * (u * u * u)) as i32;
+
+    Vec2I { x, y }
+}
+
+/// How fast the cursor was moving just before she was let go, in pixels per second.
+fn throw_velocity(trail: &VecDeque<(SystemTime, Vec2I)>) -> (f64, f64) {
+    let (Some((t0, p0)), Some((t1, p1))) = (trail.front(), trail.back()) else {
+        return (0.0, 0.0);
+    };
+    // the cursor stopped before letting go
+    if t1.elapsed().unwrap_or(Duration::ZERO) > THROW_SAMPLE_TIME {
+        return (0.0, 0.0);
+    }
+    let dt = t1
+        .duration_since(*t0)
+        .unwrap_or(Duration::ZERO)
+        .as_secs_f64();
+    if dt <= 0.0 {
+        return (0.0, 0.0);
+    }
+    let (vx, vy) = ((p1.x - p0.x) as f64 / dt, (p1.y - p0.y) as f64 / dt);
+    let scale = (MAX_THROW_SPEED / vx.hypot(vy)).min(1.0);
+    (vx * scale, vy * scale)
+}
+
 #[derive(Debug, Clone)]
 enum CreatureState {
     Hiding {
@@ -394,30 +1688,75 @@ enum CreatureState {
         facing: Facing,
         peek: bool,
         peek_end_t: SystemTime,
+        peek_t_begin: SystemTime,
+        unanswered_peeks: u32,
+        camo_color: [f32; 3],
     },
     Idle {
-        pos: Option<Vec2I>,
-        arms_raised: bool,
-        try_talk_at: SystemTime,
+        pos: Vec2I,
+        /// What she shows when she isn't looking anywhere in particular.
+        animation: String,
     },
     Talking {
         pos: Vec2I,
-        arms_raised: bool,
-        t_begin_talking: SystemTime,
-        duration: Duration,
-        id: usize,
+        /// What she's saying.
+        line: String,
+        /// What she says after that, one speech bubble at a time.
+        rest: VecDeque<String>,
     },
     Jumping {
-        from: Option<Vec2I>,
+        from: Vec2I,
         to: Vec2I,
-        t_begin: SystemTime,
-        duration: Duration,
-        following_state: Box<CreatureState>,
+    },
+    Sneaking {
+        from: Vec2I,
+        to: Vec2I,
+        /// Where she's going to hide once she gets there.
+        target_window: ExtWindowInfo,
+        pos: Vec2I,
+        facing: Facing,
     },
     Shocked {
         from: Vec2I,
         to: Facing,
+    },
+    Roaming {
+        pos: (f64, f64),
+        stride: Stride,
+        /// Platform under her feet, unless she's in the air.
+        standing_on: Option<Platform>,
+    },
+    Held {
+        pos: Vec2I,
+        /// Recent cursor positions, oldest first, for working out how hard she gets thrown.
+        trail: VecDeque<(SystemTime, Vec2I)>,
+    },
+    Thrown {
+        pos: (f64, f64),
+        /// In pixels per second.
+        vel: (f64, f64),
+        /// Work area of the monitor she's flying around in.
+        area: RectI,
+    },
+}
+
+/// What she's doing at the moment while roaming.
+#[derive(Debug, Clone)]
+enum Stride {
+    /// Dropping down because there's nothing under her.
+    Fall {
+        /// In pixels per second.
+        vel: f64,
+    },
+    /// Standing still for a bit before deciding where to go next.
+    Pause { until: SystemTime },
+    /// Walking along the platform she's on.
+    Walk { to_x: f64 },
+    /// Jumping over to another platform.
+    Jump {
+        from: Vec2I,
+        to: Vec2I,
         t_begin: SystemTime,
-        following_state: Box<CreatureState>,
+        onto: Platform,
     },
 }
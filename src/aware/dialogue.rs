@@ -0,0 +1,581 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use rand::Rng;
+
+use crate::{
+    messages::{Messages, Phrase},
+    render::text::Fonts,
+    round::{Mode, MODES},
+    util::json::{load_override, parse_file, read_file, Fields, FileError, Json},
+};
+
+use super::needs::{NeedRanges, Needs};
+
+/// File next to the game that, if present, replaces the bundled dialogue.
+const DIALOGUE_OVERRIDE_PATH: &str = "dialogue.json";
+const DIALOGUE_WHAT: &str = "dialogue file";
+/// Placeholders lines can have, filled in from the [`Context`] they're said in.
+const PLACEHOLDERS: [&str; 2] = ["catch_count", "window_name"];
+
+/// Everything a creature can say.
+#[derive(Debug, Clone)]
+pub struct Voice {
+    /// What she says when she stops to talk.
+    pub dialogue: Dialogue,
+    /// Everything else, like achievements and hints, and translations of the dialogue.
+    pub messages: Messages,
+    /// What it's all written with.
+    pub fonts: Fonts,
+}
+
+/// Everything she can say when she stops to talk, and when she says it.
+#[derive(Debug, Clone)]
+pub struct Dialogue {
+    pub lines: Vec<Line>,
+}
+
+/// Something she can say, which can take more than one speech bubble.
+#[derive(Debug, Clone)]
+pub struct Line {
+    /// Unique name of the line, for keeping track of its cooldown.
+    pub id: String,
+    pub when: Conditions,
+    /// How likely she is to pick this line over the others that fit.
+    pub weight: f64,
+    /// How long after saying it she won't say it again.
+    pub cooldown: Duration,
+    /// What she says, one speech bubble after another, unless there's a translation of it. Can
+    /// have `{placeholders}` in it.
+    pub say: Vec<Phrase>,
+}
+
+/// What has to be true for a line to be said. Conditions that are `None` always hold.
+#[derive(Debug, Clone, Default)]
+pub struct Conditions {
+    /// Fewest and most times she has to have been caught, inclusive.
+    pub catches: Option<(u32, u32)>,
+    /// Hour of the day it has to be at least and before, from 0 to 24. Wraps around midnight if
+    /// the first is bigger than the second.
+    pub hours: Option<(u32, u32)>,
+    pub mode: Option<Mode>,
+    pub last_event: Option<LastEvent>,
+    pub needs: NeedRanges,
+}
+
+/// The last thing that happened to her that she hasn't talked about yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LastEvent {
+    /// She hasn't said anything yet.
+    Start,
+    /// She was just found.
+    Found,
+    /// She was just thrown around.
+    Thrown,
+    /// She was just petted.
+    Petted,
+    /// She just yawned, since it's getting late.
+    Yawned,
+    /// She was just woken up by being clicked on.
+    Woken,
+    /// It's the first time she's seen you today: she just got up in the morning, or the game was
+    /// started for the first time since yesterday.
+    NewDay,
+    /// She's already talked about everything that happened.
+    Nothing,
+}
+impl LastEvent {
+    pub const ALL: [LastEvent; 8] = [
+        Self::Start,
+        Self::Found,
+        Self::Thrown,
+        Self::Petted,
+        Self::Yawned,
+        Self::Woken,
+        Self::NewDay,
+        Self::Nothing,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Found => "found",
+            Self::Thrown => "thrown",
+            Self::Petted => "petted",
+            Self::Yawned => "yawned",
+            Self::Woken => "woken",
+            Self::NewDay => "new_day",
+            Self::Nothing => "nothing",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|e| e.name() == name)
+    }
+}
+
+/// Everything about her situation that decides what she says.
+#[derive(Debug, Clone)]
+pub struct Context<'a> {
+    pub catch_count: u32,
+    /// Hour of the day, from 0 to 23.
+    pub hour: u32,
+    pub mode: Mode,
+    pub last_event: LastEvent,
+    /// Title of the window she was last found in.
+    pub window_name: Option<&'a str>,
+    pub needs: Needs,
+    pub now: SystemTime,
+}
+
+impl Context<'_> {
+    /// Values for the placeholders that can be filled in right now.
+    fn args(&self) -> Vec<(&'static str, &dyn Display)> {
+        let mut args: Vec<(&str, &dyn Display)> = vec![("catch_count", &self.catch_count)];
+        if let Some(name) = &self.window_name {
+            args.push(("window_name", name));
+        }
+        args
+    }
+}
+
+impl Voice {
+    /// Everything she says, in `language` as far as there are translations for it. Translated
+    /// lines that aren't in the dialogue, or that need placeholders the line doesn't, get reported
+    /// and left out.
+    pub fn load(language: &str) -> Self {
+        let dialogue = Dialogue::load();
+        let mut messages = Messages::load(language);
+        let mut broken = vec![];
+        for id in messages.translated_lines() {
+            let Some(line) = dialogue.lines.iter().find(|line| line.id == id) else {
+                broken.push((id.to_string(), "there's no line called that".to_string()));
+                continue;
+            };
+            let translated = messages.dialogue(id).unwrap_or_default();
+            for name in translated.iter().flat_map(Phrase::placeholders) {
+                if !line.placeholders().any(|it| it == name) {
+                    broken.push((
+                        id.to_string(),
+                        format!("the line doesn't have a \"{{{}}}\" to fill in", name),
+                    ));
+                }
+            }
+        }
+        for (id, problem) in broken {
+            eprintln!(
+                "[{} translation] dialogue.{}: {}",
+                messages.language, id, problem
+            );
+            messages.forget_line(&id);
+        }
+        let fonts = Fonts::load(&messages.fonts);
+        Self {
+            dialogue,
+            messages,
+            fonts,
+        }
+    }
+}
+
+impl Dialogue {
+    /// The dialogue that ships with the game.
+    pub fn bundled() -> Self {
+        Self::parse(include_str!("../assets/dialogue.json"))
+            .unwrap_or_else(|e| panic!("bundled dialogue is broken: {}", e))
+    }
+
+    /// Loads the dialogue override file if there is one, and falls back to the bundled dialogue
+    /// (after reporting what's wrong) if it can't be used.
+    pub fn load() -> Self {
+        load_override(
+            DIALOGUE_OVERRIDE_PATH,
+            Self::load_file,
+            "the bundled dialogue",
+            Self::bundled,
+        )
+    }
+
+    pub fn load_file(path: &Path) -> Result<Self, FileError> {
+        Self::parse(&read_file(path, DIALOGUE_WHAT)?)
+    }
+
+    pub fn parse(src: &str) -> Result<Self, FileError> {
+        let json = parse_file(src, DIALOGUE_WHAT)?;
+        let mut errors = vec![];
+
+        let mut root = Fields::new(&json, String::new(), &mut errors);
+        let lines_json = root.required("lines");
+        root.finish();
+
+        let mut lines: Vec<Line> = vec![];
+        match lines_json.map(|it| (it, it.as_array())) {
+            Some((_, Some(entries))) => {
+                for (i, entry) in entries.iter().enumerate() {
+                    let path = format!("lines[{}]", i);
+                    let Some(line) = Line::parse(entry, path.clone(), &mut errors) else {
+                        continue;
+                    };
+                    if lines.iter().any(|other| other.id == line.id) {
+                        errors.push(format!(
+                            "{}.id: there's already a line called \"{}\"",
+                            path, line.id
+                        ));
+                    }
+                    lines.push(line);
+                }
+            }
+            Some((it, None)) => errors.push(format!(
+                "lines: expected an array of lines, found {}",
+                it.type_name()
+            )),
+            None => {}
+        }
+
+        FileError::check(DIALOGUE_WHAT, errors)?;
+        Ok(Self { lines })
+    }
+
+    /// Picks something to say in `ctx`, out of the lines that fit it, by weight. `said` is when
+    /// each line was last said, by id.
+    pub fn pick(
+        &self,
+        ctx: &Context,
+        said: &HashMap<String, SystemTime>,
+        rng: &mut impl Rng,
+    ) -> Option<&Line> {
+        let fitting: Vec<&Line> = self
+            .lines
+            .iter()
+            .filter(|line| line.fits(ctx, said.get(&line.id).copied()))
+            .collect();
+        if fitting.is_empty() {
+            return None;
+        }
+        let total: f64 = fitting.iter().map(|line| line.weight).sum();
+        let mut x = rng.gen_range(0.0..total.max(f64::MIN_POSITIVE));
+        for line in &fitting {
+            if x < line.weight {
+                return Some(line);
+            }
+            x -= line.weight;
+        }
+        fitting.last().copied()
+    }
+}
+
+impl Line {
+    /// Whether the line can be said in `ctx`, given it was last said at `said` (if ever).
+    pub fn fits(&self, ctx: &Context, said: Option<SystemTime>) -> bool {
+        let cooled_down = said
+            .is_none_or(|t| ctx.now.duration_since(t).unwrap_or(Duration::ZERO) >= self.cooldown);
+        let args = ctx.args();
+        let fillable = self
+            .placeholders()
+            .all(|name| args.iter().any(|(arg, _)| *arg == name));
+        cooled_down && fillable && self.when.hold(ctx)
+    }
+
+    /// What she says, in the language of `messages` if it has a translation of the line, with
+    /// the placeholders filled in from `ctx`.
+    pub fn render(&self, ctx: &Context, messages: &Messages) -> Vec<String> {
+        let args = ctx.args();
+        let say = messages.dialogue(&self.id).unwrap_or(&self.say);
+        say.iter()
+            .map(|phrase| phrase.fill(messages.plural_rule(), &args))
+            .collect()
+    }
+
+    fn placeholders(&self) -> impl Iterator<Item = &str> {
+        self.say.iter().flat_map(Phrase::placeholders)
+    }
+
+    fn parse(json: &Json, path: String, errors: &mut Vec<String>) -> Option<Self> {
+        let mut fields = Fields::new(json, path, errors);
+        let id = fields.string("id");
+        let when = match fields.nested("when") {
+            Some(when) => Conditions::parse(when),
+            None => Some(Conditions::default()),
+        };
+        let weight = match fields.optional("weight") {
+            Some(value) => match value.as_f64() {
+                Some(n) if n > 0.0 => Some(n),
+                _ => {
+                    fields.error("weight", "expected a number greater than 0");
+                    None
+                }
+            },
+            None => Some(1.0),
+        };
+        let cooldown = match fields.optional("cooldown") {
+            Some(value) => match value.as_f64() {
+                Some(secs) if secs >= 0.0 && secs.is_finite() => {
+                    Some(Duration::from_secs_f64(secs))
+                }
+                _ => {
+                    fields.error("cooldown", "expected a number of seconds");
+                    None
+                }
+            },
+            None => Some(Duration::ZERO),
+        };
+        let say = fields
+            .required("say")
+            .and_then(|value| match Phrase::bubbles_from_json(value) {
+                Ok(say) => Some(say),
+                Err(e) => {
+                    fields.error("say", e);
+                    None
+                }
+            });
+        for name in say.iter().flatten().flat_map(Phrase::placeholders) {
+            if !PLACEHOLDERS.contains(&name) {
+                fields.error(
+                    "say",
+                    format!(
+                        "unknown placeholder \"{{{}}}\", expected one of {}",
+                        name,
+                        PLACEHOLDERS.map(|p| format!("{{{}}}", p)).join(", ")
+                    ),
+                );
+            }
+        }
+        fields.finish();
+        Some(Self {
+            id: id?,
+            when: when?,
+            weight: weight?,
+            cooldown: cooldown?,
+            say: say?,
+        })
+    }
+}
+
+impl Conditions {
+    /// Whether all of the conditions hold in `ctx`.
+    pub fn hold(&self, ctx: &Context) -> bool {
+        let catches = self
+            .catches
+            .is_none_or(|(min, max)| (min..=max).contains(&ctx.catch_count));
+        let hours = self.hours.is_none_or(|(from, to)| {
+            if from <= to {
+                (from..to).contains(&ctx.hour)
+            } else {
+                ctx.hour >= from || ctx.hour < to
+            }
+        });
+        let mode = self.mode.is_none_or(|mode| mode == ctx.mode);
+        let last_event = self.last_event.is_none_or(|event| event == ctx.last_event);
+        catches && hours && mode && last_event && self.needs.hold(&ctx.needs)
+    }
+
+    fn parse(mut fields: Fields) -> Option<Self> {
+        if fields.is_broken() {
+            return None;
+        }
+        let mut when = Self::default();
+        let mut ok = true;
+
+        // a number for exactly that many, or { "min": n, "max": n } where both are optional
+        if let Some(value) = fields.optional("catches") {
+            let count = |v: Option<&Json>, default: u32| match v {
+                None => Some(default),
+                Some(v) => v
+                    .as_f64()
+                    .filter(|n| n.fract() == 0.0 && *n >= 0.0)
+                    .map(|n| n as u32),
+            };
+            let range = match value {
+                Json::Number(_) => count(Some(value), 0).map(|n| (n, n)),
+                Json::Object(_) => {
+                    count(value.get("min"), 0).zip(count(value.get("max"), u32::MAX))
+                }
+                _ => None,
+            };
+            match range {
+                Some((min, max)) if min <= max => when.catches = Some((min, max)),
+                Some(_) => {
+                    fields.error("catches", "min is greater than max");
+                    ok = false;
+                }
+                None => {
+                    fields.error(
+                        "catches",
+                        "expected a whole number or { \"min\": number, \"max\": number }",
+                    );
+                    ok = false;
+                }
+            }
+        }
+        if let Some(value) = fields.optional("hours") {
+            let hour = |v: Option<&Json>| {
+                v.and_then(Json::as_f64)
+                    .filter(|n| n.fract() == 0.0 && (0.0..=24.0).contains(n))
+                    .map(|n| n as u32)
+            };
+            match hour(value.get("from")).zip(hour(value.get("to"))) {
+                Some(hours) => when.hours = Some(hours),
+                None => {
+                    fields.error(
+                        "hours",
+                        "expected { \"from\": hour, \"to\": hour }, with hours from 0 to 24",
+                    );
+                    ok = false;
+                }
+            }
+        }
+        if let Some(value) = fields.optional("mode") {
+            match value.as_str().and_then(Mode::from_name) {
+                Some(mode) => when.mode = Some(mode),
+                None => {
+                    fields.error("mode", format!("expected one of {}", MODES.join(", ")));
+                    ok = false;
+                }
+            }
+        }
+        if let Some(value) = fields.optional("last_event") {
+            match value.as_str().and_then(LastEvent::from_name) {
+                Some(event) => when.last_event = Some(event),
+                None => {
+                    let names = LastEvent::ALL.map(LastEvent::name).join(", ");
+                    fields.error("last_event", format!("expected one of {}", names));
+                    ok = false;
+                }
+            }
+        }
+        match NeedRanges::parse(&mut fields) {
+            Some(needs) => when.needs = needs,
+            None => ok = false,
+        }
+        fields.finish();
+        ok.then_some(when)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn dialogue(lines: &str) -> Dialogue {
+        Dialogue::parse(&format!(r#"{{"lines": [{}]}}"#, lines)).unwrap()
+    }
+    fn ctx(now: SystemTime) -> Context<'static> {
+        Context {
+            catch_count: 0,
+            hour: 12,
+            mode: Mode::Endless,
+            last_event: LastEvent::Nothing,
+            window_name: None,
+            needs: Needs::default(),
+            now,
+        }
+    }
+
+    #[test]
+    fn pick_by_weight() {
+        let dialogue = dialogue(
+            r#"{"id": "rare", "say": "a"},
+               {"id": "common", "weight": 3, "say": "b"},
+               {"id": "never", "weight": 100, "when": {"catches": 5}, "say": "c"}"#,
+        );
+        let ctx = ctx(SystemTime::now());
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut picks: HashMap<String, u32> = HashMap::new();
+        for _ in 0..4000 {
+            let line = dialogue.pick(&ctx, &HashMap::new(), &mut rng).unwrap();
+            *picks.entry(line.id.clone()).or_default() += 1;
+        }
+        assert_eq!(picks.get("never"), None);
+        let common = picks["common"] as f64 / 4000.0;
+        assert!((0.72..0.78).contains(&common), "{}", common);
+    }
+
+    #[test]
+    fn cooldowns() {
+        let dialogue = dialogue(r#"{"id": "a", "cooldown": 60, "say": "a"}"#);
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        let mut rng = StdRng::seed_from_u64(0);
+        let said =
+            |secs_ago: u64| HashMap::from([("a".to_string(), now - Duration::from_secs(secs_ago))]);
+        assert!(dialogue.pick(&ctx(now), &said(30), &mut rng).is_none());
+        assert!(dialogue.pick(&ctx(now), &said(60), &mut rng).is_some());
+        assert!(dialogue
+            .pick(&ctx(now), &HashMap::new(), &mut rng)
+            .is_some());
+    }
+
+    #[test]
+    fn hours_wrap_around_midnight() {
+        let dialogue = dialogue(
+            r#"{"id": "night", "when": {"hours": {"from": 22, "to": 5}}, "say": "a"},
+               {"id": "day", "when": {"hours": {"from": 9, "to": 17}}, "say": "b"}"#,
+        );
+        let holds = |id: usize, hour: u32| {
+            let ctx = Context {
+                hour,
+                ..ctx(SystemTime::now())
+            };
+            dialogue.lines[id].when.hold(&ctx)
+        };
+        for hour in [22, 23, 0, 4] {
+            assert!(holds(0, hour), "{}", hour);
+        }
+        for hour in [5, 12, 21] {
+            assert!(!holds(0, hour), "{}", hour);
+        }
+        assert!(holds(1, 9) && holds(1, 16));
+        assert!(!holds(1, 8) && !holds(1, 17));
+    }
+
+    #[test]
+    fn catches() {
+        let dialogue = dialogue(
+            r#"{"id": "exactly", "when": {"catches": 2}, "say": "a"},
+               {"id": "range", "when": {"catches": {"min": 1, "max": 3}}, "say": "b"},
+               {"id": "at_least", "when": {"catches": {"min": 4}}, "say": "c"}"#,
+        );
+        let holding = |catch_count: u32| -> Vec<&str> {
+            let ctx = Context {
+                catch_count,
+                ..ctx(SystemTime::now())
+            };
+            dialogue
+                .lines
+                .iter()
+                .filter(|line| line.when.hold(&ctx))
+                .map(|line| line.id.as_str())
+                .collect()
+        };
+        assert_eq!(holding(0), [] as [&str; 0]);
+        assert_eq!(holding(1), ["range"]);
+        assert_eq!(holding(2), ["exactly", "range"]);
+        assert_eq!(holding(3), ["range"]);
+        assert_eq!(holding(4), ["at_least"]);
+        assert_eq!(holding(1000), ["at_least"]);
+        assert!(Dialogue::parse(
+            r#"{"lines": [{"id": "a", "when": {"catches": {"min": 3, "max": 1}}, "say": "a"}]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn lines_need_their_placeholders() {
+        let dialogue = dialogue(
+            r#"{"id": "found_in", "say": "you found me in {window_name}!"},
+               {"id": "count", "say": "that's {catch_count}"}"#,
+        );
+        let now = SystemTime::now();
+        assert!(!dialogue.lines[0].fits(&ctx(now), None));
+        assert!(dialogue.lines[1].fits(&ctx(now), None));
+        let found = Context {
+            window_name: Some("notepad"),
+            ..ctx(now)
+        };
+        assert!(dialogue.lines[0].fits(&found, None));
+    }
+}
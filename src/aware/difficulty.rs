@@ -0,0 +1,189 @@
+use std::{collections::VecDeque, time::Duration};
+
+/// Names of the difficulty presets, easiest first.
+pub const PRESETS: [&str; 3] = ["easy", "normal", "hard"];
+
+/// Tunables that control how hard the creature is to find.
+#[derive(Debug, Clone)]
+pub struct Difficulty {
+    /// Number of peeks that go by without her being caught before she sneaks off to a new spot.
+    pub relocate_after_peeks: u32,
+    /// Longest time she stays in one spot before sneaking off, no matter how many peeks.
+    pub relocate_after: Duration,
+    /// Whether she can be seen while sneaking to the new spot.
+    pub visible_sneak: bool,
+    /// How far she slides out from behind the edge when peeking, from 0 (not at all) to 1 (fully).
+    pub peek_reveal: f64,
+    /// How long sliding out from (or back behind) the edge takes.
+    pub peek_slide_time: Duration,
+    /// How strongly her colors get shifted towards the background of her hiding spot, from 0 (not
+    /// at all) to 1 (same color as the background).
+    pub camouflage: f32,
+    /// Whether her eyes follow the cursor while she's standing around.
+    pub look_at_cursor: bool,
+    /// How close the cursor can come towards her while she's peeking before she ducks back, or
+    /// `None` if she doesn't.
+    pub flinch_distance: Option<f64>,
+    /// How long the cursor has to sit still near her before she comes to have a look at it, or
+    /// `None` if she doesn't.
+    pub curious_after: Option<Duration>,
+    /// How near the cursor has to be for her to get curious about it.
+    pub curious_distance: f64,
+    /// Multiplies how long each peek lasts.
+    pub peek_time_scale: f64,
+    /// Multiplies how long she stays out of sight between peeks, including before the first one.
+    pub hidden_time_scale: f64,
+    /// How many hiding spots she looks at before going with the one furthest from the cursor.
+    pub spot_picks: usize,
+    /// Whether the peek timings and spot choice get adjusted to how quickly she's been found
+    /// lately.
+    pub adaptive: bool,
+    /// How long she stays hidden before she starts dropping hints, or `None` if she never does.
+    pub hint_after: Option<Duration>,
+    /// How long each hint lasts before she moves on to a more obvious one.
+    pub hint_step: Duration,
+    /// How close to her hiding spot a click has to be to count as a miss.
+    pub miss_distance: f64,
+    /// How likely she is to sneak off to a new spot after a miss instead of taunting, from 0 to 1.
+    pub relocate_on_miss: f64,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self::normal()
+    }
+}
+
+impl Difficulty {
+    /// The preset called `name` (one of [`PRESETS`]), if there is one.
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(Self::easy()),
+            "normal" => Some(Self::normal()),
+            "hard" => Some(Self::hard()),
+            _ => None,
+        }
+    }
+    fn easy() -> Self {
+        Self {
+            relocate_after_peeks: 6,
+            relocate_after: Duration::from_secs(60),
+            visible_sneak: true,
+            peek_reveal: 1.0,
+            peek_slide_time: Duration::from_millis(200),
+            camouflage: 0.0,
+            look_at_cursor: true,
+            flinch_distance: None,
+            curious_after: Some(Duration::from_secs(2)),
+            curious_distance: 400.0,
+            peek_time_scale: 1.5,
+            hidden_time_scale: 0.6,
+            spot_picks: 1,
+            adaptive: false,
+            hint_after: Some(Duration::from_secs(20)),
+            hint_step: Duration::from_secs(10),
+            miss_distance: 60.0,
+            relocate_on_miss: 0.0,
+        }
+    }
+    fn normal() -> Self {
+        Self {
+            relocate_after_peeks: 4,
+            relocate_after: Duration::from_secs(45),
+            visible_sneak: true,
+            peek_reveal: 1.0,
+            peek_slide_time: Duration::from_millis(150),
+            camouflage: 0.0,
+            look_at_cursor: true,
+            flinch_distance: Some(150.0),
+            curious_after: Some(Duration::from_secs(3)),
+            curious_distance: 300.0,
+            peek_time_scale: 1.0,
+            hidden_time_scale: 1.0,
+            spot_picks: 1,
+            adaptive: false,
+            hint_after: Some(Duration::from_secs(40)),
+            hint_step: Duration::from_secs(15),
+            miss_distance: 100.0,
+            relocate_on_miss: 0.25,
+        }
+    }
+    fn hard() -> Self {
+        Self {
+            relocate_after_peeks: 3,
+            relocate_after: Duration::from_secs(30),
+            visible_sneak: false,
+            peek_reveal: 0.6,
+            peek_slide_time: Duration::from_millis(100),
+            camouflage: 0.4,
+            look_at_cursor: true,
+            flinch_distance: Some(250.0),
+            curious_after: Some(Duration::from_secs(5)),
+            curious_distance: 200.0,
+            peek_time_scale: 0.7,
+            hidden_time_scale: 1.4,
+            spot_picks: 4,
+            adaptive: false,
+            hint_after: Some(Duration::from_secs(60)),
+            hint_step: Duration::from_secs(20),
+            miss_distance: 150.0,
+            relocate_on_miss: 0.5,
+        }
+    }
+
+    /// This difficulty made harder (`level` above 0) or easier (below 0), with `level` going from
+    /// -1 to 1. Only the peek timings and spot choice change.
+    pub fn adapted(&self, level: f64) -> Self {
+        let level = level.clamp(-1.0, 1.0);
+        let factor = ADAPT_RANGE.sqrt().powf(level);
+        Self {
+            peek_time_scale: self.peek_time_scale / factor,
+            hidden_time_scale: self.hidden_time_scale * factor,
+            spot_picks: (self.spot_picks as f64 + level * 2.0).round().max(1.0) as usize,
+            ..self.clone()
+        }
+    }
+}
+
+/// How long finding her should take, for adaptive difficulty.
+const TARGET_FIND_TIME: Duration = Duration::from_secs(20);
+/// Being found this many times faster (or slower) than [`TARGET_FIND_TIME`] makes adaptive
+/// difficulty go all the way up (or down).
+const ADAPT_RANGE: f64 = 4.0;
+/// How many of the latest finds adaptive difficulty goes by.
+const RECENT_FINDS: usize = 8;
+
+/// Keeps track of how quickly she's been found lately, for adaptive difficulty.
+#[derive(Debug, Clone, Default)]
+pub struct Adaptive {
+    /// How long the latest finds took, oldest first.
+    recent: VecDeque<Duration>,
+}
+
+impl Adaptive {
+    pub fn record(&mut self, find_time: Duration) {
+        self.recent.push_back(find_time);
+        while self.recent.len() > RECENT_FINDS {
+            self.recent.pop_front();
+        }
+    }
+    /// How long the latest finds took, oldest first.
+    pub fn recent(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.recent.iter().copied()
+    }
+    /// How much harder she should be to find, from -1 (she's been taking ages to find) to 1
+    /// (she's been found right away). 0 until she's been found at least once.
+    pub fn level(&self) -> f64 {
+        if self.recent.is_empty() {
+            return 0.0;
+        }
+        let target = TARGET_FIND_TIME.as_secs_f64();
+        let skill = self
+            .recent
+            .iter()
+            .map(|t| (target / t.as_secs_f64().max(0.1)).ln())
+            .sum::<f64>()
+            / self.recent.len() as f64;
+        (skill / ADAPT_RANGE.ln()).clamp(-1.0, 1.0)
+    }
+}
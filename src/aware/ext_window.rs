@@ -1,9 +1,20 @@
 use image::{DynamicImage, GenericImageView, Rgba};
 
 use win_screenshot::utils::HwndName;
-use windows::Win32::{
-    Foundation::{HWND, RECT},
-    UI::WindowsAndMessaging::{GetWindowRect, IsWindow, IsWindowVisible},
+use windows::{
+    core::PWSTR,
+    Win32::{
+        Foundation::{CloseHandle, BOOL, HWND, POINT, RECT},
+        Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED},
+        System::Threading::{
+            OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+            PROCESS_QUERY_LIMITED_INFORMATION,
+        },
+        UI::WindowsAndMessaging::{
+            GetCursorPos, GetWindow, GetWindowRect, GetWindowTextW, GetWindowThreadProcessId,
+            IsIconic, IsWindow, IsWindowVisible, GW_HWNDPREV,
+        },
+    },
 };
 
 use crate::{
@@ -15,6 +26,31 @@ fn window_visible(hwnd: isize) -> bool {
     unsafe { IsWindowVisible(HWND(hwnd)).as_bool() }
 }
 
+/// Whether the window would actually show up on screen, as opposed to only being marked visible.
+/// Minimized windows and windows cloaked by the compositor (suspended store apps, windows on
+/// other virtual desktops) don't count.
+fn window_shown(hwnd: isize) -> bool {
+    let mut cloaked = BOOL(0);
+    unsafe {
+        let cloak_known = DwmGetWindowAttribute(
+            HWND(hwnd),
+            DWMWA_CLOAKED,
+            &mut cloaked as *mut _ as *mut _,
+            std::mem::size_of::<BOOL>() as u32,
+        )
+        .is_ok();
+        window_visible(hwnd)
+            && !IsIconic(HWND(hwnd)).as_bool()
+            && !(cloak_known && cloaked.as_bool())
+    }
+}
+
+fn window_title(hwnd: isize) -> String {
+    let mut buf = [0u16; 256];
+    let len = unsafe { GetWindowTextW(HWND(hwnd), &mut buf) };
+    String::from_utf16_lossy(&buf[..len.max(0) as usize])
+}
+
 fn window_rect(hwnd: isize) -> Option<RectI> {
     let mut rect: RECT = Default::default();
 
@@ -36,6 +72,37 @@ fn window_rect(hwnd: isize) -> Option<RectI> {
     })
 }
 
+/// File name of the program the window belongs to, like `notepad.exe`.
+fn window_app(hwnd: isize) -> Option<String> {
+    let mut pid = 0;
+    unsafe { GetWindowThreadProcessId(HWND(hwnd), Some(&mut pid as *mut _)) };
+    if pid == 0 {
+        return None;
+    }
+    let mut buf = [0u16; 1024];
+    let mut len = buf.len() as u32;
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let named = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(buf.as_mut_ptr()),
+            &mut len as *mut _,
+        );
+        let _ = CloseHandle(process);
+        named.ok()?;
+    }
+    let path = String::from_utf16_lossy(&buf[..len as usize]);
+    path.rsplit(['\\', '/']).next().map(str::to_string)
+}
+
+/// Where the cursor is on screen, no matter which window it's over.
+pub fn cursor_pos() -> Option<Vec2I> {
+    let mut point = POINT::default();
+    unsafe { GetCursorPos(&mut point as *mut _) }.ok()?;
+    Some(Vec2I::new(point.x, point.y))
+}
+
 fn screenshot(hwnd: isize) -> Option<DynamicImage> {
     let buf = win_screenshot::capture::capture_window(hwnd).ok()?;
     let mut img = DynamicImage::new_rgba8(buf.width, buf.height);
@@ -66,6 +133,9 @@ pub struct ExtWindowInfo {
     rect: RectI,
 }
 impl ExtWindowInfo {
+    pub fn hwnd(&self) -> isize {
+        self.hwnd
+    }
     pub fn still_exists(&self) -> bool {
         unsafe { IsWindow(HWND(self.hwnd)).as_bool() }
     }
@@ -78,6 +148,31 @@ impl ExtWindowInfo {
         }
         &self.img
     }
+    /// Average color of the screenshot in a square of the given radius around `pos`, where `pos`
+    /// is relative to the window's top left corner.
+    pub fn average_color_around(&self, pos: Vec2I, radius: i32) -> [f32; 3] {
+        let (w, h) = self.img.dimensions();
+        // the screenshot isn't always the same size as the window rect
+        let center = (
+            pos.x as i64 * w as i64 / self.rect.dim.x.max(1) as i64,
+            pos.y as i64 * h as i64 / self.rect.dim.y.max(1) as i64,
+        );
+        let mut sum = [0.0; 3];
+        let mut n = 0;
+        for y in (center.1 - radius as i64).max(0)..(center.1 + radius as i64).min(h as i64) {
+            for x in (center.0 - radius as i64).max(0)..(center.0 + radius as i64).min(w as i64) {
+                let Rgba([r, g, b, _]) = self.img.get_pixel(x as u32, y as u32);
+                sum[0] += r as f32;
+                sum[1] += g as f32;
+                sum[2] += b as f32;
+                n += 1;
+            }
+        }
+        if n == 0 {
+            return [1.0; 3];
+        }
+        sum.map(|c| c / (255.0 * n as f32))
+    }
     pub fn rect(&self) -> RectI {
         self.rect
     }
@@ -90,6 +185,48 @@ impl ExtWindowInfo {
     pub fn name(&self) -> &str {
         self.window_name.as_str()
     }
+    /// File name of the program the window belongs to, if it can be found out.
+    pub fn app_name(&self) -> Option<String> {
+        window_app(self.hwnd)
+    }
+    /// Rects of the windows stacked above this one that overlap it, nearest first.
+    pub fn rects_above(&self) -> Vec<RectI> {
+        let mut rects = vec![];
+        let mut hwnd = self.hwnd;
+        loop {
+            hwnd = unsafe { GetWindow(HWND(hwnd), GW_HWNDPREV) }.0;
+            if hwnd == 0 {
+                break;
+            }
+            if !window_shown(hwnd) || window_title(hwnd) == SELF_WINDOW_TITLE {
+                continue;
+            }
+            if let Some(rect) = window_rect(hwnd) {
+                if rect.intersects(self.rect) {
+                    rects.push(rect);
+                }
+            }
+        }
+        rects
+    }
+}
+
+/// Where every window that shows up on screen (except ours) is, topmost first, as (hwnd, rect).
+/// Unlike [`iter_window_candidates`] nothing gets captured, so this is cheap enough to call often.
+pub fn list_window_rects() -> Vec<(isize, RectI)> {
+    let Ok(windows) = win_screenshot::utils::window_list() else {
+        return vec![];
+    };
+    windows
+        .into_iter()
+        .filter(|it| {
+            window_shown(it.hwnd)
+                && it.window_name != SELF_WINDOW_TITLE
+                // the desktop itself
+                && it.window_name != "Program Manager"
+        })
+        .filter_map(|it| Some((it.hwnd, window_rect(it.hwnd)?)))
+        .collect()
 }
 
 pub fn iter_window_candidates() -> impl Iterator<Item = ExtWindowInfo> {
@@ -0,0 +1,189 @@
+use std::time::Duration;
+
+use crate::util::json::{Fields, Json};
+
+/// How much energy she uses up per second while she's up and about.
+const ENERGY_DRAIN: f64 = 1.0 / 600.0;
+/// How much energy she gets back per second while she's standing around or hiding.
+const ENERGY_RECOVERY: f64 = 1.0 / 300.0;
+/// How much more bored she gets per second, whatever she's doing.
+const BOREDOM_RATE: f64 = 1.0 / 900.0;
+/// How much affection she loses per second when she's fully bored, since nobody's playing with
+/// her. Less when she's less bored.
+const AFFECTION_DECAY: f64 = 1.0 / (7.0 * 24.0 * 3600.0);
+/// Longest time away that she catches up on between runs. Past this she's as rested and as bored
+/// as she's going to get anyway.
+const MAX_AWAY: Duration = Duration::from_secs(12 * 3600);
+
+/// How she's feeling, each from 0 to 1. They change over time and with what happens to her, and
+/// decide some of what she does and says.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Needs {
+    /// 0 is worn out, 1 is full of beans.
+    pub energy: f64,
+    /// 0 is entertained, 1 is bored out of her mind.
+    pub boredom: f64,
+    /// How much she likes whoever's playing with her.
+    pub affection: f64,
+}
+
+impl Default for Needs {
+    fn default() -> Self {
+        Self {
+            energy: 1.0,
+            boredom: 0.0,
+            affection: 0.2,
+        }
+    }
+}
+
+/// Something that happened to her that changes how she feels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interaction {
+    /// She was found while hiding.
+    Found,
+    /// She was picked up and thrown.
+    Thrown,
+    /// She was petted.
+    Petted,
+}
+
+/// One of her [`Needs`], for conditions that depend on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Need {
+    Energy,
+    Boredom,
+    Affection,
+}
+impl Need {
+    pub const ALL: [Need; 3] = [Self::Energy, Self::Boredom, Self::Affection];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Energy => "energy",
+            Self::Boredom => "boredom",
+            Self::Affection => "affection",
+        }
+    }
+    pub fn of(self, needs: &Needs) -> f64 {
+        match self {
+            Self::Energy => needs.energy,
+            Self::Boredom => needs.boredom,
+            Self::Affection => needs.affection,
+        }
+    }
+}
+
+/// Ranges her needs have to be in, inclusive. Needs that aren't listed can be anything.
+#[derive(Debug, Clone, Default)]
+pub struct NeedRanges(Vec<(Need, f64, f64)>);
+
+impl Needs {
+    /// Lets `dt` go by. `resting` is whether she's taking it easy (standing around, talking or
+    /// hiding) rather than running around.
+    pub fn update(&mut self, dt: Duration, resting: bool) {
+        let secs = dt.as_secs_f64();
+        self.energy += if resting {
+            ENERGY_RECOVERY * secs
+        } else {
+            -ENERGY_DRAIN * secs
+        };
+        self.boredom += BOREDOM_RATE * secs;
+        self.affection -= AFFECTION_DECAY * self.boredom * secs;
+        self.clamp();
+    }
+
+    /// Catches up on the time `away` she spent resting while the game wasn't running.
+    pub fn update_away(&mut self, away: Duration) {
+        self.update(away.min(MAX_AWAY), true);
+    }
+
+    pub fn react(&mut self, interaction: Interaction) {
+        match interaction {
+            Interaction::Found => {
+                self.boredom -= 0.4;
+                self.affection += 0.05;
+            }
+            Interaction::Thrown => {
+                self.energy -= 0.05;
+                self.boredom -= 0.2;
+                self.affection += 0.02;
+            }
+            Interaction::Petted => {
+                self.boredom -= 0.1;
+                self.affection += 0.1;
+            }
+        }
+        self.clamp();
+    }
+
+    fn clamp(&mut self) {
+        for need in [&mut self.energy, &mut self.boredom, &mut self.affection] {
+            *need = need.clamp(0.0, 1.0);
+        }
+    }
+
+    pub fn from_json(json: &Json) -> Option<Self> {
+        let need = |name: &str| {
+            json.get(name)
+                .and_then(Json::as_f64)
+                .filter(|n| (0.0..=1.0).contains(n))
+        };
+        Some(Self {
+            energy: need("energy")?,
+            boredom: need("boredom")?,
+            affection: need("affection")?,
+        })
+    }
+    pub fn to_json(self) -> Json {
+        Json::Object(
+            Need::ALL
+                .into_iter()
+                .map(|need| (need.name().to_string(), Json::Number(need.of(&self))))
+                .collect(),
+        )
+    }
+}
+
+impl NeedRanges {
+    /// Whether every need is in its range.
+    pub fn hold(&self, needs: &Needs) -> bool {
+        self.0
+            .iter()
+            .all(|(need, min, max)| (*min..=*max).contains(&need.of(needs)))
+    }
+
+    /// Reads `{ "min": n, "max": n }` (both optional, from 0 to 1) for each need that's in
+    /// `fields`. `None` if any of them are broken, after reporting why.
+    pub(super) fn parse(fields: &mut Fields) -> Option<Self> {
+        let mut ranges = vec![];
+        let mut ok = true;
+        for need in Need::ALL {
+            let Some(value) = fields.optional(need.name()) else {
+                continue;
+            };
+            let bound = |key: &str, default: f64| match value.get(key) {
+                None => Some(default),
+                Some(v) => v.as_f64().filter(|n| (0.0..=1.0).contains(n)),
+            };
+            match value
+                .as_object()
+                .and(bound("min", 0.0).zip(bound("max", 1.0)))
+            {
+                Some((min, max)) if min <= max => ranges.push((need, min, max)),
+                Some(_) => {
+                    fields.error(need.name(), "min is greater than max");
+                    ok = false;
+                }
+                None => {
+                    fields.error(
+                        need.name(),
+                        "expected { \"min\": number, \"max\": number }, from 0 to 1",
+                    );
+                    ok = false;
+                }
+            }
+        }
+        ok.then_some(Self(ranges))
+    }
+}
@@ -0,0 +1,263 @@
+use std::time::{Duration, SystemTime};
+
+use crate::util::{json::Json, Vec2I};
+
+/// How moving the cursor over her has to go to count as petting her.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PettingSettings {
+    /// How many strokes in a row it takes, each one going back the way the last one came.
+    pub strokes: u32,
+    /// How far the cursor has to go for a stroke to count, in pixels. Wiggles shorter than this
+    /// are ignored.
+    pub stroke_length: f64,
+    /// Longest a stroke can take before the strokes so far are forgotten.
+    pub stroke_time: Duration,
+}
+
+impl Default for PettingSettings {
+    fn default() -> Self {
+        Self {
+            strokes: 4,
+            stroke_length: 20.0,
+            stroke_time: Duration::from_millis(600),
+        }
+    }
+}
+
+impl PettingSettings {
+    /// Reads `{ "strokes": 4, "stroke_length": 20, "stroke_time": 0.6 }`, where any of them can be
+    /// left out to keep its default. Returns what's wrong with it otherwise.
+    pub fn from_json(json: &Json) -> Result<Self, Vec<String>> {
+        let mut settings = Self::default();
+        let mut errors = vec![];
+        let Some(entries) = json.as_object() else {
+            return Err(vec![format!(
+                "expected an object, found {}",
+                json.type_name()
+            )]);
+        };
+        for (key, value) in entries {
+            let n = value.as_f64().filter(|n| *n > 0.0 && n.is_finite());
+            match (key.as_str(), n) {
+                ("strokes", Some(n)) if n.fract() == 0.0 => settings.strokes = n as u32,
+                ("strokes", _) => errors.push(format!("{}: expected a whole number above 0", key)),
+                ("stroke_length", Some(n)) => settings.stroke_length = n,
+                ("stroke_length", None) => {
+                    errors.push(format!("{}: expected a number of pixels above 0", key))
+                }
+                ("stroke_time", Some(n)) => settings.stroke_time = Duration::from_secs_f64(n),
+                ("stroke_time", None) => {
+                    errors.push(format!("{}: expected a number of seconds above 0", key))
+                }
+                _ => errors.push(format!("{}: unknown setting", key)),
+            }
+        }
+        if errors.is_empty() {
+            Ok(settings)
+        } else {
+            Err(errors)
+        }
+    }
+    pub fn to_json(self) -> Json {
+        Json::Object(vec![
+            ("strokes".to_string(), Json::Number(self.strokes as f64)),
+            (
+                "stroke_length".to_string(),
+                Json::Number(self.stroke_length),
+            ),
+            (
+                "stroke_time".to_string(),
+                Json::Number(self.stroke_time.as_secs_f64()),
+            ),
+        ])
+    }
+}
+
+/// Picks petting out of where the cursor goes, going by its strokes left and right and up and
+/// down separately.
+#[derive(Debug, Clone, Default)]
+pub struct Strokes {
+    last: Option<Vec2I>,
+    x: Axis,
+    y: Axis,
+}
+
+/// Strokes along one axis.
+#[derive(Debug, Clone, Copy, Default)]
+struct Axis {
+    /// Which way the current stroke is going, -1 or 1, or 0 before the first one.
+    dir: i32,
+    /// How far the current stroke has gone, at its furthest.
+    travel: f64,
+    /// How far the cursor has come back since then. It only turns into the next stroke once
+    /// it's gone the stroke length, so that wiggles don't break up a stroke.
+    back: f64,
+    /// When the current stroke started.
+    t_begin: Option<SystemTime>,
+    /// When the cursor started coming back, if it has.
+    t_turn: Option<SystemTime>,
+    /// How many strokes in a row there have been, not counting the current one.
+    count: u32,
+}
+
+impl Strokes {
+    /// The cursor moved to `cursor` at `now` while over her. Returns whether that made enough
+    /// strokes to count as petting, in which case it starts counting over.
+    pub fn moved(&mut self, cursor: Vec2I, settings: &PettingSettings, now: SystemTime) -> bool {
+        let Some(last) = self.last.replace(cursor) else {
+            return false;
+        };
+        let d = cursor - last;
+        let petted = self.x.moved(d.x, settings, now) || self.y.moved(d.y, settings, now);
+        if petted {
+            self.reset();
+        }
+        petted
+    }
+    /// Forgets the strokes so far, like when the cursor leaves her.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Axis {
+    fn moved(&mut self, d: i32, settings: &PettingSettings, now: SystemTime) -> bool {
+        if d == 0 {
+            return false;
+        }
+        // while coming back, it's the stroke that might be starting that has to be quick enough
+        let too_slow = self.t_turn.or(self.t_begin).is_some_and(|t| {
+            now.duration_since(t).unwrap_or(Duration::ZERO) > settings.stroke_time
+        });
+        if too_slow {
+            *self = Self::default();
+        }
+        let d_abs = d.abs() as f64;
+        if self.dir == 0 {
+            self.dir = d.signum();
+            self.travel = d_abs;
+            self.t_begin = Some(now);
+            return false;
+        }
+        if d.signum() == self.dir {
+            // carrying on, maybe past where it came back from
+            self.travel += (d_abs - self.back).max(0.0);
+            self.back = (self.back - d_abs).max(0.0);
+            if self.back == 0.0 {
+                self.t_turn = None;
+            }
+            return false;
+        }
+        if self.back == 0.0 {
+            self.t_turn = Some(now);
+        }
+        self.back += d_abs;
+        if self.back < settings.stroke_length {
+            return false;
+        }
+        // it's come back far enough to be a stroke of its own, so the one before it is over, and
+        // counts if it was long enough
+        let count = if self.travel >= settings.stroke_length {
+            self.count + 1
+        } else {
+            0
+        };
+        *self = Self {
+            dir: -self.dir,
+            travel: self.back,
+            back: 0.0,
+            t_begin: self.t_turn,
+            t_turn: None,
+            count,
+        };
+        self.count >= settings.strokes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Where the cursor is along x, and when.
+    struct Cursor {
+        x: i32,
+        t: SystemTime,
+    }
+
+    impl Cursor {
+        fn start(strokes: &mut Strokes) -> Self {
+            let cursor = Self {
+                x: 0,
+                t: SystemTime::UNIX_EPOCH,
+            };
+            strokes.moved(Vec2I::new(0, 0), &PettingSettings::default(), cursor.t);
+            cursor
+        }
+        /// Moves to `to` in steps of 5 pixels, one every `step`. Returns whether that petted her.
+        fn sweep(&mut self, strokes: &mut Strokes, to: i32, step: Duration) -> bool {
+            let mut petted = false;
+            while self.x != to {
+                self.x += 5 * (to - self.x).signum();
+                self.t += step;
+                petted |= strokes.moved(Vec2I::new(self.x, 0), &PettingSettings::default(), self.t);
+            }
+            petted
+        }
+    }
+
+    const QUICK: Duration = Duration::from_millis(10);
+
+    #[test]
+    fn full_strokes() {
+        let mut strokes = Strokes::default();
+        let mut cursor = Cursor::start(&mut strokes);
+        // each turn back ends a stroke, and it takes 4 of them
+        for to in [30, 0, 30, 0] {
+            assert!(!cursor.sweep(&mut strokes, to, QUICK));
+        }
+        assert!(cursor.sweep(&mut strokes, 30, QUICK));
+        // and then it starts over
+        for to in [0, 30, 0] {
+            assert!(!cursor.sweep(&mut strokes, to, QUICK));
+        }
+    }
+
+    #[test]
+    fn jitter_is_ignored() {
+        let mut strokes = Strokes::default();
+        let mut cursor = Cursor::start(&mut strokes);
+        for _ in 0..20 {
+            assert!(!cursor.sweep(&mut strokes, 10, QUICK));
+            assert!(!cursor.sweep(&mut strokes, 0, QUICK));
+        }
+
+        // wiggling back a bit in the middle of a stroke doesn't break it up
+        let mut strokes = Strokes::default();
+        let mut cursor = Cursor::start(&mut strokes);
+        for to in [30, 0, 30, 20, 30, 0] {
+            assert!(!cursor.sweep(&mut strokes, to, QUICK));
+        }
+        assert!(cursor.sweep(&mut strokes, 30, QUICK));
+    }
+
+    #[test]
+    fn slow_strokes_are_forgotten() {
+        let mut strokes = Strokes::default();
+        let mut cursor = Cursor::start(&mut strokes);
+        for to in [30, 0, 30, 0, 30, 0, 30, 0] {
+            assert!(!cursor.sweep(&mut strokes, to, Duration::from_millis(150)));
+        }
+
+        // stopping for a while forgets the strokes before it
+        let mut strokes = Strokes::default();
+        let mut cursor = Cursor::start(&mut strokes);
+        for to in [30, 0, 30] {
+            assert!(!cursor.sweep(&mut strokes, to, QUICK));
+        }
+        cursor.t += Duration::from_secs(1);
+        for to in [0, 30, 0, 30] {
+            assert!(!cursor.sweep(&mut strokes, to, QUICK));
+        }
+        assert!(cursor.sweep(&mut strokes, 0, QUICK));
+    }
+}
@@ -0,0 +1,76 @@
+use crate::util::RectI;
+
+use super::ext_window::list_window_rects;
+
+/// Narrowest strip worth standing on, in pixels.
+const MIN_PLATFORM_WIDTH: i32 = 60;
+
+/// Something she can stand on while roaming: a horizontal strip of the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Platform {
+    /// Window whose top edge this is, or `None` for the bottom of a monitor's work area.
+    pub hwnd: Option<isize>,
+    pub y: i32,
+    /// Left end, inclusive.
+    pub x0: i32,
+    /// Right end, exclusive.
+    pub x1: i32,
+}
+impl Platform {
+    pub fn holds(self, x: i32) -> bool {
+        (self.x0..self.x1).contains(&x)
+    }
+    /// How far `x` is from the nearest point on the platform.
+    pub fn distance_to(self, x: i32) -> i32 {
+        (self.x0 - x).max(x - (self.x1 - 1)).max(0)
+    }
+}
+
+/// Everything she can stand on right now. The top edge of a window only counts where no other
+/// window covers it and there's `headroom` pixels of the same monitor above it, so maximized
+/// windows don't count.
+pub fn find_platforms(work_areas: &[RectI], headroom: i32) -> Vec<Platform> {
+    let windows = list_window_rects();
+    let mut platforms = vec![];
+    for (i, &(hwnd, rect)) in windows.iter().enumerate() {
+        let y = rect.pos.y;
+        let mut spans = vec![(rect.pos.x, rect.pos.x + rect.dim.x)];
+        for &(_, above) in &windows[..i] {
+            if (above.pos.y..above.pos.y + above.dim.y).contains(&y) {
+                spans = subtract(spans, (above.pos.x, above.pos.x + above.dim.x));
+            }
+        }
+        for area in work_areas {
+            if y - headroom < area.pos.y || y >= area.pos.y + area.dim.y {
+                continue;
+            }
+            for &(x0, x1) in &spans {
+                let (x0, x1) = (x0.max(area.pos.x), x1.min(area.pos.x + area.dim.x));
+                if x1 - x0 >= MIN_PLATFORM_WIDTH {
+                    platforms.push(Platform {
+                        hwnd: Some(hwnd),
+                        y,
+                        x0,
+                        x1,
+                    });
+                }
+            }
+        }
+    }
+    platforms.extend(work_areas.iter().map(|area| Platform {
+        hwnd: None,
+        y: area.pos.y + area.dim.y,
+        x0: area.pos.x,
+        x1: area.pos.x + area.dim.x,
+    }));
+    platforms
+}
+
+/// Cuts `(c0, c1)` out of each of the `(start, end)` spans.
+fn subtract(spans: Vec<(i32, i32)>, (c0, c1): (i32, i32)) -> Vec<(i32, i32)> {
+    spans
+        .into_iter()
+        .flat_map(|(s0, s1)| [(s0, s1.min(c0)), (s0.max(c1), s1)])
+        .filter(|(s0, s1)| s0 < s1)
+        .collect()
+}
@@ -0,0 +1,328 @@
+use std::time::SystemTime;
+
+use crate::util::json::Json;
+
+use super::{behavior::Event, system::local_time};
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+/// How long (on her clock) after yawning or nodding off she does it again, if it's still that
+/// time of day and she's up and free to.
+const REPEAT_MINUTES: u32 = 15;
+
+/// A moment on the local wall clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LocalTime {
+    /// Days since 1970-01-01.
+    pub day: i64,
+    /// Minutes since midnight, from 0 to 1439.
+    pub minute: u32,
+}
+impl LocalTime {
+    /// Hour of the day, from 0 to 23.
+    pub fn hour(self) -> u32 {
+        self.minute / 60
+    }
+    fn plus_minutes(self, minutes: i64) -> Self {
+        let total = self.day * MINUTES_PER_DAY as i64 + self.minute as i64 + minutes;
+        Self {
+            day: total.div_euclid(MINUTES_PER_DAY as i64),
+            minute: total.rem_euclid(MINUTES_PER_DAY as i64) as u32,
+        }
+    }
+}
+/// Days from 1970-01-01 to the given date, counting back for dates before it.
+pub fn days_since_epoch(year: i64, month: i64, day: i64) -> i64 {
+    // counting years from March, so that leap days come at the end of them
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// What time she thinks it is: the system's, or a made-up time for trying out her schedule
+/// without staying up all night.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Clock {
+    fake: Option<FakeClock>,
+}
+#[derive(Debug, Clone, Copy)]
+struct FakeClock {
+    start: LocalTime,
+    started: SystemTime,
+    /// How many minutes go by on it per real minute.
+    speed: f64,
+}
+
+impl Clock {
+    /// A clock that starts at `minute` past midnight today and runs `speed` times as fast as a
+    /// real one.
+    pub fn fake(minute: u32, speed: f64) -> Self {
+        Self {
+            fake: Some(FakeClock {
+                start: LocalTime {
+                    day: local_time().day,
+                    minute: minute % MINUTES_PER_DAY,
+                },
+                started: SystemTime::now(),
+                speed,
+            }),
+        }
+    }
+
+    pub fn now(&self) -> LocalTime {
+        match self.fake {
+            None => local_time(),
+            Some(fake) => {
+                let real = fake.started.elapsed().unwrap_or_default();
+                fake.start
+                    .plus_minutes((real.as_secs_f64() / 60.0 * fake.speed) as i64)
+            }
+        }
+    }
+}
+
+/// Reads a time of day like `"23:30"` into minutes past midnight.
+pub fn parse_time_of_day(s: &str) -> Option<u32> {
+    let (hours, minutes) = s.split_once(':')?;
+    if minutes.len() != 2 {
+        return None;
+    }
+    let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+fn format_time_of_day(minute: u32) -> String {
+    format!("{:02}:{:02}", minute / 60, minute % 60)
+}
+
+/// Part of her day, going by the clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    /// When she starts yawning.
+    Evening,
+    /// When she goes to sleep.
+    Night,
+}
+impl Period {
+    /// What she's told when it starts.
+    pub fn event(self) -> Event {
+        match self {
+            Self::Day => Event::Morning,
+            Self::Evening => Event::Evening,
+            Self::Night => Event::Night,
+        }
+    }
+}
+
+/// When each part of her day starts, in minutes past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduleTimes {
+    pub evening: u32,
+    pub night: u32,
+    pub morning: u32,
+}
+
+impl Default for ScheduleTimes {
+    fn default() -> Self {
+        Self {
+            evening: 21 * 60,
+            night: 23 * 60,
+            morning: 7 * 60,
+        }
+    }
+}
+
+impl ScheduleTimes {
+    /// Which part of the day `minute` (past midnight) is in: whichever started last.
+    pub fn period_at(&self, minute: u32) -> Period {
+        let since = |start: u32| (minute + MINUTES_PER_DAY - start) % MINUTES_PER_DAY;
+        [
+            (Period::Day, self.morning),
+            (Period::Evening, self.evening),
+            (Period::Night, self.night),
+        ]
+        .into_iter()
+        .min_by_key(|(_, start)| since(*start))
+        .unwrap()
+        .0
+    }
+
+    /// Reads `{ "evening": "21:00", "night": "23:00", "morning": "07:00" }`, where any of them
+    /// can be left out to keep its default. Returns what's wrong with it otherwise.
+    pub fn from_json(json: &Json) -> Result<Self, Vec<String>> {
+        let mut times = Self::default();
+        let mut errors = vec![];
+        let Some(entries) = json.as_object() else {
+            return Err(vec![format!(
+                "expected an object, found {}",
+                json.type_name()
+            )]);
+        };
+        for (key, value) in entries {
+            let time = match key.as_str() {
+                "evening" => &mut times.evening,
+                "night" => &mut times.night,
+                "morning" => &mut times.morning,
+                _ => {
+                    errors.push(format!("{}: unknown setting", key));
+                    continue;
+                }
+            };
+            match value.as_str().and_then(parse_time_of_day) {
+                Some(minute) => *time = minute,
+                None => errors.push(format!("{}: expected a time like \"21:30\"", key)),
+            }
+        }
+        if errors.is_empty() {
+            Ok(times)
+        } else {
+            Err(errors)
+        }
+    }
+    pub fn to_json(self) -> Json {
+        Json::Object(
+            [
+                ("evening", self.evening),
+                ("night", self.night),
+                ("morning", self.morning),
+            ]
+            .into_iter()
+            .map(|(key, minute)| (key.to_string(), Json::String(format_time_of_day(minute))))
+            .collect(),
+        )
+    }
+}
+
+/// Tells her when it's time to yawn, go to bed and get up. Each part of the day's event is held
+/// on to until she's in a state that does something with it (or the next part of the day starts),
+/// and evening and night come around again every [`REPEAT_MINUTES`] after she acts on them, in
+/// case she got woken up.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    times: ScheduleTimes,
+    period: Period,
+    /// The event waiting to be acted on, and from when.
+    pending: Option<(Event, LocalTime)>,
+}
+
+impl Schedule {
+    /// Starts off with the event of the part of the day it is `now`, so that she goes to bed
+    /// right away if it's already night.
+    pub fn new(times: ScheduleTimes, now: LocalTime) -> Self {
+        let period = times.period_at(now.minute);
+        Self {
+            times,
+            period,
+            pending: Some((period.event(), now)),
+        }
+    }
+
+    /// The event she should act on at `now`, if any. Call [`Schedule::handled`] once she does.
+    pub fn due(&mut self, now: LocalTime) -> Option<Event> {
+        let period = self.times.period_at(now.minute);
+        if period != self.period {
+            self.period = period;
+            self.pending = Some((period.event(), now));
+        }
+        self.pending
+            .filter(|(_, from)| now >= *from)
+            .map(|(event, _)| event)
+    }
+
+    /// She acted on the event [`Schedule::due`] gave at `now`.
+    pub fn handled(&mut self, now: LocalTime) {
+        self.pending = match self.pending {
+            Some((event, _)) if event != Event::Morning => {
+                Some((event, now.plus_minutes(REPEAT_MINUTES as i64)))
+            }
+            _ => None,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: i64, hour: u32, minute: u32) -> LocalTime {
+        LocalTime {
+            day,
+            minute: hour * 60 + minute,
+        }
+    }
+
+    #[test]
+    fn days_since_epoch_known_dates() {
+        assert_eq!(days_since_epoch(1970, 1, 1), 0);
+        assert_eq!(days_since_epoch(1969, 12, 31), -1);
+        assert_eq!(days_since_epoch(2000, 3, 1), 11017);
+        assert_eq!(days_since_epoch(2024, 2, 29), 19782);
+        assert_eq!(days_since_epoch(2024, 3, 1), 19783);
+        // not a leap year, being a century that isn't a multiple of 400
+        assert_eq!(days_since_epoch(1900, 3, 1), -25508);
+    }
+
+    #[test]
+    fn plus_minutes_crosses_days() {
+        assert_eq!(at(5, 23, 50).plus_minutes(20), at(6, 0, 10));
+        assert_eq!(at(5, 0, 5).plus_minutes(-10), at(4, 23, 55));
+        assert_eq!(at(0, 12, 0).plus_minutes(3 * 24 * 60), at(3, 12, 0));
+    }
+
+    #[test]
+    fn period_at_wraps_around_midnight() {
+        let times = ScheduleTimes::default();
+        assert_eq!(times.period_at(12 * 60), Period::Day);
+        assert_eq!(times.period_at(21 * 60), Period::Evening);
+        assert_eq!(times.period_at(23 * 60 + 30), Period::Night);
+        assert_eq!(times.period_at(0), Period::Night);
+        assert_eq!(times.period_at(7 * 60 - 1), Period::Night);
+        assert_eq!(times.period_at(7 * 60), Period::Day);
+
+        // bedtime after midnight
+        let late = ScheduleTimes {
+            evening: 23 * 60,
+            night: 60,
+            morning: 9 * 60,
+        };
+        assert_eq!(late.period_at(23 * 60 + 30), Period::Evening);
+        assert_eq!(late.period_at(30), Period::Evening);
+        assert_eq!(late.period_at(60), Period::Night);
+        assert_eq!(late.period_at(8 * 60), Period::Night);
+        assert_eq!(late.period_at(9 * 60), Period::Day);
+    }
+
+    #[test]
+    fn morning_is_handled_once() {
+        let mut schedule = Schedule::new(ScheduleTimes::default(), at(0, 12, 0));
+        assert_eq!(schedule.due(at(0, 12, 0)), Some(Event::Morning));
+        schedule.handled(at(0, 12, 0));
+        assert_eq!(schedule.due(at(0, 12, 30)), None);
+        assert_eq!(schedule.due(at(0, 20, 59)), None);
+    }
+
+    #[test]
+    fn evening_and_night_come_around_again() {
+        let mut schedule = Schedule::new(ScheduleTimes::default(), at(0, 12, 0));
+        schedule.handled(at(0, 12, 0));
+
+        assert_eq!(schedule.due(at(0, 21, 0)), Some(Event::Evening));
+        // held on to until she acts on it
+        assert_eq!(schedule.due(at(0, 21, 5)), Some(Event::Evening));
+        schedule.handled(at(0, 21, 5));
+        assert_eq!(schedule.due(at(0, 21, 19)), None);
+        assert_eq!(schedule.due(at(0, 21, 20)), Some(Event::Evening));
+
+        assert_eq!(schedule.due(at(0, 23, 0)), Some(Event::Night));
+        schedule.handled(at(0, 23, 50));
+        assert_eq!(schedule.due(at(1, 0, 4)), None);
+        assert_eq!(schedule.due(at(1, 0, 5)), Some(Event::Night));
+
+        // morning replaces night even if she never got woken up
+        assert_eq!(schedule.due(at(1, 7, 0)), Some(Event::Morning));
+        schedule.handled(at(1, 7, 0));
+        assert_eq!(schedule.due(at(1, 7, 15)), None);
+    }
+}
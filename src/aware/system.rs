@@ -0,0 +1,98 @@
+use std::{io, path::Path};
+
+use windows::{
+    core::HSTRING,
+    Win32::{
+        Globalization::GetUserDefaultLocaleName,
+        Storage::FileSystem::{MoveFileExW, MOVE_FILE_FLAGS},
+        System::{Diagnostics::Debug::MessageBeep, SystemInformation::GetLocalTime},
+        UI::Input::KeyboardAndMouse::{GetAsyncKeyState, VK_LBUTTON, VK_RBUTTON},
+        UI::WindowsAndMessaging::{
+            AppendMenuW, CreatePopupMenu, DestroyMenu, GetForegroundWindow, GetSystemMetrics,
+            SetForegroundWindow, TrackPopupMenu, MB_ICONASTERISK, MF_GRAYED, MF_STRING,
+            SM_SWAPBUTTON, TPM_RETURNCMD, TPM_RIGHTBUTTON,
+        },
+    },
+};
+
+use super::{
+    ext_window::cursor_pos,
+    schedule::{days_since_epoch, LocalTime},
+};
+
+/// Plays the system's notification sound.
+pub fn beep() {
+    let _ = unsafe { MessageBeep(MB_ICONASTERISK) };
+}
+
+/// The locale the user has picked in Windows' settings, like `en-US`.
+pub fn user_locale() -> Option<String> {
+    // LOCALE_NAME_MAX_LENGTH
+    let mut buf = [0u16; 85];
+    // Counts the NUL at the end, or is 0 if it failed.
+    let len = unsafe { GetUserDefaultLocaleName(&mut buf) };
+    (len > 1).then(|| String::from_utf16_lossy(&buf[..len as usize - 1]))
+}
+
+/// Whether the left mouse button is down, no matter which window the cursor is over. That's the
+/// physical right one for people who've swapped them in Windows' settings, same as everywhere
+/// else.
+pub fn left_button_down() -> bool {
+    // GetAsyncKeyState goes by the physical buttons, unlike window messages
+    let swapped = unsafe { GetSystemMetrics(SM_SWAPBUTTON) } != 0;
+    let button = if swapped { VK_RBUTTON } else { VK_LBUTTON };
+    // the top bit is whether it's down
+    let state = unsafe { GetAsyncKeyState(button.0 as i32) };
+    state < 0
+}
+
+/// Shows a menu of `items` at the cursor, with the ones that are `false` greyed out, and waits
+/// until one gets picked or the menu gets closed. Returns the index of the one that was picked.
+pub fn popup_menu(items: &[(String, bool)]) -> Option<usize> {
+    let cursor = cursor_pos()?;
+    unsafe {
+        let menu = CreatePopupMenu().ok()?;
+        for (i, (label, enabled)) in items.iter().enumerate() {
+            let flags = if *enabled {
+                MF_STRING
+            } else {
+                MF_STRING | MF_GRAYED
+            };
+            // 0 is what picking nothing returns, so the ids start at 1
+            let _ = AppendMenuW(menu, flags, i + 1, &HSTRING::from(label.as_str()));
+        }
+        // The menu only closes when clicking elsewhere if its owner is in the foreground, which
+        // the window that was just clicked on is.
+        let owner = GetForegroundWindow();
+        let _ = SetForegroundWindow(owner);
+        let picked = TrackPopupMenu(
+            menu,
+            TPM_RETURNCMD | TPM_RIGHTBUTTON,
+            cursor.x,
+            cursor.y,
+            0,
+            owner,
+            None,
+        );
+        let _ = DestroyMenu(menu);
+        (picked.0 > 0).then(|| picked.0 as usize - 1)
+    }
+}
+
+/// What the system clock says, in local time.
+pub fn local_time() -> LocalTime {
+    let t = unsafe { GetLocalTime() };
+    LocalTime {
+        day: days_since_epoch(t.wYear as i64, t.wMonth as i64, t.wDay as i64),
+        minute: t.wHour as u32 * 60 + t.wMinute as u32,
+    }
+}
+
+/// Moves `from` to `to`, which has to be on the same drive. Unlike [`std::fs::rename`], it fails
+/// with [`io::ErrorKind::AlreadyExists`] instead of replacing whatever is at `to` already.
+pub fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    // no MOVEFILE_REPLACE_EXISTING
+    unsafe { MoveFileExW(&HSTRING::from(from), &HSTRING::from(to), MOVE_FILE_FLAGS(0)) }
+        // the error it gives is an HRESULT, which io::Error can't tell the kind of
+        .map_err(|_| io::Error::last_os_error())
+}
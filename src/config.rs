@@ -0,0 +1,350 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    aware::{
+        difficulty::{Difficulty, PRESETS},
+        petting::PettingSettings,
+        schedule::{Clock, ScheduleTimes},
+    },
+    feeding::FileDrops,
+    round::{Mode, MODES},
+    util::json::{load_override, parse_file, read_file, FileError, FileErrorKind, Json},
+};
+
+/// File next to the game that, if present, changes the settings.
+const CONFIG_PATH: &str = "config.json";
+const CONFIG_WHAT: &str = "config file";
+/// Most creatures there can be at once, since each one gets her own window.
+const MAX_CREATURES: usize = 8;
+/// Most hides a time-attack round can have.
+pub const MAX_ROUND_HIDES: u32 = 50;
+
+/// Settings for the whole game.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// How many creatures there are.
+    pub creatures: usize,
+    /// Sprite sheet (path to its JSON file) each creature wears, in order. Creatures past the end
+    /// of the list, or with `None`, wear the default one.
+    pub skins: Vec<Option<PathBuf>>,
+    /// How hard the creatures are to find.
+    pub difficulty: Difficulty,
+    /// Name of the preset `difficulty` came from.
+    pub difficulty_preset: String,
+    pub mode: Mode,
+    /// How many times she has to be found to finish a time-attack round.
+    pub round_hides: u32,
+    /// Language to show everything in, like `es` or `pt-BR`, or `None` for the system's.
+    pub language: Option<String>,
+    /// When she gets sleepy, goes to bed and gets up, or `None` to keep her up around the clock.
+    pub schedule: Option<ScheduleTimes>,
+    /// What time she thinks it is. Only ever set from the command line.
+    pub clock: Clock,
+    /// How moving the cursor back and forth over her pets her, or `None` if she can't be petted.
+    pub petting: Option<PettingSettings>,
+    /// What happens to files dropped on her, or `None` if she doesn't take them.
+    pub file_drops: Option<FileDrops>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            creatures: 1,
+            skins: vec![],
+            difficulty: Difficulty::default(),
+            difficulty_preset: "normal".to_string(),
+            mode: Mode::Endless,
+            round_hides: 5,
+            language: None,
+            schedule: Some(ScheduleTimes::default()),
+            clock: Clock::default(),
+            petting: Some(PettingSettings::default()),
+            file_drops: Some(FileDrops::Eat),
+        }
+    }
+}
+
+/// Settings given on the command line. They win over the config file, but don't get remembered.
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    pub mode: Option<Mode>,
+    pub round_hides: Option<u32>,
+    /// Time of day to start a fake clock at, in minutes past midnight.
+    pub clock: Option<u32>,
+    /// How many times as fast as a real one the fake clock runs.
+    pub clock_speed: Option<f64>,
+}
+
+impl Overrides {
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(mode) = self.mode {
+            config.mode = mode;
+        }
+        if let Some(round_hides) = self.round_hides {
+            config.round_hides = round_hides;
+        }
+        if self.clock.is_some() || self.clock_speed.is_some() {
+            config.clock = Clock::fake(
+                self.clock.unwrap_or_else(|| Clock::default().now().minute),
+                self.clock_speed.unwrap_or(1.0),
+            );
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config file if there is one, and falls back to `fallback` if there isn't (or,
+    /// after reporting what's wrong, if it can't be used).
+    pub fn load(fallback: Self) -> Self {
+        load_override(CONFIG_PATH, Self::load_file, "the last settings", || {
+            fallback
+        })
+    }
+
+    pub fn load_file(path: &Path) -> Result<Self, FileError> {
+        Self::parse(&read_file(path, CONFIG_WHAT)?)
+    }
+
+    /// Parses a config file. Settings it leaves out keep their defaults.
+    pub fn parse(src: &str) -> Result<Self, FileError> {
+        Self::from_json(&parse_file(src, CONFIG_WHAT)?)
+    }
+
+    /// Reads settings laid out the same way as in a config file.
+    pub fn from_json(json: &Json) -> Result<Self, FileError> {
+        let mut config = Self::default();
+        let mut errors = vec![];
+
+        let Some(entries) = json.as_object() else {
+            return Err(FileError::new(
+                CONFIG_WHAT,
+                FileErrorKind::Invalid(vec![format!(
+                    "<root>: expected an object, found {}",
+                    json.type_name()
+                )]),
+            ));
+        };
+        // applied after the preset, whichever order they're in
+        let mut adaptive = None;
+        let mut hint_after = None;
+        let mut hint_step = None;
+        for (key, value) in entries {
+            match key.as_str() {
+                "difficulty" => match value
+                    .as_str()
+                    .and_then(|name| Some((name, Difficulty::preset(name)?)))
+                {
+                    Some((name, difficulty)) => {
+                        config.difficulty = difficulty;
+                        config.difficulty_preset = name.to_string();
+                    }
+                    None => errors.push(format!(
+                        "difficulty: expected one of {}",
+                        PRESETS
+                            .iter()
+                            .map(|p| format!("\"{}\"", p))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )),
+                },
+                "adaptive_difficulty" => match value.as_bool() {
+                    Some(b) => adaptive = Some(b),
+                    None => errors.push(format!(
+                        "adaptive_difficulty: expected true or false, found {}",
+                        value.type_name()
+                    )),
+                },
+                "mode" => match value.as_str().and_then(Mode::from_name) {
+                    Some(mode) => config.mode = mode,
+                    None => errors.push(format!(
+                        "mode: expected one of {}",
+                        MODES
+                            .iter()
+                            .map(|m| format!("\"{}\"", m))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )),
+                },
+                "round_hides" => match value.as_f64() {
+                    Some(n) if n.fract() == 0.0 && (1.0..=MAX_ROUND_HIDES as f64).contains(&n) => {
+                        config.round_hides = n as u32;
+                    }
+                    _ => errors.push(format!(
+                        "round_hides: expected a whole number from 1 to {}",
+                        MAX_ROUND_HIDES
+                    )),
+                },
+                "hint_after" => match (value, value.as_f64()) {
+                    (Json::Null, _) => hint_after = Some(None),
+                    (_, Some(secs)) if secs >= 0.0 && secs.is_finite() => {
+                        hint_after = Some(Some(Duration::from_secs_f64(secs)));
+                    }
+                    _ => errors.push(
+                        "hint_after: expected a number of seconds, or null for no hints"
+                            .to_string(),
+                    ),
+                },
+                "hint_step" => match value.as_f64() {
+                    Some(secs) if secs > 0.0 && secs.is_finite() => {
+                        hint_step = Some(Duration::from_secs_f64(secs));
+                    }
+                    _ => errors.push("hint_step: expected a number of seconds above 0".to_string()),
+                },
+                "creatures" => match value.as_f64() {
+                    Some(n) if n.fract() == 0.0 && (1.0..=MAX_CREATURES as f64).contains(&n) => {
+                        config.creatures = n as usize;
+                    }
+                    _ => errors.push(format!(
+                        "creatures: expected a whole number from 1 to {}",
+                        MAX_CREATURES
+                    )),
+                },
+                "skins" => match value.as_array() {
+                    Some(skins) => {
+                        config.skins = skins
+                            .iter()
+                            .enumerate()
+                            .map(|(i, skin)| match skin {
+                                Json::Null => None,
+                                Json::String(path) => Some(PathBuf::from(path)),
+                                other => {
+                                    errors.push(format!(
+                                        "skins[{}]: expected the path to a sprite sheet's JSON \
+                                         file or null, found {}",
+                                        i,
+                                        other.type_name()
+                                    ));
+                                    None
+                                }
+                            })
+                            .collect();
+                    }
+                    None => errors.push(format!(
+                        "skins: expected an array, found {}",
+                        value.type_name()
+                    )),
+                },
+                "language" => match value {
+                    Json::Null => config.language = None,
+                    Json::String(language) if !language.is_empty() => {
+                        config.language = Some(language.clone());
+                    }
+                    _ => errors.push(
+                        "language: expected a language like \"es\" or \"pt-BR\", or null for \
+                         the system's"
+                            .to_string(),
+                    ),
+                },
+                "schedule" => match value {
+                    Json::Null => config.schedule = None,
+                    _ => match ScheduleTimes::from_json(value) {
+                        Ok(times) => config.schedule = Some(times),
+                        Err(problems) => errors.extend(
+                            problems
+                                .into_iter()
+                                .map(|problem| format!("schedule.{}", problem)),
+                        ),
+                    },
+                },
+                "petting" => match value {
+                    Json::Null => config.petting = None,
+                    _ => match PettingSettings::from_json(value) {
+                        Ok(settings) => config.petting = Some(settings),
+                        Err(problems) => errors.extend(
+                            problems
+                                .into_iter()
+                                .map(|problem| format!("petting.{}", problem)),
+                        ),
+                    },
+                },
+                "file_drops" => match value {
+                    Json::Null => config.file_drops = None,
+                    _ => match FileDrops::from_json(value) {
+                        Ok(file_drops) => config.file_drops = Some(file_drops),
+                        Err(problem) => errors.push(format!("file_drops: {}", problem)),
+                    },
+                },
+                _ => errors.push(format!("{}: unknown setting", key)),
+            }
+        }
+
+        if let Some(adaptive) = adaptive {
+            config.difficulty.adaptive = adaptive;
+        }
+        if let Some(hint_after) = hint_after {
+            config.difficulty.hint_after = hint_after;
+        }
+        if let Some(hint_step) = hint_step {
+            config.difficulty.hint_step = hint_step;
+        }
+
+        FileError::check(CONFIG_WHAT, errors)?;
+        Ok(config)
+    }
+
+    /// Writes the settings out the same way as in a config file.
+    pub fn to_json(&self) -> Json {
+        Json::Object(vec![
+            ("creatures".to_string(), Json::Number(self.creatures as f64)),
+            (
+                "skins".to_string(),
+                Json::Array(
+                    self.skins
+                        .iter()
+                        .map(|skin| match skin {
+                            Some(path) => Json::String(path.to_string_lossy().into_owned()),
+                            None => Json::Null,
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "difficulty".to_string(),
+                Json::String(self.difficulty_preset.clone()),
+            ),
+            (
+                "adaptive_difficulty".to_string(),
+                Json::Bool(self.difficulty.adaptive),
+            ),
+            (
+                "hint_after".to_string(),
+                self.difficulty
+                    .hint_after
+                    .map_or(Json::Null, |t| Json::Number(t.as_secs_f64())),
+            ),
+            (
+                "hint_step".to_string(),
+                Json::Number(self.difficulty.hint_step.as_secs_f64()),
+            ),
+            (
+                "mode".to_string(),
+                Json::String(self.mode.name().to_string()),
+            ),
+            (
+                "round_hides".to_string(),
+                Json::Number(self.round_hides as f64),
+            ),
+            (
+                "language".to_string(),
+                self.language.clone().map_or(Json::Null, Json::String),
+            ),
+            (
+                "schedule".to_string(),
+                self.schedule.map_or(Json::Null, ScheduleTimes::to_json),
+            ),
+            (
+                "petting".to_string(),
+                self.petting.map_or(Json::Null, PettingSettings::to_json),
+            ),
+            (
+                "file_drops".to_string(),
+                self.file_drops
+                    .as_ref()
+                    .map_or(Json::Null, FileDrops::to_json),
+            ),
+        ])
+    }
+}
@@ -0,0 +1,589 @@
+use std::{
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    aware::system::rename_no_replace,
+    messages::Messages,
+    save::{data_dir, load_or_default, load_versioned, write_json},
+    util::json::{FileError, FileErrorKind, Json},
+};
+
+/// Version of the undo log file layout this build writes.
+const UNDO_LOG_VERSION: u32 = 1;
+const UNDO_LOG_FILE: &str = "undo.json";
+const UNDO_LOG_WHAT: &str = "undo log";
+/// Folder in the data directory that files she eats end up in, so they can still be gotten back.
+const STOMACH_DIR: &str = "stomach";
+/// How many drops the undo log remembers, not counting ones with anything still in her stomach.
+/// Those are never forgotten however many there are, since the log is the only way to get those
+/// files back. Files from older drops stay wherever they were put.
+const KEEP_BATCHES: usize = 50;
+
+/// What happens to files dropped on her.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileDrops {
+    /// She eats them, which puts them in her stomach.
+    Eat,
+    /// They get put in this folder, like a trash or an inbox.
+    MoveTo(PathBuf),
+}
+
+impl FileDrops {
+    /// Either `"eat"` or `{ "move_to": folder }`.
+    pub fn from_json(json: &Json) -> Result<Self, String> {
+        match (json, json.as_object()) {
+            (Json::String(s), _) if s == "eat" => Ok(Self::Eat),
+            (_, Some([(key, Json::String(folder))])) if key == "move_to" && !folder.is_empty() => {
+                Ok(Self::MoveTo(PathBuf::from(folder)))
+            }
+            _ => Err("expected \"eat\" or {\"move_to\": <folder>}".to_string()),
+        }
+    }
+    pub fn to_json(&self) -> Json {
+        match self {
+            Self::Eat => Json::String("eat".to_string()),
+            Self::MoveTo(folder) => Json::Object(vec![(
+                "move_to".to_string(),
+                Json::String(folder.to_string_lossy().into_owned()),
+            )]),
+        }
+    }
+    /// Folder the files go into, or `None` if she has no stomach on this system.
+    pub fn folder(&self) -> Option<PathBuf> {
+        match self {
+            Self::Eat => Some(data_dir()?.join(STOMACH_DIR)),
+            Self::MoveTo(folder) => Some(folder.clone()),
+        }
+    }
+}
+
+/// What kind of thing a file is, going by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Folder,
+    Image,
+    Text,
+    Code,
+    Audio,
+    Video,
+    Archive,
+    Document,
+    Program,
+    Other,
+}
+
+impl FileKind {
+    pub fn of(path: &Path) -> Self {
+        if path.is_dir() {
+            return Self::Folder;
+        }
+        let ext = path
+            .extension()
+            .and_then(OsStr::to_str)
+            .unwrap_or_default()
+            .to_lowercase();
+        match ext.as_str() {
+            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "svg" | "ico" | "tif" | "tiff"
+            | "heic" | "psd" => Self::Image,
+            "txt" | "md" | "log" | "csv" | "json" | "xml" | "yaml" | "yml" | "toml" | "ini"
+            | "cfg" => Self::Text,
+            "rs" | "c" | "h" | "cpp" | "hpp" | "cs" | "py" | "js" | "ts" | "java" | "go" | "rb"
+            | "php" | "html" | "css" | "sh" | "lua" | "kt" | "swift" => Self::Code,
+            "mp3" | "wav" | "ogg" | "flac" | "m4a" | "aac" | "opus" | "mid" | "midi" => Self::Audio,
+            "mp4" | "mkv" | "avi" | "mov" | "webm" | "wmv" | "flv" => Self::Video,
+            "zip" | "rar" | "7z" | "tar" | "gz" | "bz2" | "xz" | "zst" => Self::Archive,
+            "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" | "odt" | "ods" | "odp"
+            | "rtf" | "epub" => Self::Document,
+            "exe" | "msi" | "dll" | "bat" | "cmd" | "lnk" | "apk" => Self::Program,
+            _ => Self::Other,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Folder => "folder",
+            Self::Image => "image",
+            Self::Text => "text",
+            Self::Code => "code",
+            Self::Audio => "audio",
+            Self::Video => "video",
+            Self::Archive => "archive",
+            Self::Document => "document",
+            Self::Program => "program",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// One file (or folder) that got moved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Moved {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Everything dropped on her at once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batch {
+    pub files: Vec<Moved>,
+    pub when: SystemTime,
+}
+
+/// Moves each of `paths` into `folder`. Ones that can't be moved are reported and left out.
+pub fn move_all(paths: &[PathBuf], folder: &Path) -> Batch {
+    let files = paths
+        .iter()
+        .filter_map(|path| match move_into(path, folder) {
+            Ok(to) => Some(Moved {
+                from: path.clone(),
+                to,
+            }),
+            Err(e) => {
+                eprintln!("could not move {}: {}", path.display(), e);
+                None
+            }
+        })
+        .collect();
+    Batch {
+        files,
+        when: SystemTime::now(),
+    }
+}
+
+/// Moves `path` into `folder`, under a new name if something there is already called the same.
+/// Returns where it ended up.
+fn move_into(path: &Path, folder: &Path) -> io::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "it has no name"))?;
+    fs::create_dir_all(folder)?;
+    for to in free_paths(folder, name) {
+        match move_path(path, &to) {
+            // something got there first after all
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            result => return result.map(|()| to),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "every name for it is taken",
+    ))
+}
+
+/// `folder/name`, then `folder/name (2)` and so on, skipping ones that are taken.
+fn free_paths<'a>(folder: &'a Path, name: &'a OsStr) -> impl Iterator<Item = PathBuf> + 'a {
+    let stem = Path::new(name)
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let ext = Path::new(name)
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    std::iter::once(folder.join(name))
+        .chain((2..=u32::MAX).map(move |n| folder.join(format!("{} ({}){}", stem, n, ext))))
+        .filter(|path| !path.exists())
+}
+
+/// Moves `from` to `to`, failing with [`io::ErrorKind::AlreadyExists`] rather than replacing
+/// anything that's there.
+fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    match rename_no_replace(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Err(e),
+        // files can't be renamed onto another drive, but they can be copied
+        Err(_) if from.is_file() => {
+            // claims the name first, so the copy only ever goes over its own empty file
+            fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(to)?;
+            fs::copy(from, to)
+                .and_then(|_| fs::remove_file(from))
+                .inspect_err(|_| {
+                    let _ = fs::remove_file(to);
+                })
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// What she says about `batch` after eating it (or after putting it in the folder, if `eaten`
+/// isn't set): what kind of file it was and how big, or how many there were.
+pub fn comment(batch: &Batch, eaten: bool, messages: &Messages) -> String {
+    let count = batch.files.len();
+    match (&batch.files[..], eaten) {
+        ([file], true) => {
+            let kind = FileKind::of(&file.to);
+            let name = display_name(&file.from);
+            if kind == FileKind::Folder {
+                let count = fs::read_dir(&file.to).map_or(0, Iterator::count);
+                return messages.get("ate_folder", &[("name", &name), ("count", &count)]);
+            }
+            let size = format_size(fs::metadata(&file.to).map_or(0, |m| m.len()), messages);
+            messages.get(
+                &format!("ate_{}", kind.name()),
+                &[("name", &name), ("size", &size)],
+            )
+        }
+        (_, true) => {
+            let bytes = batch
+                .files
+                .iter()
+                .filter_map(|file| fs::metadata(&file.to).ok())
+                .filter(|m| m.is_file())
+                .map(|m| m.len())
+                .sum();
+            let size = format_size(bytes, messages);
+            messages.get("ate_many", &[("count", &count), ("size", &size)])
+        }
+        (files, false) => {
+            let folder = files[0].to.parent().map(display_name).unwrap_or_default();
+            match files {
+                [file] => messages.get(
+                    "filed",
+                    &[("name", &display_name(&file.from)), ("folder", &folder)],
+                ),
+                _ => messages.get("filed_many", &[("count", &count), ("folder", &folder)]),
+            }
+        }
+    }
+}
+
+/// What she says after giving back `files`, which went back to these paths.
+pub fn comment_on_undo(files: &[PathBuf], messages: &Messages) -> String {
+    match files {
+        [file] => messages.get("gave_back", &[("name", &display_name(file))]),
+        _ => messages.get("gave_back_many", &[("count", &files.len())]),
+    }
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// `bytes` in whichever unit keeps the number small, like `3.4 MB`.
+fn format_size(bytes: u64, messages: &Messages) -> String {
+    const UNITS: [&str; 3] = ["size_kb", "size_mb", "size_gb"];
+    if bytes < 1024 {
+        return messages.get("size_bytes", &[("count", &bytes)]);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    let size = if size < 10.0 {
+        format!("{:.1}", size)
+    } else {
+        format!("{:.0}", size)
+    };
+    messages.get(UNITS[unit], &[("size", &size)])
+}
+
+/// The latest drops, so the files in them can be put back where they came from.
+#[derive(Debug, Clone, Default)]
+pub struct UndoLog {
+    /// Oldest first.
+    batches: Vec<Batch>,
+}
+
+impl UndoLog {
+    pub fn path() -> Option<PathBuf> {
+        Some(data_dir()?.join(UNDO_LOG_FILE))
+    }
+
+    /// Loads the undo log, or starts a fresh one if there isn't one (see [`load_or_default`]).
+    pub fn load() -> Self {
+        load_or_default(Self::path(), UNDO_LOG_WHAT, Self::load_file)
+    }
+
+    pub fn load_file(path: &Path) -> Result<Self, FileError> {
+        load_versioned(path, UNDO_LOG_WHAT, UNDO_LOG_VERSION, Self::parse)
+    }
+
+    fn parse(_version: u32, json: Json, errors: &mut Vec<String>) -> Self {
+        let mut log = Self::default();
+        match json.get("drops").map(|d| (d, d.as_array())) {
+            Some((_, Some(batches))) => {
+                for (i, batch) in batches.iter().enumerate() {
+                    match Batch::from_json(batch) {
+                        Ok(batch) => log.batches.push(batch),
+                        Err(problems) => errors
+                            .extend(problems.into_iter().map(|p| format!("drops[{}].{}", i, p))),
+                    }
+                }
+            }
+            Some((other, None)) => errors.push(format!(
+                "drops: expected an array, found {}",
+                other.type_name()
+            )),
+            None => {}
+        }
+
+        log
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::Object(vec![
+            ("version".to_string(), Json::Number(UNDO_LOG_VERSION as f64)),
+            (
+                "drops".to_string(),
+                Json::Array(self.batches.iter().map(Batch::to_json).collect()),
+            ),
+        ])
+    }
+
+    /// Writes the undo log to the data directory.
+    pub fn write(&self) -> Result<(), FileError> {
+        let path =
+            Self::path().ok_or_else(|| FileError::new(UNDO_LOG_WHAT, FileErrorKind::NoDataDir))?;
+        write_json(&path, UNDO_LOG_WHAT, &self.to_json())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.batches.is_empty()
+    }
+
+    /// Remembers `batch`, forgetting the oldest ones if there are too many, unless they have
+    /// anything left in her stomach (see [`KEEP_BATCHES`]).
+    pub fn push(&mut self, batch: Batch) {
+        self.batches.push(batch);
+        let stomach = FileDrops::Eat.folder();
+        let mut excess = self.batches.len().saturating_sub(KEEP_BATCHES);
+        self.batches.retain(|batch| {
+            let in_stomach = stomach
+                .as_deref()
+                .is_some_and(|stomach| batch.still_in(stomach));
+            if excess == 0 || in_stomach {
+                return true;
+            }
+            excess -= 1;
+            false
+        });
+    }
+
+    /// Puts the files from the latest drop back where they came from. Returns where the ones that
+    /// made it back are, or `None` if there's nothing to undo. Ones that can't be put back are
+    /// reported and stay in the log as a drop of their own, so undoing again can retry them.
+    pub fn undo(&mut self) -> Option<Vec<PathBuf>> {
+        let batch = self.batches.pop()?;
+        let mut restored = vec![];
+        let mut stuck = vec![];
+        for Moved { from, to } in batch.files.into_iter().rev() {
+            let result = from
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|()| move_path(&to, &from))
+                .map_err(|e| match e.kind() {
+                    io::ErrorKind::AlreadyExists => {
+                        io::Error::new(e.kind(), "something else is there now")
+                    }
+                    _ => e,
+                });
+            match result {
+                Ok(()) => restored.push(from),
+                Err(e) => {
+                    eprintln!(
+                        "could not put {} back at {}: {}",
+                        to.display(),
+                        from.display(),
+                        e
+                    );
+                    stuck.push(Moved { from, to });
+                }
+            }
+        }
+        if !stuck.is_empty() {
+            stuck.reverse();
+            self.batches.push(Batch {
+                files: stuck,
+                when: batch.when,
+            });
+        }
+        Some(restored)
+    }
+}
+
+impl Batch {
+    /// Whether any of its files are still where they were put in `folder`.
+    fn still_in(&self, folder: &Path) -> bool {
+        self.files
+            .iter()
+            .any(|file| file.to.starts_with(folder) && file.to.exists())
+    }
+
+    /// Problems are returned without the path to the batch, which the caller adds.
+    fn from_json(json: &Json) -> Result<Self, Vec<String>> {
+        let mut errors = vec![];
+        let when = json
+            .get("when")
+            .and_then(Json::as_f64)
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+            .and_then(|since| SystemTime::UNIX_EPOCH.checked_add(since));
+        let when = match when {
+            Some(when) => when,
+            None => {
+                errors.push("when: expected a number of seconds".to_string());
+                SystemTime::UNIX_EPOCH
+            }
+        };
+        let mut files = vec![];
+        match json.get("files").and_then(Json::as_array) {
+            Some(entries) => {
+                for (i, file) in entries.iter().enumerate() {
+                    let path = |key| file.get(key).and_then(Json::as_str).map(PathBuf::from);
+                    match (path("from"), path("to")) {
+                        (Some(from), Some(to)) => files.push(Moved { from, to }),
+                        _ => errors.push(format!(
+                            "files[{}]: expected {{\"from\": <path>, \"to\": <path>}}",
+                            i
+                        )),
+                    }
+                }
+            }
+            None => errors.push("files: expected an array".to_string()),
+        }
+        if errors.is_empty() {
+            Ok(Self { files, when })
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn to_json(&self) -> Json {
+        let when = self
+            .when
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO);
+        let path = |path: &Path| Json::String(path.to_string_lossy().into_owned());
+        Json::Object(vec![
+            ("when".to_string(), Json::Number(when.as_secs() as f64)),
+            (
+                "files".to_string(),
+                Json::Array(
+                    self.files
+                        .iter()
+                        .map(|file| {
+                            Json::Object(vec![
+                                ("from".to_string(), path(&file.from)),
+                                ("to".to_string(), path(&file.to)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::save::test_dir;
+
+    use super::*;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn free_paths_skip_taken_names() {
+        let dir = test_dir("free-paths");
+        write(&dir.join("a.txt"), "");
+        write(&dir.join("a (2).txt"), "");
+        write(&dir.join("notes"), "");
+        let first = |name: &str| free_paths(&dir, OsStr::new(name)).next().unwrap();
+        assert_eq!(first("b.txt"), dir.join("b.txt"));
+        assert_eq!(first("a.txt"), dir.join("a (3).txt"));
+        assert_eq!(first("notes"), dir.join("notes (2)"));
+        assert_eq!(first("archive.tar.gz"), dir.join("archive.tar.gz"));
+    }
+
+    #[test]
+    fn move_into_keeps_whats_there() {
+        let dir = test_dir("move-into");
+        let (desktop, stomach) = (dir.join("desktop"), dir.join("stomach"));
+        write(&desktop.join("a.txt"), "new");
+        write(&stomach.join("a.txt"), "old");
+
+        let to = move_into(&desktop.join("a.txt"), &stomach).unwrap();
+        assert_eq!(to, stomach.join("a (2).txt"));
+        assert_eq!(fs::read_to_string(&to).unwrap(), "new");
+        assert_eq!(fs::read_to_string(stomach.join("a.txt")).unwrap(), "old");
+        assert!(!desktop.join("a.txt").exists());
+
+        assert!(move_into(&desktop.join("gone.txt"), &stomach).is_err());
+    }
+
+    #[test]
+    fn undo_puts_files_back() {
+        let dir = test_dir("undo");
+        let (desktop, stomach) = (dir.join("desktop"), dir.join("stomach"));
+        let dropped = [desktop.join("a.txt"), desktop.join("sub/b.txt")];
+        let [a, b] = dropped.clone();
+        write(&a, "a");
+        write(&b, "b");
+
+        let mut log = UndoLog::default();
+        log.push(move_all(&dropped[..1], &stomach));
+        log.push(move_all(&dropped[1..], &stomach));
+        assert!(!a.exists() && !b.exists());
+
+        // the latest drop comes back first, folders and all
+        fs::remove_dir(desktop.join("sub")).unwrap();
+        assert_eq!(log.undo(), Some(vec![b.clone()]));
+        assert_eq!(fs::read_to_string(&b).unwrap(), "b");
+        assert_eq!(log.undo(), Some(vec![a.clone()]));
+        assert_eq!(fs::read_to_string(&a).unwrap(), "a");
+        assert!(log.is_empty());
+        assert_eq!(log.undo(), None);
+    }
+
+    #[test]
+    fn stuck_files_stay_in_the_log() {
+        let dir = test_dir("undo-stuck");
+        let (desktop, stomach) = (dir.join("desktop"), dir.join("stomach"));
+        let (a, b) = (desktop.join("a.txt"), desktop.join("b.txt"));
+        write(&a, "a");
+        write(&b, "b");
+
+        let mut log = UndoLog::default();
+        log.push(move_all(&[a.clone(), b.clone()], &stomach));
+        // something new took b's place, which doesn't get replaced
+        write(&b, "new b");
+        assert_eq!(log.undo(), Some(vec![a.clone()]));
+        assert_eq!(fs::read_to_string(&b).unwrap(), "new b");
+        assert!(!log.is_empty());
+
+        fs::remove_file(&b).unwrap();
+        assert_eq!(log.undo(), Some(vec![b.clone()]));
+        assert_eq!(fs::read_to_string(&b).unwrap(), "b");
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn reports_bad_drops() {
+        let mut errors = vec![];
+        let json = Json::parse(
+            r#"{"drops": [{"when": 1e20, "files": []}, {"when": 5, "files": [{"from": "a"}]}]}"#,
+        )
+        .unwrap();
+        let log = UndoLog::parse(UNDO_LOG_VERSION, json, &mut errors);
+        assert!(log.is_empty());
+        assert_eq!(
+            errors,
+            [
+                "drops[0].when: expected a number of seconds",
+                "drops[1].files[0]: expected {\"from\": <path>, \"to\": <path>}",
+            ]
+        );
+    }
+}
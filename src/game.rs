@@ -1,32 +1,403 @@
-use std::time::Duration;
+use std::{
+    f64::consts::PI,
+    fmt::Display,
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
 
 use crate::{
-    aware::creature::Creature,
-    render::renderer::{RenderWindowId, Renderer},
-    util::Vec2I,
+    achievements::{Achievement, Achievements},
+    aware::{
+        behavior::{Behavior, Event},
+        creature::{self, Creature, Report},
+        dialogue::Voice,
+        ext_window::cursor_pos,
+        system::{left_button_down, popup_menu},
+    },
+    config::{Config, Overrides},
+    feeding::{self, FileDrops, UndoLog},
+    include_imageasset,
+    leaderboard::{Entry, Leaderboard},
+    locale,
+    messages::Messages,
+    render::{
+        image_asset::ImageAsset,
+        renderer::{Anchor, RelativeTo, RenderWindowId, RenderableId, Renderer},
+        sprite::Sprite,
+        text::{Fonts, Text},
+    },
+    round::{clock, Mode, Round},
+    save::{data_dir, Save},
+    util::{RectI, Vec2I},
 };
 
-pub struct Game {
-    main_window: RenderWindowId,
+/// How far apart the creatures' home spots are, in pixels.
+const HOME_SPACING: i32 = 150;
+/// How far the timer is from the top of the screen, in pixels.
+const HUD_MARGIN: i32 = 10;
+/// How far the hint arrow is from the edge of the screen, in pixels.
+const ARROW_MARGIN: i32 = 10;
+/// How far the hint arrow bobs towards her, in pixels.
+const ARROW_BOB: f64 = 8.0;
+/// How many times a second the hint arrow bobs.
+const ARROW_BOB_RATE: f64 = 1.5;
+/// Longest a single update steps things along by. The loop stalls while her menu is open, and
+/// she shouldn't jump across the screen once it starts back up.
+const MAX_DT: Duration = Duration::from_millis(100);
 
+pub struct Game {
     // hiding: Option<(ExtWindowInfo, Vec2I, Facing)>,
+    /// Every creature whose window is still open.
+    creatures: Vec<CreatureSlot>,
+    /// Absolute position of the cursor, as of the last time it moved over one of our windows.
+    cursor: Vec2I,
+    /// Whether the left mouse button was down as of the last update, wherever it was.
+    left_down: bool,
+    /// What gets remembered until the next run, as of the last update.
+    save: Save,
+    /// Whether the stats or achievements changed since the save file was last written.
+    unsaved: bool,
+    achievements: Achievements,
+    /// Everything the creatures and the timer can say.
+    messages: Messages,
+    mode: Mode,
+    /// How many finds a time-attack round takes.
+    round_hides: u32,
+    /// The time-attack round being played, or the last one if it's over.
+    round: Option<Round>,
+    leaderboard: Leaderboard,
+    /// Shows the timer in time-attack mode.
+    hud: Option<Hud>,
+    /// What happens to files dropped on her, if she takes them.
+    file_drops: Option<FileDrops>,
+    /// Where the files dropped on her came from, so they can be given back.
+    undo_log: UndoLog,
+}
+
+struct CreatureSlot {
+    /// Which of the save file's creatures she is.
+    index: usize,
+    /// Window she's drawn in.
+    window: RenderWindowId,
+    /// Where the window was last put.
+    rect: RectI,
     creature: Creature,
+    /// [`Creature::changes`] as of when the save file was last written.
+    saved_changes: u64,
+    /// Points her out once she's been hiding for long enough, if it's been needed yet.
+    arrow: Option<HintArrow>,
+}
+
+/// What can be picked from her right-click menu.
+#[derive(Debug, Clone, Copy)]
+enum MenuItem {
+    Hide,
+    Chat,
+    UndoDrop,
+    ExportStats,
+    Quit,
+}
+impl MenuItem {
+    /// Id of the message it's labeled with.
+    fn message(self) -> &'static str {
+        match self {
+            Self::Hide => "menu_hide",
+            Self::Chat => "menu_chat",
+            Self::UndoDrop => "menu_undo_drop",
+            Self::ExportStats => "menu_export_stats",
+            Self::Quit => "menu_quit",
+        }
+    }
+}
+
+/// A window at the edge of the screen with an arrow in it pointing at where she's hiding.
+struct HintArrow {
+    window: RenderWindowId,
+    /// Textures: [nothing, pointing right, pointing left]
+    sprite: RenderableId<Sprite<3>>,
+    /// Size of the arrow.
+    dim: Vec2I,
+    /// Where the window was last put.
+    pos: Vec2I,
+    work_areas: Vec<RectI>,
+    t_begin: SystemTime,
+}
+
+/// A window at the top of the screen with the round's timer in it.
+struct Hud {
+    window: RenderWindowId,
+    text: RenderableId<Text>,
+    /// Absolute position of the middle of the top edge of the screen it's on.
+    top_center: Vec2I,
 }
 
 impl Game {
-    pub fn init(renderer: &mut Renderer) -> Self {
+    pub fn init(renderer: &mut Renderer, overrides: &Overrides) -> Self {
+        let mut save = Save::load();
+        let saved_config = save.settings.as_ref().and_then(|settings| {
+            Config::from_json(settings)
+                .map_err(|e| eprintln!("[save file settings] {}", e))
+                .ok()
+        });
+        let mut config = Config::load(saved_config.unwrap_or_default());
+        save.settings = Some(config.to_json());
+        overrides.apply(&mut config);
+        if save.creatures.len() < config.creatures {
+            save.creatures
+                .resize_with(config.creatures, Default::default);
+        }
+
+        let voice = Voice::load(&locale::detect(config.language.as_deref()));
+        let sheet = creature::load_sheet();
+        let behavior = Behavior::load(&sheet.animation_names());
+
+        let center = renderer.center_pos();
+        let creatures = (0..config.creatures)
+            .map(|i| {
+                let skin = config.skins.get(i).cloned().flatten();
+                let skin = skin.and_then(|path| creature::load_skin(&path, &behavior));
+                let home = center
+                    + Vec2I::new(
+                        (2 * i as i32 - (config.creatures as i32 - 1)) * HOME_SPACING / 2,
+                        0,
+                    );
+                let window = renderer.add_window(Vec2I::new(100, 100), Vec2I::new(500, 500));
+                let mut creature = Creature::new(
+                    renderer,
+                    window,
+                    behavior.clone(),
+                    skin.as_ref().unwrap_or(&sheet),
+                    home,
+                    &config,
+                    voice.clone(),
+                );
+                creature.restore(&save.creatures[i]);
+                CreatureSlot {
+                    index: i,
+                    window,
+                    rect: RectI {
+                        pos: Vec2I::new(100, 100),
+                        dim: Vec2I::new(500, 500),
+                    },
+                    creature,
+                    saved_changes: 0,
+                    arrow: None,
+                }
+            })
+            .collect();
+        let hud = (config.mode == Mode::TimeAttack)
+            .then(|| Hud::new(renderer, center, voice.fonts.clone()));
         Self {
-            main_window: renderer.add_window(Vec2I::new(100, 100), Vec2I::new(500, 500)),
-            creature: Creature::new(renderer),
+            creatures,
+            cursor: Vec2I::new(0, 0),
+            left_down: false,
+            unsaved: true,
+            save,
+            achievements: Achievements::load(),
+            messages: voice.messages.clone(),
+            mode: config.mode,
+            round_hides: config.round_hides,
+            round: None,
+            leaderboard: if config.mode == Mode::TimeAttack {
+                Leaderboard::load()
+            } else {
+                Leaderboard::default()
+            },
+            hud,
+            file_drops: config.file_drops,
+            undo_log: UndoLog::load(),
         }
     }
 
+    /// Writes a summary of the stats in the save file to `path` (see [`Stats::export`]), without
+    /// starting the game.
+    ///
+    /// [`Stats::export`]: crate::stats::Stats::export
+    pub fn export_saved_stats(path: &Path) -> io::Result<()> {
+        let save = Save::load();
+        let achievements = Achievements::load();
+        save.stats
+            .export(path, &achievements.with_ids(&save.achievements))
+    }
+    /// Writes a summary of the stats so far to `stats.json` and `stats.csv` in the data directory.
+    fn export_stats(&self) {
+        let Some(dir) = data_dir() else {
+            return;
+        };
+        let unlocked = self.achievements.with_ids(&self.save.achievements);
+        for file in ["stats.json", "stats.csv"] {
+            let path = dir.join(file);
+            if let Err(e) = self.save.stats.export(&path, &unlocked) {
+                eprintln!("could not write {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    fn creature_in(&mut self, window: RenderWindowId) -> Option<&mut Creature> {
+        self.creatures
+            .iter_mut()
+            .find(|slot| slot.window == window)
+            .map(|slot| &mut slot.creature)
+    }
+
+    /// Writes the save file if something worth remembering changed since it was last written, or
+    /// regardless if `force` is set. Where the creatures are and how they feel change all the
+    /// time, so they alone aren't worth writing the file for.
+    fn write_save(&mut self, force: bool) {
+        let changed = self
+            .creatures
+            .iter()
+            .any(|slot| slot.creature.changes() != slot.saved_changes);
+        if !force && !self.unsaved && !changed {
+            return;
+        }
+        for slot in &mut self.creatures {
+            self.save.creatures[slot.index] = slot.creature.save();
+            slot.saved_changes = slot.creature.changes();
+        }
+        if let Err(e) = self.save.write() {
+            eprintln!("{}", e);
+        }
+        // even if it failed, so it doesn't get retried every frame
+        self.unsaved = false;
+    }
+    /// Called once the last window has closed.
+    pub fn exit(&mut self) {
+        self.write_save(true);
+    }
+
     pub fn update(&mut self, dt: Duration) {
-        self.creature.update();
+        let dt = dt.min(MAX_DT);
+        let cursor = cursor_pos();
+        // Clicks on her own window come in as window events, but ones right next to her land on
+        // other programs' windows, so the button gets watched here to catch misses.
+        let left_down = left_button_down();
+        if let (true, false, Some(cursor)) = (left_down, self.left_down, cursor) {
+            for slot in &mut self.creatures {
+                if !slot.rect.contains(cursor) {
+                    slot.creature.press_elsewhere(cursor);
+                }
+            }
+        }
+        self.left_down = left_down;
+        let neighbors: Vec<_> = self
+            .creatures
+            .iter()
+            .map(|slot| slot.creature.as_neighbor())
+            .collect();
+        for (i, CreatureSlot { creature, .. }) in self.creatures.iter_mut().enumerate() {
+            if let Some(cursor) = cursor {
+                creature.notice_cursor(cursor);
+            }
+            creature.set_neighbors(
+                neighbors
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, n)| *n)
+                    .collect(),
+            );
+            creature.update(dt);
+
+            for report in creature.take_reports() {
+                self.unsaved = true;
+                let round = self.round.as_mut().filter(|round| !round.is_over());
+                match report {
+                    Report::Hid => {
+                        self.save.stats.hid();
+                        if self.mode == Mode::TimeAttack && round.is_none() {
+                            self.round = Some(Round::new(self.round_hides));
+                        }
+                    }
+                    Report::Found { time, app, hints } => {
+                        self.save.stats.found(time, app.as_deref());
+                        if let Some(round) = round {
+                            round.found(time, hints);
+                            if round.is_over() {
+                                creature.say(&finish_round(
+                                    round,
+                                    &mut self.leaderboard,
+                                    &self.messages,
+                                ));
+                            }
+                        }
+                    }
+                    Report::Missed => {
+                        self.save.stats.missed();
+                        if let Some(round) = round {
+                            round.missed();
+                        }
+                    }
+                }
+            }
+            let unlocked: Vec<Achievement> = self
+                .achievements
+                .newly_unlocked(&self.save.stats, &self.save.achievements)
+                .cloned()
+                .collect();
+            for achievement in unlocked {
+                creature.announce(&achievement.name);
+                self.save.achievements.push(achievement.id);
+                self.unsaved = true;
+            }
+        }
+        self.write_save(false);
     }
     pub fn update_for_render(&mut self, renderer: &mut Renderer) {
-        let new_window_rect = self.creature.update_for_render(renderer);
-        self.main_window.set_location(renderer, new_window_rect);
+        // a creature goes away along with her window
+        self.creatures.retain(|slot| {
+            let exists = slot.window.still_exists(renderer);
+            if let (false, Some(arrow)) = (exists, &slot.arrow) {
+                arrow.window.close(renderer);
+            }
+            exists
+        });
+        for slot in &mut self.creatures {
+            slot.rect = slot.creature.update_for_render(renderer);
+            slot.window.set_location(renderer, slot.rect);
+
+            let spot = slot.creature.hint_arrow();
+            if spot.is_some()
+                && slot
+                    .arrow
+                    .as_ref()
+                    .is_none_or(|arrow| !arrow.window.still_exists(renderer))
+            {
+                slot.arrow = Some(HintArrow::new(renderer));
+            }
+            if let Some(arrow) = &mut slot.arrow {
+                arrow.point_at(renderer, spot);
+            }
+        }
+
+        if self
+            .hud
+            .as_ref()
+            .is_some_and(|hud| !hud.window.still_exists(renderer))
+        {
+            self.hud = None;
+        }
+        if let Some(hud) = &mut self.hud {
+            let text = match &self.round {
+                None => self.messages.get("timer_start", &[]),
+                Some(round) => {
+                    let args: [(&str, &dyn Display); 4] = [
+                        ("finds", &round.find_times.len()),
+                        ("hides", &round.hides),
+                        ("time", &clock(round.elapsed())),
+                        ("score", &round.score()),
+                    ];
+                    let id = if round.is_over() {
+                        "timer_over"
+                    } else {
+                        "timer"
+                    };
+                    self.messages.get(id, &args)
+                }
+            };
+            hud.show(renderer, &text);
+        }
     }
 
     pub fn on_key(
@@ -45,8 +416,16 @@ impl Game {
                         let is_decorated = w.is_decorated();
                         w.set_decorated(!is_decorated);
                     }
+                    glfw::Key::E if modifiers == glfw::Modifiers::Control => {
+                        self.export_stats();
+                    }
                     glfw::Key::H if modifiers == glfw::Modifiers::Control => {
-                        self.creature.hide();
+                        if let Some(creature) = self.creature_in(window) {
+                            creature.hide();
+                        }
+                    }
+                    glfw::Key::Z if modifiers == glfw::Modifiers::Control => {
+                        self.undo_drop(window);
                     }
                     _ => {}
                 }
@@ -59,6 +438,14 @@ impl Game {
         window: RenderWindowId,
         pos: (f64, f64),
     ) {
+        let Some(w) = window.get(renderer) else {
+            return;
+        };
+        self.cursor = w.pos() + Vec2I::new(pos.0 as i32, pos.1 as i32);
+        // she can be dragged outside of her own window
+        for slot in &mut self.creatures {
+            slot.creature.cursor_moved(self.cursor);
+        }
     }
     pub fn on_mouse(
         &mut self,
@@ -68,10 +455,257 @@ impl Game {
         action: glfw::Action,
         modifiers: glfw::Modifiers,
     ) {
-        self.creature.click();
+        if let (glfw::MouseButton::Button1, glfw::Action::Release) = (mouse_button, action) {
+            // the button can come up over another creature's window after a drag
+            for slot in &mut self.creatures {
+                slot.creature.release();
+            }
+            return;
+        }
+        let cursor = self.cursor;
+        match (mouse_button, action) {
+            (glfw::MouseButton::Button1, glfw::Action::Press) => {
+                if let Some(creature) = self.creature_in(window) {
+                    creature.press(cursor);
+                }
+            }
+            (glfw::MouseButton::Button2, glfw::Action::Press) => self.open_menu(renderer, window),
+            _ => {}
+        }
+    }
+
+    /// Shows the right-click menu for the creature in `window` and does whatever gets picked.
+    fn open_menu(&mut self, renderer: &mut Renderer, window: RenderWindowId) {
+        let Some(creature) = self.creature_in(window) else {
+            return;
+        };
+        let items = [
+            (
+                MenuItem::Hide,
+                creature.listens_for(Event::Click) && !creature.asleep(),
+            ),
+            (MenuItem::Chat, creature.listens_for(Event::Chat)),
+            (MenuItem::UndoDrop, !self.undo_log.is_empty()),
+            (MenuItem::ExportStats, true),
+            (MenuItem::Quit, true),
+        ];
+        let labels: Vec<(String, bool)> = items
+            .iter()
+            .map(|(item, enabled)| (self.messages.get(item.message(), &[]), *enabled))
+            .collect();
+        let Some(picked) = popup_menu(&labels) else {
+            return;
+        };
+        match items[picked].0 {
+            MenuItem::Hide => {
+                if let Some(creature) = self.creature_in(window) {
+                    creature.hide();
+                }
+            }
+            MenuItem::Chat => {
+                if let Some(creature) = self.creature_in(window) {
+                    creature.chat();
+                }
+            }
+            MenuItem::UndoDrop => self.undo_drop(window),
+            MenuItem::ExportStats => self.export_stats(),
+            MenuItem::Quit => self.quit(renderer),
+        }
+    }
+    /// Files were dropped on `window`. If it's a creature that takes them, they get eaten (or put
+    /// away, depending on the settings) and she says what she thinks of them.
+    pub fn on_file_drop(&mut self, window: RenderWindowId, paths: &[PathBuf]) {
+        let Some(file_drops) = &self.file_drops else {
+            return;
+        };
+        let hungry = self
+            .creatures
+            .iter()
+            .any(|slot| slot.window == window && slot.creature.listens_for(Event::Fed));
+        if !hungry {
+            return;
+        }
+        let Some(folder) = file_drops.folder() else {
+            eprintln!("could not find a data directory for her to eat files into");
+            return;
+        };
+        let batch = feeding::move_all(paths, &folder);
+        if batch.files.is_empty() {
+            return;
+        }
+        let line = feeding::comment(&batch, *file_drops == FileDrops::Eat, &self.messages);
+        if let Some(creature) = self.creature_in(window) {
+            creature.feed(&line);
+        }
+        self.undo_log.push(batch);
+        if let Err(e) = self.undo_log.write() {
+            eprintln!("{}", e);
+        }
+    }
+    /// Puts the files from the latest drop back where they came from, with the creature in
+    /// `window` saying so.
+    fn undo_drop(&mut self, window: RenderWindowId) {
+        let Some(restored) = self.undo_log.undo() else {
+            return;
+        };
+        if let Err(e) = self.undo_log.write() {
+            eprintln!("{}", e);
+        }
+        if restored.is_empty() {
+            return;
+        }
+        let line = feeding::comment_on_undo(&restored, &self.messages);
+        if let Some(creature) = self.creature_in(window) {
+            creature.say(&line);
+            creature.chat();
+        }
+    }
+    /// Closes every window, which ends the game once they're gone.
+    fn quit(&mut self, renderer: &mut Renderer) {
+        for slot in &self.creatures {
+            slot.window.close(renderer);
+            if let Some(arrow) = &slot.arrow {
+                arrow.window.close(renderer);
+            }
+        }
+        if let Some(hud) = &self.hud {
+            hud.window.close(renderer);
+        }
     }
 
+    /// Whether any creature is still around. The timer doesn't count.
     pub fn running(&self, renderer: &Renderer) -> bool {
-        self.main_window.still_exists(renderer)
+        self.creatures
+            .iter()
+            .any(|slot| slot.window.still_exists(renderer))
+    }
+}
+
+/// Puts `round`, which just ended, on the leaderboard and writes it out. Returns what she says
+/// about how it went.
+fn finish_round(round: &Round, leaderboard: &mut Leaderboard, messages: &Messages) -> String {
+    let entry = Entry::of(round);
+    let best = leaderboard.best(round.hides).map(|best| best.score);
+    leaderboard.add(entry.clone());
+    if let Err(e) = leaderboard.write() {
+        eprintln!("{}", e);
+    }
+    let time = clock(entry.time);
+    match best {
+        Some(best) if best >= entry.score => messages.get(
+            "round_over",
+            &[("time", &time), ("score", &entry.score), ("best", &best)],
+        ),
+        _ => messages.get(
+            "round_over_best",
+            &[("time", &time), ("score", &entry.score)],
+        ),
+    }
+}
+
+impl Hud {
+    fn new(renderer: &mut Renderer, screen_center: Vec2I, fonts: Fonts) -> Self {
+        let top = renderer
+            .work_areas()
+            .into_iter()
+            .find(|area| area.contains(screen_center))
+            .map_or(screen_center.y, |area| area.pos.y);
+        let window = renderer.add_window(Vec2I::new(0, 0), Vec2I::new(1, 1));
+        window.make_current(renderer);
+        Self {
+            text: renderer.add_renderable(window, Text::new(fonts)),
+            window,
+            top_center: Vec2I::new(screen_center.x, top),
+        }
+    }
+
+    /// Changes what it shows to `text`, and fits the window around it.
+    fn show(&mut self, renderer: &mut Renderer, text: &str) {
+        let changed = self.text.get(renderer).is_some_and(|it| it.text() != text);
+        if !changed {
+            return;
+        }
+        self.window.make_current(renderer);
+        let Some(it) = self.text.get_mut(renderer) else {
+            return;
+        };
+        it.set_text(text);
+        let dim = it.current_dims();
+        self.window.set_location(
+            renderer,
+            RectI {
+                pos: Vec2I::new(
+                    self.top_center.x - dim.x / 2,
+                    self.top_center.y + HUD_MARGIN,
+                ),
+                dim,
+            },
+        );
+    }
+}
+
+impl HintArrow {
+    fn new(renderer: &mut Renderer) -> Self {
+        let image = image::load_from_memory(include_bytes!("assets/arrow.png")).unwrap();
+        let window = renderer.add_window(Vec2I::new(0, 0), Vec2I::new(1, 1));
+        window.make_current(renderer);
+        let mut sprite = Sprite::new([
+            include_imageasset!("assets/empty.png"),
+            ImageAsset::from_dynamicimage(image.clone()),
+            ImageAsset::from_dynamicimage(image.fliph()),
+        ]);
+        sprite.pos = (Vec2I::new(0, 0), RelativeTo::Window, Anchor::TopLeft);
+        Self {
+            sprite: renderer.add_renderable(window, sprite),
+            window,
+            dim: Vec2I::new(image.width() as i32, image.height() as i32),
+            pos: Vec2I::new(0, 0),
+            work_areas: renderer.work_areas(),
+            t_begin: SystemTime::now(),
+        }
+    }
+
+    /// Points at `spot` (absolute) from whichever side of its screen is closer, or hides the arrow
+    /// if there's nothing to point at.
+    fn point_at(&mut self, renderer: &mut Renderer, spot: Option<Vec2I>) {
+        let area = spot.and_then(|spot| {
+            self.work_areas
+                .iter()
+                .find(|area| area.contains(spot))
+                .or(self.work_areas.first())
+                .map(|area| (spot, *area))
+        });
+        let Some((spot, area)) = area else {
+            if let Some(sprite) = self.sprite.get_mut(renderer) {
+                sprite.set_current_tex_index(0);
+                let dim = sprite.current_dims();
+                self.window
+                    .set_location(renderer, RectI { pos: self.pos, dim });
+            }
+            return;
+        };
+        let t = self
+            .t_begin
+            .elapsed()
+            .unwrap_or(Duration::ZERO)
+            .as_secs_f64();
+        let bob = ((t * ARROW_BOB_RATE * 2.0 * PI).sin().abs() * ARROW_BOB) as i32;
+        let y = spot.y - self.dim.y / 2;
+        let (tex, x) = if spot.x - area.pos.x < area.pos.x + area.dim.x - spot.x {
+            (1, area.pos.x + ARROW_MARGIN + bob)
+        } else {
+            (2, area.pos.x + area.dim.x - ARROW_MARGIN - self.dim.x - bob)
+        };
+        if let Some(sprite) = self.sprite.get_mut(renderer) {
+            sprite.set_current_tex_index(tex);
+        }
+        self.pos = Vec2I::new(x, y);
+        self.window.set_location(
+            renderer,
+            RectI {
+                pos: self.pos,
+                dim: self.dim,
+            },
+        );
     }
 }
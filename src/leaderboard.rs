@@ -0,0 +1,267 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    round::Round,
+    save::{data_dir, load_or_default, load_versioned, write_json},
+    util::json::{Fields, FileError, FileErrorKind, Json},
+};
+
+/// Version of the leaderboard file layout this build writes.
+const LEADERBOARD_VERSION: u32 = 1;
+const LEADERBOARD_FILE: &str = "leaderboard.json";
+const LEADERBOARD_WHAT: &str = "leaderboard";
+/// How many of the best rounds are kept for each round length.
+const KEEP_BEST: usize = 10;
+
+/// The best time-attack rounds played, kept separately for each number of hides per round.
+#[derive(Debug, Clone, Default)]
+pub struct Leaderboard {
+    /// Best first within each round length.
+    pub entries: Vec<Entry>,
+}
+
+/// One finished round.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub hides: u32,
+    pub score: u32,
+    pub time: Duration,
+    pub misses: u32,
+    /// When the round was played.
+    pub when: SystemTime,
+}
+
+impl Entry {
+    /// The entry for `round`, which has to be over.
+    pub fn of(round: &Round) -> Self {
+        Self {
+            hides: round.hides,
+            score: round.score(),
+            time: round.elapsed(),
+            misses: round.misses,
+            when: SystemTime::now(),
+        }
+    }
+    /// Whether `self` ranks above `other`: higher score first, then faster, then fewer misses.
+    fn beats(&self, other: &Entry) -> bool {
+        (other.score, self.time, self.misses) < (self.score, other.time, other.misses)
+    }
+}
+
+impl Leaderboard {
+    pub fn path() -> Option<PathBuf> {
+        Some(data_dir()?.join(LEADERBOARD_FILE))
+    }
+
+    /// Loads the leaderboard, or starts a fresh one if there isn't one (see [`load_or_default`]).
+    pub fn load() -> Self {
+        load_or_default(Self::path(), LEADERBOARD_WHAT, Self::load_file)
+    }
+
+    pub fn load_file(path: &Path) -> Result<Self, FileError> {
+        load_versioned(path, LEADERBOARD_WHAT, LEADERBOARD_VERSION, Self::parse)
+    }
+
+    fn parse(_version: u32, json: Json, errors: &mut Vec<String>) -> Self {
+        let mut leaderboard = Self::default();
+        match json.get("entries").map(|e| (e, e.as_array())) {
+            Some((_, Some(entries))) => {
+                for (i, entry) in entries.iter().enumerate() {
+                    let mut fields = Fields::new(entry, format!("entries[{}]", i), errors);
+                    if let Some(entry) = Entry::parse(&mut fields) {
+                        leaderboard.add(entry);
+                    }
+                    fields.finish();
+                }
+            }
+            Some((other, None)) => errors.push(format!(
+                "entries: expected an array, found {}",
+                other.type_name()
+            )),
+            None => {}
+        }
+
+        leaderboard
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::Object(vec![
+            (
+                "version".to_string(),
+                Json::Number(LEADERBOARD_VERSION as f64),
+            ),
+            (
+                "entries".to_string(),
+                Json::Array(self.entries.iter().map(Entry::to_json).collect()),
+            ),
+        ])
+    }
+
+    /// Writes the leaderboard to the data directory.
+    pub fn write(&self) -> Result<(), FileError> {
+        let path = Self::path()
+            .ok_or_else(|| FileError::new(LEADERBOARD_WHAT, FileErrorKind::NoDataDir))?;
+        write_json(&path, LEADERBOARD_WHAT, &self.to_json())
+    }
+
+    /// The best round played with `hides` hides, if there's been one.
+    pub fn best(&self, hides: u32) -> Option<&Entry> {
+        self.entries.iter().find(|e| e.hides == hides)
+    }
+
+    /// Puts `entry` in its place, dropping the worst round of its length if there are too many.
+    /// Returns whether it made the board.
+    pub fn add(&mut self, entry: Entry) -> bool {
+        let at = self
+            .entries
+            .iter()
+            .position(|e| e.hides == entry.hides && entry.beats(e))
+            .or_else(|| {
+                // right after the last round of the same length
+                self.entries
+                    .iter()
+                    .rposition(|e| e.hides == entry.hides)
+                    .map(|i| i + 1)
+            })
+            .unwrap_or(self.entries.len());
+        let rank = self.entries[..at]
+            .iter()
+            .filter(|e| e.hides == entry.hides)
+            .count();
+        if rank >= KEEP_BEST {
+            return false;
+        }
+        let hides = entry.hides;
+        self.entries.insert(at, entry);
+        if let Some(worst) = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.hides == hides)
+            .nth(KEEP_BEST)
+            .map(|(i, _)| i)
+        {
+            self.entries.remove(worst);
+        }
+        true
+    }
+}
+
+impl Entry {
+    /// Reads an entry the way [`Entry::to_json`] writes it, reporting any problems to `fields`.
+    fn parse(fields: &mut Fields) -> Option<Self> {
+        let hides = fields.whole_number("hides");
+        let score = fields.count("score").unwrap_or(0);
+        let time = fields.seconds("time").unwrap_or_default();
+        let misses = fields.count("misses").unwrap_or(0);
+        let when = fields.timestamp("when").unwrap_or(SystemTime::UNIX_EPOCH);
+        Some(Self {
+            hides: hides? as u32,
+            score,
+            time,
+            misses,
+            when,
+        })
+    }
+
+    fn to_json(&self) -> Json {
+        let when = self
+            .when
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO);
+        Json::Object(vec![
+            ("hides".to_string(), Json::Number(self.hides as f64)),
+            ("score".to_string(), Json::Number(self.score as f64)),
+            ("time".to_string(), Json::Number(self.time.as_secs_f64())),
+            ("misses".to_string(), Json::Number(self.misses as f64)),
+            ("when".to_string(), Json::Number(when.as_secs() as f64)),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(hides: u32, score: u32, secs: u64) -> Entry {
+        Entry {
+            hides,
+            score,
+            time: Duration::from_secs(secs),
+            misses: 0,
+            when: SystemTime::UNIX_EPOCH,
+        }
+    }
+    fn scores(leaderboard: &Leaderboard, hides: u32) -> Vec<(u32, u64)> {
+        leaderboard
+            .entries
+            .iter()
+            .filter(|e| e.hides == hides)
+            .map(|e| (e.score, e.time.as_secs()))
+            .collect()
+    }
+
+    #[test]
+    fn ranks_by_score_then_time() {
+        let mut leaderboard = Leaderboard::default();
+        for e in [entry(5, 500, 60), entry(5, 700, 60), entry(5, 600, 60)] {
+            assert!(leaderboard.add(e));
+        }
+        leaderboard.add(entry(5, 600, 30));
+        // a tie goes after the round that got there first
+        leaderboard.add(entry(5, 500, 60));
+        let mut slower = entry(5, 500, 60);
+        slower.misses = 1;
+        leaderboard.add(slower);
+        assert_eq!(
+            scores(&leaderboard, 5),
+            [
+                (700, 60),
+                (600, 30),
+                (600, 60),
+                (500, 60),
+                (500, 60),
+                (500, 60)
+            ]
+        );
+        assert_eq!(leaderboard.entries[5].misses, 1);
+        assert_eq!(leaderboard.best(5).unwrap().score, 700);
+    }
+
+    #[test]
+    fn keeps_the_best_few() {
+        let mut leaderboard = Leaderboard::default();
+        for score in 1..=KEEP_BEST as u32 {
+            assert!(leaderboard.add(entry(5, score * 100, 60)));
+        }
+        assert!(!leaderboard.add(entry(5, 50, 60)));
+        assert!(!leaderboard.add(entry(5, 100, 90)));
+        assert_eq!(leaderboard.entries.len(), KEEP_BEST);
+
+        assert!(leaderboard.add(entry(5, 2000, 60)));
+        let kept = scores(&leaderboard, 5);
+        assert_eq!(kept.len(), KEEP_BEST);
+        assert_eq!(kept[0], (2000, 60));
+        assert_eq!(kept[KEEP_BEST - 1], (200, 60));
+    }
+
+    #[test]
+    fn round_lengths_are_ranked_separately() {
+        let mut leaderboard = Leaderboard::default();
+        for score in 1..=KEEP_BEST as u32 {
+            leaderboard.add(entry(5, score * 100, 60));
+        }
+        assert!(leaderboard.add(entry(10, 50, 60)));
+        assert!(leaderboard.add(entry(10, 300, 60)));
+        leaderboard.add(entry(5, 2000, 60));
+
+        assert_eq!(scores(&leaderboard, 10), [(300, 60), (50, 60)]);
+        assert_eq!(scores(&leaderboard, 5).len(), KEEP_BEST);
+        assert_eq!(leaderboard.best(10).unwrap().score, 300);
+        assert_eq!(leaderboard.best(5).unwrap().score, 2000);
+        assert!(leaderboard.best(3).is_none());
+    }
+}
@@ -0,0 +1,203 @@
+use std::env;
+
+use crate::aware::system::user_locale;
+
+/// Language everything is written in to begin with, and that anything missing from a translation
+/// is shown in.
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+/// Which language to show everything in: `configured` if it's set, otherwise the one the system is
+/// set to (from `LC_ALL`, `LC_MESSAGES` or `LANG`, then the user's locale on Windows), otherwise
+/// [`FALLBACK_LANGUAGE`]. Comes out as a lowercase tag like `pt-br`.
+pub fn detect(configured: Option<&str>) -> String {
+    let from_env = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX");
+    configured
+        .map(str::to_string)
+        .or(from_env)
+        .or_else(user_locale)
+        .map(|tag| normalize(&tag))
+        .filter(|tag| !tag.is_empty())
+        .unwrap_or_else(|| FALLBACK_LANGUAGE.to_string())
+}
+
+/// `pt_BR.UTF-8@euro` and `pt-BR` both become `pt-br`.
+pub fn normalize(tag: &str) -> String {
+    let tag = tag.split(['.', '@']).next().unwrap_or_default();
+    tag.replace('_', "-").to_ascii_lowercase()
+}
+
+/// The part of a tag that says which language it is, without the region: `pt` for `pt-br`.
+pub fn language_of(tag: &str) -> &str {
+    tag.split('-').next().unwrap_or(tag)
+}
+
+/// Which form of a word goes with a number. Languages use different subsets of these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Plural {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+impl Plural {
+    pub const ALL: [Plural; 6] = [
+        Self::Zero,
+        Self::One,
+        Self::Two,
+        Self::Few,
+        Self::Many,
+        Self::Other,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Zero => "zero",
+            Self::One => "one",
+            Self::Two => "two",
+            Self::Few => "few",
+            Self::Many => "many",
+            Self::Other => "other",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.name() == name)
+    }
+}
+
+/// How a language picks the [`Plural`] form for a whole number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluralRule {
+    /// 1 is `one`, everything else is `other` (English, Spanish, German, ...).
+    One,
+    /// 0 and 1 are `one`, everything else is `other` (French, ...).
+    ZeroOne,
+    /// `one` for 1, 21, 31, ..., `few` for 2-4, 22-24, ..., and `many` for the rest (Russian,
+    /// Ukrainian, ...).
+    EastSlavic,
+    /// `one` for 1, `few` for 2-4, 22-24, ..., and `many` for the rest.
+    Polish,
+    /// `one` for 1, `few` for 2-4, and `other` for the rest (Czech, Slovak).
+    WestSlavic,
+    /// Always `other` (Japanese, Chinese, Korean, ...).
+    None,
+}
+impl PluralRule {
+    pub const ALL: [PluralRule; 6] = [
+        Self::One,
+        Self::ZeroOne,
+        Self::EastSlavic,
+        Self::Polish,
+        Self::WestSlavic,
+        Self::None,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::One => "one",
+            Self::ZeroOne => "zero_one",
+            Self::EastSlavic => "east_slavic",
+            Self::Polish => "polish",
+            Self::WestSlavic => "west_slavic",
+            Self::None => "none",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.name() == name)
+    }
+
+    /// The rule the language `tag` is written in uses, for the languages that are known. The rest
+    /// get [`PluralRule::One`].
+    pub fn for_language(tag: &str) -> Self {
+        match language_of(tag) {
+            "fr" | "pt" if tag != "pt-pt" => Self::ZeroOne,
+            "ru" | "uk" | "be" => Self::EastSlavic,
+            "pl" => Self::Polish,
+            "cs" | "sk" => Self::WestSlavic,
+            "ja" | "zh" | "ko" | "vi" | "th" | "id" | "ms" => Self::None,
+            _ => Self::One,
+        }
+    }
+
+    pub fn plural(self, n: u64) -> Plural {
+        let (n10, n100) = (n % 10, n % 100);
+        match self {
+            Self::One if n == 1 => Plural::One,
+            Self::ZeroOne if n <= 1 => Plural::One,
+            Self::EastSlavic if n10 == 1 && n100 != 11 => Plural::One,
+            Self::EastSlavic if (2..=4).contains(&n10) && !(12..=14).contains(&n100) => Plural::Few,
+            Self::EastSlavic => Plural::Many,
+            Self::Polish if n == 1 => Plural::One,
+            Self::Polish if (2..=4).contains(&n10) && !(12..=14).contains(&n100) => Plural::Few,
+            Self::Polish => Plural::Many,
+            Self::WestSlavic if n == 1 => Plural::One,
+            Self::WestSlavic if (2..=4).contains(&n) => Plural::Few,
+            _ => Plural::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plurals(language: &str, numbers: &[u64]) -> Vec<Plural> {
+        let rule = PluralRule::for_language(language);
+        numbers.iter().map(|n| rule.plural(*n)).collect()
+    }
+
+    #[test]
+    fn russian() {
+        use Plural::*;
+        assert_eq!(plurals("ru", &[1, 21, 101]), [One, One, One]);
+        assert_eq!(
+            plurals("ru", &[2, 3, 4, 22, 104]),
+            [Few, Few, Few, Few, Few]
+        );
+        assert_eq!(
+            plurals("ru", &[0, 5, 11, 12, 14, 111, 112]),
+            [Many, Many, Many, Many, Many, Many, Many]
+        );
+    }
+
+    #[test]
+    fn polish() {
+        use Plural::*;
+        assert_eq!(plurals("pl", &[1, 2, 4, 22]), [One, Few, Few, Few]);
+        // unlike Russian, 21 isn't `one`
+        assert_eq!(
+            plurals("pl", &[0, 5, 12, 21, 112]),
+            [Many, Many, Many, Many, Many]
+        );
+    }
+
+    #[test]
+    fn czech() {
+        use Plural::*;
+        assert_eq!(plurals("cs", &[1, 2, 3, 4]), [One, Few, Few, Few]);
+        assert_eq!(plurals("cs", &[0, 5, 21, 22]), [Other, Other, Other, Other]);
+    }
+
+    #[test]
+    fn languages() {
+        use Plural::*;
+        assert_eq!(plurals("en", &[0, 1, 2]), [Other, One, Other]);
+        assert_eq!(plurals("fr", &[0, 1, 2]), [One, One, Other]);
+        assert_eq!(plurals("pt-br", &[0, 1, 2]), [One, One, Other]);
+        // European Portuguese goes with English rather than Brazilian
+        assert_eq!(plurals("pt-pt", &[0, 1, 2]), [Other, One, Other]);
+        assert_eq!(plurals("ja", &[0, 1, 2]), [Other, Other, Other]);
+        assert_eq!(PluralRule::for_language("uk-ua"), PluralRule::EastSlavic);
+    }
+
+    #[test]
+    fn normalized_tags() {
+        assert_eq!(normalize("pt_BR.UTF-8@euro"), "pt-br");
+        assert_eq!(normalize("pt-PT"), "pt-pt");
+        assert_eq!(language_of("pt-br"), "pt");
+    }
+}
@@ -1,20 +1,77 @@
 #![windows_subsystem = "windows"]
 
-use std::sync::mpsc;
+use std::{path::Path, process, sync::mpsc};
 
+use aware::schedule::parse_time_of_day;
+use config::{Overrides, MAX_ROUND_HIDES};
 use game::Game;
+use round::Mode;
 use util::DeltaTimer;
+use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
 
+mod achievements;
 mod aware;
+mod config;
+mod feeding;
 mod game;
+mod leaderboard;
+mod locale;
+mod messages;
 mod render;
+mod round;
+mod save;
+mod stats;
 mod util;
 
+const USAGE: &str = "usage: silly_little_creature [--mode endless|time_attack] [--round-hides <n>]
+                            [--clock <hh:mm>] [--clock-speed <n>]
+       silly_little_creature --export-stats <file.json|file.csv>";
+
 fn main() {
+    // Windows programs don't get a console, so without borrowing the one it was started from (if
+    // any) nothing printed would show up.
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+
+    let mut overrides = Overrides::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        match (flag.as_str(), args.next()) {
+            ("--export-stats", Some(path)) => {
+                if let Err(e) = Game::export_saved_stats(Path::new(&path)) {
+                    eprintln!("could not write {}: {}", path, e);
+                    process::exit(1);
+                }
+                return;
+            }
+            ("--mode", Some(mode)) if Mode::from_name(&mode).is_some() => {
+                overrides.mode = Mode::from_name(&mode);
+            }
+            ("--round-hides", Some(n))
+                if n.parse().is_ok_and(|n| (1..=MAX_ROUND_HIDES).contains(&n)) =>
+            {
+                overrides.round_hides = n.parse().ok();
+            }
+            ("--clock", Some(time)) if parse_time_of_day(&time).is_some() => {
+                overrides.clock = parse_time_of_day(&time);
+            }
+            ("--clock-speed", Some(n))
+                if n.parse::<f64>().is_ok_and(|n| n > 0.0 && n.is_finite()) =>
+            {
+                overrides.clock_speed = n.parse().ok();
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(1);
+            }
+        }
+    }
+
     // Initialize render engine
     let mut renderer = render::renderer::Renderer::new();
 
-    let mut game = Game::init(&mut renderer);
+    let mut game = Game::init(&mut renderer, &overrides);
 
     // renderer.debug_nontransparent_clear = true;
 
@@ -38,6 +95,7 @@ fn main() {
                 glfw::WindowEvent::CursorPos(x, y) => {
                     game.on_cursor_pos(&mut renderer, window_id, (x, y))
                 }
+                glfw::WindowEvent::FileDrop(paths) => game.on_file_drop(window_id, &paths),
                 _ => {}
             }
         }
@@ -45,4 +103,5 @@ fn main() {
         game.update_for_render(&mut renderer);
         renderer.render();
     }
+    game.exit();
 }
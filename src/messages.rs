@@ -0,0 +1,360 @@
+use std::{collections::HashMap, fmt::Display, path::Path};
+
+use crate::{
+    locale::{language_of, Plural, PluralRule, FALLBACK_LANGUAGE},
+    util::json::{parse_file, read_file, FileError, FileErrorKind, Json},
+};
+
+/// File next to the game that, if present, changes what she says.
+const MESSAGES_OVERRIDE_PATH: &str = "messages.json";
+const MESSAGES_WHAT: &str = "messages file";
+/// Folder next to the game with translations in it, as `<language>.json`. They're used instead of
+/// the bundled translation for the same language, if there is one.
+const LOCALES_OVERRIDE_DIR: &str = "locales";
+/// Translations that ship with the game, by language. The first one is the
+/// [`FALLBACK_LANGUAGE`], which has every message.
+const BUNDLED_LOCALES: [(&str, &str); 4] = [
+    ("en", include_str!("assets/locales/en.json")),
+    ("es", include_str!("assets/locales/es.json")),
+    ("ru", include_str!("assets/locales/ru.json")),
+    ("ja", include_str!("assets/locales/ja.json")),
+];
+
+/// Everything she (and the timer) can say, by id, in one language. Messages can have
+/// `{placeholders}` in them that get filled in when they're shown.
+#[derive(Debug, Clone)]
+pub struct Messages {
+    /// Language the messages are in, as a tag like `pt-br`. Messages missing from its translation
+    /// are in the [`FALLBACK_LANGUAGE`] instead.
+    pub language: String,
+    /// How plurals work in the language, if the translation says so.
+    plural: Option<PluralRule>,
+    phrases: HashMap<String, Phrase>,
+    /// Translations of the lines in the dialogue file, by line id, one phrase per speech bubble.
+    dialogue: HashMap<String, Vec<Phrase>>,
+    /// Font files (or names of fonts the system has) to write the language with, best first, for
+    /// when the built-in font doesn't have a character.
+    pub fonts: Vec<String>,
+}
+
+/// Text that can have a different form depending on a number in it.
+#[derive(Debug, Clone)]
+pub enum Phrase {
+    Plain(String),
+    /// One form for each plural category the language uses, picked by the number the placeholder
+    /// called `count` is filled in with. `other` is always there.
+    Plural {
+        count: String,
+        forms: Vec<(Plural, String)>,
+    },
+}
+
+impl Messages {
+    /// The messages that ship with the game, in the [`FALLBACK_LANGUAGE`].
+    pub fn bundled() -> Self {
+        let (language, src) = BUNDLED_LOCALES[0];
+        Self::parse(src, language).unwrap_or_else(|e| panic!("bundled messages are broken: {}", e))
+    }
+
+    /// The messages in `language` (or just its language without the region, if there's no
+    /// translation for that exact one), with the [`FALLBACK_LANGUAGE`] for whatever the
+    /// translation doesn't have, and the ones in the override file (if there is one) over all of
+    /// it. Files that can't be used get reported and left out.
+    pub fn load(language: &str) -> Self {
+        let mut messages = Self::bundled();
+        if language != messages.language {
+            match Self::load_translation(language) {
+                Some(Ok(translation)) => {
+                    let language = translation.language.clone();
+                    if let Err(e) = messages.merge(translation) {
+                        eprintln!("[{} translation] {}", language, e);
+                        eprintln!("using {} instead", FALLBACK_LANGUAGE);
+                    }
+                }
+                Some(Err(e)) => {
+                    eprintln!("[{} translation] {}", language, e);
+                    eprintln!("using {} instead", FALLBACK_LANGUAGE);
+                }
+                None => eprintln!(
+                    "there's no translation for {}, using {} instead",
+                    language, FALLBACK_LANGUAGE
+                ),
+            }
+        }
+        if Path::new(MESSAGES_OVERRIDE_PATH).exists() {
+            let language = messages.language.clone();
+            let overrides = Self::load_file(Path::new(MESSAGES_OVERRIDE_PATH), &language);
+            if let Err(e) = overrides.and_then(|overrides| messages.merge(overrides)) {
+                eprintln!("[{}] {}", MESSAGES_OVERRIDE_PATH, e);
+                eprintln!("using the bundled messages instead");
+            }
+        }
+        messages
+    }
+
+    /// The translation for `language`, or for its language without the region. Ones next to the
+    /// game win over bundled ones. `None` if there isn't one at all.
+    fn load_translation(language: &str) -> Option<Result<Self, FileError>> {
+        let mut tags = vec![language];
+        if language_of(language) != language {
+            tags.push(language_of(language));
+        }
+        tags.into_iter().find_map(|tag| {
+            let path = Path::new(LOCALES_OVERRIDE_DIR).join(format!("{}.json", tag));
+            if path.exists() {
+                return Some(Self::load_file(&path, tag));
+            }
+            BUNDLED_LOCALES
+                .iter()
+                .find(|(bundled, _)| *bundled == tag)
+                .map(|(_, src)| Self::parse(src, tag))
+        })
+    }
+
+    pub fn load_file(path: &Path, language: &str) -> Result<Self, FileError> {
+        Self::parse(&read_file(path, MESSAGES_WHAT)?, language)
+    }
+
+    /// Parses a messages file written in `language`.
+    pub fn parse(src: &str, language: &str) -> Result<Self, FileError> {
+        let json = parse_file(src, MESSAGES_WHAT)?;
+        let mut errors = vec![];
+        let mut messages = Self {
+            language: language.to_string(),
+            plural: None,
+            phrases: HashMap::new(),
+            dialogue: HashMap::new(),
+            fonts: vec![],
+        };
+        let Some(entries) = json.as_object() else {
+            return Err(FileError::new(
+                MESSAGES_WHAT,
+                FileErrorKind::Invalid(vec![format!(
+                    "<root>: expected an object, found {}",
+                    json.type_name()
+                )]),
+            ));
+        };
+        for (key, value) in entries {
+            match key.as_str() {
+                "messages" => match value.as_object() {
+                    Some(phrases) => {
+                        for (id, phrase) in phrases {
+                            match Phrase::from_json(phrase) {
+                                Ok(phrase) => {
+                                    messages.phrases.insert(id.clone(), phrase);
+                                }
+                                Err(e) => errors.push(format!("messages.{}: {}", id, e)),
+                            }
+                        }
+                    }
+                    None => errors.push(format!(
+                        "messages: expected an object, found {}",
+                        value.type_name()
+                    )),
+                },
+                "dialogue" => match value.as_object() {
+                    Some(lines) => {
+                        for (id, say) in lines {
+                            match Phrase::bubbles_from_json(say) {
+                                Ok(say) => {
+                                    messages.dialogue.insert(id.clone(), say);
+                                }
+                                Err(e) => errors.push(format!("dialogue.{}: {}", id, e)),
+                            }
+                        }
+                    }
+                    None => errors.push(format!(
+                        "dialogue: expected an object, found {}",
+                        value.type_name()
+                    )),
+                },
+                "plural" => match value.as_str().and_then(PluralRule::from_name) {
+                    Some(rule) => messages.plural = Some(rule),
+                    None => errors.push(format!(
+                        "plural: expected one of {}",
+                        PluralRule::ALL.map(PluralRule::name).join(", ")
+                    )),
+                },
+                "fonts" => match value.as_array() {
+                    Some(fonts) => {
+                        for font in fonts {
+                            match font.as_str() {
+                                Some(font) => messages.fonts.push(font.to_string()),
+                                None => errors.push(format!(
+                                    "fonts: expected font files, found {}",
+                                    font.type_name()
+                                )),
+                            }
+                        }
+                    }
+                    None => errors.push(format!(
+                        "fonts: expected an array, found {}",
+                        value.type_name()
+                    )),
+                },
+                _ => errors.push(format!("{}: unknown entry", key)),
+            }
+        }
+        FileError::check(MESSAGES_WHAT, errors)?;
+        Ok(messages)
+    }
+
+    /// Puts everything in `other` in place of what's in `self`, as long as every message in it is
+    /// one that `self` has too, so there are no typos. Nothing changes if there's a problem.
+    fn merge(&mut self, other: Messages) -> Result<(), FileError> {
+        let mut unknown: Vec<String> = other
+            .phrases
+            .keys()
+            .filter(|id| !self.phrases.contains_key(*id))
+            .map(|id| format!("messages.{}: unknown message", id))
+            .collect();
+        unknown.sort();
+        FileError::check(MESSAGES_WHAT, unknown)?;
+        self.language = other.language;
+        self.plural = other.plural.or(self.plural);
+        self.phrases.extend(other.phrases);
+        self.dialogue.extend(other.dialogue);
+        if !other.fonts.is_empty() {
+            self.fonts = other.fonts;
+        }
+        Ok(())
+    }
+
+    /// How plurals work in the language the messages are in.
+    pub fn plural_rule(&self) -> PluralRule {
+        self.plural
+            .unwrap_or_else(|| PluralRule::for_language(&self.language))
+    }
+
+    /// The message called `id`, filled in with `args` (see [`Phrase::fill`]).
+    pub fn get(&self, id: &str, args: &[(&str, &dyn Display)]) -> String {
+        let Some(phrase) = self.phrases.get(id) else {
+            eprintln!("no message called \"{}\"", id);
+            return id.to_string();
+        };
+        phrase.fill(self.plural_rule(), args)
+    }
+
+    /// The translation of the dialogue line called `id`, if there is one.
+    pub fn dialogue(&self, id: &str) -> Option<&[Phrase]> {
+        self.dialogue.get(id).map(Vec::as_slice)
+    }
+    /// Ids of the dialogue lines there are translations for.
+    pub fn translated_lines(&self) -> impl Iterator<Item = &str> {
+        self.dialogue.keys().map(String::as_str)
+    }
+    /// Drops the translation of the dialogue line called `id`, so it's said as it's written in
+    /// the dialogue file.
+    pub fn forget_line(&mut self, id: &str) {
+        self.dialogue.remove(id);
+    }
+}
+
+impl Phrase {
+    /// Either a string, or `{ "count": placeholder, "one": text, ..., "other": text }` with a form
+    /// for each plural category.
+    pub fn from_json(json: &Json) -> Result<Self, String> {
+        let entries = match json {
+            Json::String(text) => return Ok(Self::Plain(text.clone())),
+            Json::Object(entries) => entries,
+            other => {
+                return Err(format!(
+                    "expected a string or an object of plural forms, found {}",
+                    other.type_name()
+                ))
+            }
+        };
+        let mut count = None;
+        let mut forms = vec![];
+        for (key, value) in entries {
+            match (key.as_str(), value.as_str()) {
+                ("count", Some(name)) => count = Some(name.to_string()),
+                (key, Some(text)) if Plural::from_name(key).is_some() => {
+                    forms.push((Plural::from_name(key).unwrap(), text.to_string()))
+                }
+                (key, _) => {
+                    return Err(format!(
+                        "{}: expected \"count\" (the placeholder the number is in) or one of {}, \
+                         with a string",
+                        key,
+                        Plural::ALL.map(Plural::name).join(", ")
+                    ))
+                }
+            }
+        }
+        let Some(count) = count else {
+            return Err("plural forms need a \"count\" saying which placeholder to go by".into());
+        };
+        if !forms.iter().any(|(plural, _)| *plural == Plural::Other) {
+            return Err("plural forms need an \"other\" form".into());
+        }
+        Ok(Self::Plural { count, forms })
+    }
+
+    /// What's said over one or more speech bubbles: one phrase, or an array of them.
+    pub fn bubbles_from_json(json: &Json) -> Result<Vec<Self>, String> {
+        match json {
+            Json::Array(phrases) if !phrases.is_empty() => {
+                phrases.iter().map(Self::from_json).collect()
+            }
+            Json::Array(_) => Err("expected at least one speech bubble".into()),
+            phrase => Ok(vec![Self::from_json(phrase)?]),
+        }
+    }
+
+    /// Every form the phrase can take.
+    pub fn texts(&self) -> impl Iterator<Item = &str> {
+        let (plain, forms) = match self {
+            Self::Plain(text) => (Some(text.as_str()), &[][..]),
+            Self::Plural { forms, .. } => (None, forms.as_slice()),
+        };
+        plain
+            .into_iter()
+            .chain(forms.iter().map(|(_, text)| text.as_str()))
+    }
+    /// Names of the placeholders the phrase needs filled in, including the one plurals go by.
+    pub fn placeholders(&self) -> impl Iterator<Item = &str> {
+        let count = match self {
+            Self::Plain(_) => None,
+            Self::Plural { count, .. } => Some(count.as_str()),
+        };
+        self.texts().flat_map(placeholders).chain(count)
+    }
+
+    /// The phrase in the form that goes with its count (going by `rule`), with each `{name}` in it
+    /// replaced by the value that goes with `name` in `args`. Placeholders that aren't in `args`
+    /// are left as they are.
+    pub fn fill(&self, rule: PluralRule, args: &[(&str, &dyn Display)]) -> String {
+        let text = match self {
+            Self::Plain(text) => text,
+            Self::Plural { count, forms } => {
+                let n = args
+                    .iter()
+                    .find(|(name, _)| name == count)
+                    .and_then(|(_, value)| value.to_string().parse::<u64>().ok());
+                let plural = n.map_or(Plural::Other, |n| rule.plural(n));
+                let form = |plural| forms.iter().find(|(p, _)| *p == plural);
+                &form(plural).or_else(|| form(Plural::Other)).unwrap().1
+            }
+        };
+        fill(text, args)
+    }
+}
+
+/// `text` with each `{name}` in it replaced by the value that goes with `name` in `args`.
+/// Placeholders that aren't in `args` are left as they are.
+pub fn fill(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut out = text.to_string();
+    for (name, value) in args {
+        out = out.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    out
+}
+
+/// Names of the `{placeholders}` in `text`.
+pub fn placeholders(text: &str) -> impl Iterator<Item = &str> {
+    text.split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+}
@@ -1,4 +1,9 @@
+pub mod animation;
+pub mod bubble;
+pub mod emote;
 mod glrs;
 pub mod image_asset;
 pub mod renderer;
 pub mod sprite;
+pub mod sprite_sheet;
+pub mod text;
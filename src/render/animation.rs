@@ -0,0 +1,120 @@
+use std::{collections::HashMap, time::Duration};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Frame {
+    /// Index of the sprite sheet frame to show.
+    pub index: usize,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayMode {
+    /// Start over from the first frame after the last one.
+    Loop,
+    /// Stop on the last frame.
+    Once,
+}
+
+#[derive(Debug, Clone)]
+pub struct Clip {
+    pub frames: Vec<Frame>,
+    pub mode: PlayMode,
+}
+impl Clip {
+    pub fn new(mode: PlayMode, frames: Vec<Frame>) -> Self {
+        assert!(
+            !frames.is_empty(),
+            "animation clips need at least one frame"
+        );
+        Self { frames, mode }
+    }
+    pub fn total_duration(&self) -> Duration {
+        self.frames.iter().map(|f| f.duration).sum()
+    }
+    /// The frame showing at `t` into the clip.
+    fn frame_at(&self, t: Duration) -> &Frame {
+        let total = self.total_duration();
+        let mut t = match self.mode {
+            PlayMode::Loop if !total.is_zero() => {
+                Duration::from_nanos((t.as_nanos() % total.as_nanos()) as u64)
+            }
+            _ => t,
+        };
+        for frame in &self.frames {
+            if t < frame.duration {
+                return frame;
+            }
+            t -= frame.duration;
+        }
+        self.frames.last().unwrap()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnimationEvent {
+    /// A clip played with [`PlayMode::Once`] reached its end.
+    Finished(String),
+}
+
+/// Plays named clips, one at a time.
+#[derive(Debug, Clone)]
+pub struct Animator {
+    clips: HashMap<String, Clip>,
+    current: String,
+    elapsed: Duration,
+    finished: bool,
+}
+impl Animator {
+    pub fn new(clips: HashMap<String, Clip>, initial: &str) -> Self {
+        let mut animator = Self {
+            clips,
+            current: String::new(),
+            elapsed: Duration::ZERO,
+            finished: false,
+        };
+        animator.replay(initial);
+        animator
+    }
+
+    pub fn has_clip(&self, name: &str) -> bool {
+        self.clips.contains_key(name)
+    }
+
+    /// Switches to the named clip, unless it's already the one playing.
+    pub fn play(&mut self, name: &str) {
+        if self.current != name {
+            self.replay(name);
+        }
+    }
+    /// Starts the named clip over from the beginning.
+    pub fn replay(&mut self, name: &str) {
+        if !self.has_clip(name) {
+            eprintln!(
+                "attempted to play animation \"{}\" which doesn't exist",
+                name
+            );
+            return;
+        }
+        self.current = name.to_string();
+        self.elapsed = Duration::ZERO;
+        self.finished = false;
+    }
+
+    /// Advances the current clip by `dt`.
+    pub fn update(&mut self, dt: Duration) -> Option<AnimationEvent> {
+        let clip = self.clips.get(&self.current)?;
+        self.elapsed += dt;
+        if clip.mode == PlayMode::Once && !self.finished && self.elapsed >= clip.total_duration() {
+            self.finished = true;
+            return Some(AnimationEvent::Finished(self.current.clone()));
+        }
+        None
+    }
+
+    /// Sprite sheet frame that should be showing right now.
+    pub fn current_frame(&self) -> usize {
+        self.clips
+            .get(&self.current)
+            .map_or(0, |clip| clip.frame_at(self.elapsed).index)
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{
+    include_imageasset,
+    util::{RectI, Vec2I},
+};
+
+use super::{
+    glrs,
+    renderer::{Anchor, GLUtil, RelativeTo, Renderable},
+    sprite::{load_sprite_shaders, QuadStyle, FULL_QUAD_TRIS},
+    text::{Fonts, Text},
+};
+
+/// Size of the corners of `bubble.png`, which are drawn as they are while the edges and middle
+/// between them get stretched to fit the text.
+const SLICE: i32 = 8;
+/// Size of the whole of `bubble.png`.
+const BODY_TEXTURE_DIM: i32 = 24;
+/// Size of `bubble_tail.png`. It points down, with its tip on the left.
+const TAIL_DIM: Vec2I = Vec2I { x: 16, y: 14 };
+/// How far in from the left of `bubble_tail.png` its tip is.
+const TAIL_TIP_X: i32 = 2;
+/// How far the tail reaches into the body, to cover up the body's outline where they meet.
+const TAIL_OVERLAP: i32 = 2;
+/// Space between the text and the edge of the bubble.
+const TEXT_PADDING: Vec2I = Vec2I { x: 10, y: 7 };
+
+/// A speech bubble fitted around some text, with a tail pointing at whoever's saying it. It goes
+/// above them, or below if there's no room above, and stays inside the area it's given, turning
+/// its tail around to keep pointing at them.
+pub struct SpeechBubble {
+    vo: glrs::TriPosVO<2>,
+    shaders: glrs::GLShaderProgramLinked,
+    body: glrs::GLTexture2d,
+    tail: glrs::GLTexture2d,
+    text: Text,
+    /// Whoever's speaking.
+    speaker: RectI,
+    /// Where the bubble has to fit, if anywhere.
+    area: Option<RectI>,
+    relative_to: RelativeTo,
+    /// Where everything goes, or `None` if there's no text.
+    layout: Option<Layout>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    body: RectI,
+    tail: RectI,
+    /// Whether the tail's tip is on its right, for when the bubble had to move left of the
+    /// speaker.
+    tail_flip_x: bool,
+    /// Whether the bubble is below the speaker, with the tail pointing up.
+    tail_flip_y: bool,
+}
+
+impl SpeechBubble {
+    /// Has to be called while the context of the window it'll be drawn in is current, like any
+    /// other renderable.
+    pub fn new(fonts: Fonts) -> Self {
+        Self {
+            vo: glrs::TriPosVO::new(FULL_QUAD_TRIS),
+            shaders: load_sprite_shaders(),
+            body: glrs::GLTexture2d::new(&include_imageasset!("../assets/bubble.png")),
+            tail: glrs::GLTexture2d::new(&include_imageasset!("../assets/bubble_tail.png")),
+            text: Text::bare(fonts),
+            speaker: RectI {
+                pos: Vec2I::new(0, 0),
+                dim: Vec2I::new(0, 0),
+            },
+            area: None,
+            relative_to: RelativeTo::Window,
+            layout: None,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        self.text.text()
+    }
+    /// Shows `text` (see [`Text::set_text`]), with the bubble resized to fit. The window it's
+    /// drawn in has to be current.
+    pub fn set_text(&mut self, text: &str) {
+        self.text.set_text(text);
+        self.arrange();
+    }
+
+    /// Points the bubble at `speaker`, keeping it inside `area` if there is one. Both are in
+    /// `relative_to` coordinates.
+    pub fn place(&mut self, speaker: RectI, area: Option<RectI>, relative_to: RelativeTo) {
+        self.speaker = speaker;
+        self.area = area;
+        self.relative_to = relative_to;
+        self.arrange();
+    }
+
+    /// Everything the bubble covers, tail included, or `None` if it isn't showing.
+    pub fn current_bounds(&self) -> Option<RectI> {
+        self.layout.map(|layout| layout.body.union(layout.tail))
+    }
+
+    fn arrange(&mut self) {
+        let text_dim = self.text.current_dims();
+        if text_dim.x == 0 {
+            self.layout = None;
+            return;
+        }
+        let dim = Vec2I::new(
+            (text_dim.x + 2 * TEXT_PADDING.x).max(TAIL_DIM.x + 2 * SLICE),
+            (text_dim.y + 2 * TEXT_PADDING.y).max(2 * SLICE),
+        );
+        let speaker = self.speaker;
+        let area = self.area;
+        let reach = TAIL_DIM.y - TAIL_OVERLAP + dim.y;
+
+        // above, unless that's off the top and there's room below
+        let tail_flip_y = area.is_some_and(|area| {
+            speaker.pos.y - reach < area.pos.y
+                && speaker.pos.y + speaker.dim.y + reach <= area.pos.y + area.dim.y
+        });
+        let tip = Vec2I::new(
+            speaker.pos.x + speaker.dim.x / 2,
+            if tail_flip_y {
+                speaker.pos.y + speaker.dim.y
+            } else {
+                speaker.pos.y
+            },
+        );
+
+        let mut body_x = tip.x - dim.x / 2;
+        if let Some(area) = area {
+            body_x = body_x.min(area.pos.x + area.dim.x - dim.x).max(area.pos.x);
+        }
+        // the tail goes out to the right of the tip unless the bubble is mostly left of it
+        let tail_flip_x = tip.x > body_x + dim.x / 2;
+        let tail_x = if tail_flip_x {
+            tip.x - (TAIL_DIM.x - TAIL_TIP_X)
+        } else {
+            tip.x - TAIL_TIP_X
+        }
+        .clamp(body_x + SLICE, body_x + dim.x - SLICE - TAIL_DIM.x);
+
+        let (tail_y, body_y) = if tail_flip_y {
+            (tip.y, tip.y + TAIL_DIM.y - TAIL_OVERLAP)
+        } else {
+            (tip.y - TAIL_DIM.y, tip.y - reach)
+        };
+        let layout = Layout {
+            body: RectI {
+                pos: Vec2I::new(body_x, body_y),
+                dim,
+            },
+            tail: RectI {
+                pos: Vec2I::new(tail_x, tail_y),
+                dim: TAIL_DIM,
+            },
+            tail_flip_x,
+            tail_flip_y,
+        };
+        self.text.pos = (layout.body.pos + dim / 2, self.relative_to, Anchor::Center);
+        self.layout = Some(layout);
+    }
+}
+
+impl Renderable for SpeechBubble {
+    fn render(&self, glu: GLUtil) {
+        let Some(layout) = self.layout else {
+            return;
+        };
+        let style = |uv_rect| QuadStyle {
+            uv_rect,
+            clip: None,
+            occluders: &[],
+            tint: [0.0; 4],
+        };
+
+        // Nine slices: the corners as they are, the edges stretched along the sides, and the
+        // middle stretched both ways.
+        let body = layout.body;
+        let texture = BODY_TEXTURE_DIM as f32;
+        // (where, how big) on screen and in the texture, for each slice along one side
+        let slices = |pos: i32, dim: i32| {
+            let middle = BODY_TEXTURE_DIM - 2 * SLICE;
+            [
+                (pos, SLICE, 0, SLICE),
+                (pos + SLICE, dim - 2 * SLICE, SLICE, middle),
+                (pos + dim - SLICE, SLICE, SLICE + middle, SLICE),
+            ]
+        };
+        for (y, h, v, tex_h) in slices(body.pos.y, body.dim.y) {
+            for (x, w, u, tex_w) in slices(body.pos.x, body.dim.x) {
+                style([
+                    u as f32 / texture,
+                    v as f32 / texture,
+                    tex_w as f32 / texture,
+                    tex_h as f32 / texture,
+                ])
+                .draw(
+                    glu,
+                    &self.shaders,
+                    &self.vo,
+                    &self.body,
+                    RectI {
+                        pos: Vec2I::new(x, y),
+                        dim: Vec2I::new(w, h),
+                    },
+                    self.relative_to,
+                );
+            }
+        }
+
+        // flipped by reading the texture backwards
+        let (u, w) = if layout.tail_flip_x {
+            (1.0, -1.0)
+        } else {
+            (0.0, 1.0)
+        };
+        let (v, h) = if layout.tail_flip_y {
+            (1.0, -1.0)
+        } else {
+            (0.0, 1.0)
+        };
+        style([u, v, w, h]).draw(
+            glu,
+            &self.shaders,
+            &self.vo,
+            &self.tail,
+            layout.tail,
+            self.relative_to,
+        );
+
+        self.text.render(glu);
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    f64::consts::PI,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    include_imageasset,
+    util::{RectI, Vec2I},
+};
+
+use super::{
+    glrs,
+    renderer::{GLUtil, RelativeTo, Renderable},
+    sprite::{load_sprite_shaders, QuadStyle, FULL_QUAD_TRIS},
+};
+
+/// Size of `heart.png`.
+const HEART_DIM: Vec2I = Vec2I { x: 20, y: 18 };
+/// How many hearts float up each time.
+const HEARTS: usize = 3;
+/// Time between one heart and the next one.
+const HEART_DELAY: Duration = Duration::from_millis(250);
+/// How long each heart floats for.
+const HEART_TIME: Duration = Duration::from_millis(1000);
+/// How far up each heart floats, in pixels.
+const RISE: f64 = 40.0;
+/// How far each heart sways side to side while floating up, in pixels.
+const SWAY: f64 = 4.0;
+/// Last part of its float, from 0 to 1, over which a heart shrinks away.
+const SHRINK_FROM: f64 = 0.7;
+
+/// A few hearts floating up out of someone's head.
+pub struct Emote {
+    vo: glrs::TriPosVO<2>,
+    shaders: glrs::GLShaderProgramLinked,
+    heart: glrs::GLTexture2d,
+    /// When the hearts started floating up, while they still are.
+    t_begin: Option<SystemTime>,
+    relative_to: RelativeTo,
+    /// Where each heart that's showing is.
+    hearts: Vec<RectI>,
+}
+
+impl Emote {
+    /// Has to be called while the context of the window it'll be drawn in is current, like any
+    /// other renderable.
+    pub fn new() -> Self {
+        Self {
+            vo: glrs::TriPosVO::new(FULL_QUAD_TRIS),
+            shaders: load_sprite_shaders(),
+            heart: glrs::GLTexture2d::new(&include_imageasset!("../assets/heart.png")),
+            t_begin: None,
+            relative_to: RelativeTo::Window,
+            hearts: vec![],
+        }
+    }
+
+    /// Sends up a new lot of hearts, cutting off any that are still floating.
+    pub fn start(&mut self) {
+        self.t_begin = Some(SystemTime::now());
+    }
+
+    /// Floats the hearts up from the top of `from`, in `relative_to` coordinates. Has to be
+    /// called every frame to move them along.
+    pub fn place(&mut self, from: RectI, relative_to: RelativeTo) {
+        self.relative_to = relative_to;
+        self.hearts.clear();
+        let Some(t_begin) = self.t_begin else {
+            return;
+        };
+        let elapsed = t_begin.elapsed().unwrap_or(Duration::ZERO);
+        if elapsed >= HEART_DELAY * (HEARTS as u32 - 1) + HEART_TIME {
+            self.t_begin = None;
+            return;
+        }
+        for i in 0..HEARTS {
+            let Some(t) = elapsed.checked_sub(HEART_DELAY * i as u32) else {
+                continue;
+            };
+            let u = t.as_secs_f64() / HEART_TIME.as_secs_f64();
+            if u >= 1.0 {
+                continue;
+            }
+            // spread out across the top of her head, left to right
+            let x = from.pos.x as f64 + from.dim.x as f64 * (i + 1) as f64 / (HEARTS + 1) as f64;
+            let center = Vec2I::new(
+                (x + (u * 2.0 * PI + i as f64).sin() * SWAY) as i32,
+                (from.pos.y as f64 - u * RISE) as i32,
+            );
+            let scale = ((1.0 - u) / (1.0 - SHRINK_FROM)).min(1.0);
+            let dim = Vec2I::new(
+                (HEART_DIM.x as f64 * scale) as i32,
+                (HEART_DIM.y as f64 * scale) as i32,
+            );
+            self.hearts.push(RectI {
+                pos: center - dim / 2,
+                dim,
+            });
+        }
+    }
+
+    /// Everything the hearts cover, or `None` if none are showing.
+    pub fn current_bounds(&self) -> Option<RectI> {
+        self.hearts.iter().copied().reduce(RectI::union)
+    }
+}
+
+impl Renderable for Emote {
+    fn render(&self, glu: GLUtil) {
+        for heart in &self.hearts {
+            QuadStyle {
+                uv_rect: [0.0, 0.0, 1.0, 1.0],
+                clip: None,
+                occluders: &[],
+                tint: [0.0; 4],
+            }
+            .draw(
+                glu,
+                &self.shaders,
+                &self.vo,
+                &self.heart,
+                *heart,
+                self.relative_to,
+            );
+        }
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
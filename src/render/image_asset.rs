@@ -1,6 +1,6 @@
-use std::io::Cursor;
+use std::{io::Cursor, path::Path};
 
-use image::{io::Reader as ImageReader, DynamicImage};
+use image::{io::Reader as ImageReader, DynamicImage, ImageError};
 
 use crate::util::Vec2I;
 
@@ -20,6 +20,9 @@ impl ImageAsset {
 
         Self::from_dynamicimage(img)
     }
+    pub fn load_file(path: &Path) -> Result<Self, ImageError> {
+        Ok(Self::from_dynamicimage(image::open(path)?))
+    }
     pub fn from_dynamicimage(img: DynamicImage) -> Self {
         Self {
             dims: Vec2I::new(img.width() as i32, img.height() as i32),
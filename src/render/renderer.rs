@@ -15,6 +15,8 @@ pub struct RenderWindow {
 
     pos: Vec2I,
     dim: Vec2I,
+    /// Ids of the renderables drawn in this window, in drawing order.
+    renderables: Vec<u64>,
 }
 
 pub const SELF_WINDOW_TITLE: &str = "silly little creature :3";
@@ -38,12 +40,20 @@ impl RenderWindow {
         window.make_current();
         let real_pos = renderer.screen_origin + pos;
         window.set_pos(real_pos.x, real_pos.y);
-        renderer.glfw.set_swap_interval(glfw::SwapInterval::Sync(1));
+        // Only one window waits for vsync, otherwise every extra window would halve the frame rate.
+        renderer
+            .glfw
+            .set_swap_interval(if renderer.windows.is_empty() {
+                glfw::SwapInterval::Sync(1)
+            } else {
+                glfw::SwapInterval::None
+            });
 
         // Initialize polling
         window.set_key_polling(true);
         window.set_mouse_button_polling(true);
         window.set_cursor_pos_polling(true);
+        window.set_drag_and_drop_polling(true);
 
         window.set_size_polling(true);
         window.set_pos_polling(true);
@@ -54,6 +64,7 @@ impl RenderWindow {
             events,
             pos,
             dim,
+            renderables: vec![],
         }
     }
 
@@ -103,7 +114,8 @@ impl RenderWindow {
                     0.0
                 },
             );
-            gl::Clear(gl::COLOR_BUFFER_BIT);
+            gl::ClearStencil(0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
 
             // Disable clipping based on the depth buffer.
             gl::Disable(gl::DEPTH_TEST);
@@ -122,6 +134,10 @@ impl RenderWindow {
     pub fn should_close(&self) -> bool {
         self.window.should_close()
     }
+    /// Absolute position of the window's top left corner.
+    pub fn pos(&self) -> Vec2I {
+        self.pos
+    }
     pub fn raw_window(&self) -> &glfw::PWindow {
         &self.window
     }
@@ -146,7 +162,7 @@ impl<T: Renderable + 'static> RenderableId<T> {
             .and_then(|r| r.as_any_mut().downcast_mut::<T>())
     }
 }
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RenderWindowId(u64);
 impl RenderWindowId {
     pub fn get(self, renderer: &Renderer) -> Option<&RenderWindow> {
@@ -158,6 +174,18 @@ impl RenderWindowId {
     pub fn still_exists(self, renderer: &Renderer) -> bool {
         renderer.windows.contains_key(&self.0)
     }
+    /// Closes the window (and gets rid of everything drawn in it) the next time events are handled.
+    pub fn close(self, renderer: &mut Renderer) {
+        if let Some(window) = renderer.windows.get_mut(&self.0) {
+            window.window.set_should_close(true);
+        }
+    }
+    /// Makes GL calls go to this window, so that GL objects get created for it.
+    pub fn make_current(self, renderer: &mut Renderer) {
+        if let Some(window) = renderer.windows.get_mut(&self.0) {
+            window.window.make_current();
+        }
+    }
 
     pub fn set_location(self, renderer: &mut Renderer, rect: RectI) {
         let screen_origin = renderer.screen_origin;
@@ -202,6 +230,8 @@ impl Renderer {
         glfw.window_hint(glfw::WindowHint::Floating(true));
         glfw.window_hint(glfw::WindowHint::Decorated(false));
         glfw.window_hint(glfw::WindowHint::TransparentFramebuffer(true));
+        // Sprites use the stencil buffer to cut out the parts hidden by other windows.
+        glfw.window_hint(glfw::WindowHint::StencilBits(Some(8)));
 
         let (screen_origin, screen_dim) = {
             let (mx, my, mw, mh) = glfw.with_primary_monitor(|_, m| {
@@ -232,8 +262,19 @@ impl Renderer {
         }
     }
 
-    pub fn add_renderable<T: Renderable + 'static>(&mut self, r: T) -> RenderableId<T> {
+    /// Adds a renderable that gets drawn in `window`, on top of the ones added before it. Every
+    /// window has its own GL context, so `r` has to have been created while `window` was current
+    /// (see [`RenderWindowId::make_current`]).
+    pub fn add_renderable<T: Renderable + 'static>(
+        &mut self,
+        window: RenderWindowId,
+        r: T,
+    ) -> RenderableId<T> {
+        let Some(render_window) = self.windows.get_mut(&window.0) else {
+            panic!("added a renderable to a window that doesn't exist");
+        };
         self.next_renderable_id += 1;
+        render_window.renderables.push(self.next_renderable_id);
         if self
             .renderables
             .insert(self.next_renderable_id, Box::new(r))
@@ -264,7 +305,13 @@ impl Renderer {
             }
         }
         for id in to_close.into_iter().flatten() {
-            self.windows.remove(&id);
+            if let Some(mut window) = self.windows.remove(&id) {
+                // the window's GL objects have to be deleted while its context is current
+                window.window.make_current();
+                for renderable in &window.renderables {
+                    self.renderables.remove(renderable);
+                }
+            }
         }
     }
 
@@ -276,12 +323,41 @@ impl Renderer {
         mem::swap(&mut windows, &mut self.windows);
 
         for window in windows.values_mut() {
-            window.render(&self, self.renderables.values());
+            let renderables = window
+                .renderables
+                .iter()
+                .filter_map(|id| self.renderables.get(id))
+                .collect::<Vec<_>>();
+            window.render(self, renderables.into_iter());
         }
 
         mem::swap(&mut windows, &mut self.windows);
     }
 
+    /// The part of each connected monitor that isn't covered by the taskbar and such.
+    pub fn work_areas(&mut self) -> Vec<RectI> {
+        let areas = self.glfw.with_connected_monitors(|_, monitors| {
+            monitors
+                .iter()
+                .map(|m| {
+                    let (x, y, w, h) = m.get_workarea();
+                    RectI {
+                        pos: Vec2I::new(x, y),
+                        dim: Vec2I::new(w, h),
+                    }
+                })
+                .collect::<Vec<_>>()
+        });
+        if areas.is_empty() {
+            vec![RectI {
+                pos: self.screen_origin,
+                dim: self.screen_dim,
+            }]
+        } else {
+            areas
+        }
+    }
+
     /// Absolute position of the center of the screen.
     pub fn center_pos(&self) -> Vec2I {
         Vec2I {
@@ -309,16 +385,61 @@ impl GLUtil {
         }
     }
 
-    pub fn viewport(&self, mut pos: Vec2I, dim: Vec2I, relative_to: RelativeTo) {
-        pos = match relative_to {
+    fn to_window(self, pos: Vec2I, relative_to: RelativeTo) -> Vec2I {
+        match relative_to {
             RelativeTo::Window => pos,
             RelativeTo::Screen => pos - self.window_pos_screen,
             RelativeTo::Absolute => pos - self.window_pos_abs,
-        };
+        }
+    }
+
+    pub fn viewport(&self, pos: Vec2I, dim: Vec2I, relative_to: RelativeTo) {
+        let pos = self.to_window(pos, relative_to);
         unsafe {
             gl::Viewport(pos.x, self.window_dim.y - pos.y - dim.y, dim.x, dim.y);
         }
     }
+
+    /// Prevents drawing inside any of `rects` until [`GLUtil::unmask`] is called.
+    pub fn mask_out(&self, rects: &[RectI], relative_to: RelativeTo) {
+        if rects.is_empty() {
+            return;
+        }
+        unsafe {
+            // Mark the masked areas in the stencil buffer by clearing just those regions.
+            gl::ClearStencil(1);
+            for &rect in rects {
+                self.scissor(Some(rect), relative_to);
+                gl::Clear(gl::STENCIL_BUFFER_BIT);
+            }
+            self.scissor(None, relative_to);
+            gl::ClearStencil(0);
+
+            gl::Enable(gl::STENCIL_TEST);
+            gl::StencilFunc(gl::EQUAL, 0, 0xFF);
+            gl::StencilOp(gl::KEEP, gl::KEEP, gl::KEEP);
+        }
+    }
+    pub fn unmask(&self) {
+        unsafe {
+            gl::Disable(gl::STENCIL_TEST);
+            gl::Clear(gl::STENCIL_BUFFER_BIT);
+        }
+    }
+
+    /// Restricts drawing to `rect`, or lifts the restriction if `rect` is `None`.
+    pub fn scissor(&self, rect: Option<RectI>, relative_to: RelativeTo) {
+        unsafe {
+            match rect {
+                Some(RectI { pos, dim }) => {
+                    let pos = self.to_window(pos, relative_to);
+                    gl::Enable(gl::SCISSOR_TEST);
+                    gl::Scissor(pos.x, self.window_dim.y - pos.y - dim.y, dim.x, dim.y);
+                }
+                None => gl::Disable(gl::SCISSOR_TEST),
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -330,14 +451,12 @@ pub enum RelativeTo {
 #[derive(Debug, Clone, Copy)]
 pub enum Anchor {
     TopLeft,
-    BottomCenter,
     Center,
 }
 impl Anchor {
     pub fn apply(self, pos: Vec2I, dim: Vec2I) -> Vec2I {
         match self {
             Self::TopLeft => pos,
-            Self::BottomCenter => Vec2I::new(pos.x - dim.x / 2, pos.y - dim.y),
             Self::Center => Vec2I::new(pos.x - dim.x / 2, pos.y - dim.y / 2),
         }
     }
@@ -1,4 +1,4 @@
-use crate::util::Vec2I;
+use crate::util::{RectI, Vec2I};
 
 use super::{
     glrs,
@@ -6,42 +6,95 @@ use super::{
     renderer::{Anchor, GLUtil, RelativeTo, Renderable, TransparencyMode},
 };
 
-const FULL_QUAD_TRIS: [[[f32; 2]; 3]; 2] = [
+pub(super) const FULL_QUAD_TRIS: [[[f32; 2]; 3]; 2] = [
     [[1.0, 1.0], [-1.0, 1.0], [1.0, -1.0]],
     [[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0]],
 ];
+
+pub(super) fn load_sprite_shaders() -> glrs::GLShaderProgramLinked {
+    let builder = glrs::GLShaderProgramBuilder::new();
+    let vert = glrs::GLShader::load(
+        glrs::GLShaderType::Vertex,
+        include_str!("shader/sprite.vsh"),
+    )
+    .unwrap();
+    let frag = glrs::GLShader::load(
+        glrs::GLShaderType::Fragment,
+        include_str!("shader/sprite.fsh"),
+    )
+    .unwrap();
+
+    builder.attatch_shader(&vert);
+    builder.attatch_shader(&frag);
+
+    builder.link().unwrap()
+}
+
+/// Everything that decides how a quad drawn with the sprite shaders looks.
+pub(super) struct QuadStyle<'a> {
+    /// Part of the texture to draw as (x, y, w, h), in fractions of the texture's size.
+    pub uv_rect: [f32; 4],
+    pub clip: Option<RectI>,
+    pub occluders: &'a [RectI],
+    pub tint: [f32; 4],
+}
+impl QuadStyle<'_> {
+    /// Draws `texture` into `rect`, with `clip` and `occluders` in the same coordinates as `rect`.
+    pub fn draw(
+        &self,
+        glu: GLUtil,
+        shaders: &glrs::GLShaderProgramLinked,
+        vo: &glrs::TriPosVO<2>,
+        texture: &glrs::GLTexture2d,
+        rect: RectI,
+        relative_to: RelativeTo,
+    ) {
+        glu.viewport(rect.pos, rect.dim, relative_to);
+        glu.mask_out(self.occluders, relative_to);
+        glu.scissor(self.clip, relative_to);
+        TransparencyMode::Normal.apply();
+
+        shaders.use_for_draw();
+        vo.bind();
+        texture.bind(glrs::GLTextureSlot::Tex0, 1);
+        unsafe {
+            let [r, g, b, a] = self.tint;
+            gl::Uniform4f(2, r, g, b, a);
+            let [x, y, w, h] = self.uv_rect;
+            gl::Uniform4f(3, x, y, w, h);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3 * FULL_QUAD_TRIS.len() as i32);
+        }
+        glu.scissor(None, relative_to);
+        if !self.occluders.is_empty() {
+            glu.unmask();
+        }
+    }
+}
+
 pub struct Sprite<const N: usize> {
     vo: glrs::TriPosVO<2>,
     shaders: glrs::GLShaderProgramLinked,
     textures: [glrs::GLTexture2d; N],
     pub pos: (Vec2I, RelativeTo, Anchor),
+    /// Only the part of the sprite inside this rect gets drawn, in the same coordinates as `pos`.
+    pub clip: Option<RectI>,
+    /// Parts of the sprite covered by these rects don't get drawn, in the same coordinates as `pos`.
+    pub occluders: Vec<RectI>,
+    /// Color the sprite gets shifted towards, with alpha as the strength of the shift.
+    pub tint: [f32; 4],
     current_tex: usize,
 }
 impl<const N: usize> Sprite<N> {
     pub fn new(image_assets: [ImageAsset; N]) -> Self {
         Self {
-            shaders: {
-                let builder = glrs::GLShaderProgramBuilder::new();
-                let vert = glrs::GLShader::load(
-                    glrs::GLShaderType::Vertex,
-                    include_str!("shader/sprite.vsh"),
-                )
-                .unwrap();
-                let frag = glrs::GLShader::load(
-                    glrs::GLShaderType::Fragment,
-                    include_str!("shader/sprite.fsh"),
-                )
-                .unwrap();
-
-                builder.attatch_shader(&vert);
-                builder.attatch_shader(&frag);
-
-                builder.link().unwrap()
-            },
+            shaders: load_sprite_shaders(),
             vo: glrs::TriPosVO::new(FULL_QUAD_TRIS),
             textures: std::array::from_fn(|i| glrs::GLTexture2d::new(&image_assets[i])),
             current_tex: 0,
             pos: (Vec2I::new(0, 0), RelativeTo::Window, Anchor::TopLeft),
+            clip: None,
+            occluders: vec![],
+            tint: [0.0; 4],
         }
     }
 
@@ -71,16 +124,25 @@ impl<const N: usize> Sprite<N> {
 impl<const N: usize> Renderable for Sprite<N> {
     fn render(&self, glu: GLUtil) {
         let (pos, relative_to, anchor) = self.pos;
-        let dim = self.textures[self.current_tex].get_dimensions();
-        glu.viewport(anchor.apply(pos, dim), dim, relative_to);
-        TransparencyMode::Normal.apply();
-
-        self.shaders.use_for_draw();
-        self.vo.bind();
-        self.textures[self.current_tex].bind(glrs::GLTextureSlot::Tex0, 1);
-        unsafe {
-            gl::DrawArrays(gl::TRIANGLES, 0, 3 * FULL_QUAD_TRIS.len() as i32);
+        let texture = &self.textures[self.current_tex];
+        let dim = texture.get_dimensions();
+        QuadStyle {
+            uv_rect: [0.0, 0.0, 1.0, 1.0],
+            clip: self.clip,
+            occluders: &self.occluders,
+            tint: self.tint,
         }
+        .draw(
+            glu,
+            &self.shaders,
+            &self.vo,
+            texture,
+            RectI {
+                pos: anchor.apply(pos, dim),
+                dim,
+            },
+            relative_to,
+        );
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
@@ -0,0 +1,606 @@
+//! Sprite sheets as exported by Aseprite: every frame packed into one image, plus a JSON file
+//! saying where each frame is, how long it shows, and which frames make up which animation.
+
+use std::{collections::HashMap, path::Path, time::Duration};
+
+use crate::util::{
+    json::{millis, parse_file, read_file, FileError, FileErrorKind, Json, MAX_DURATION_MS},
+    RectI, Vec2I,
+};
+
+use super::{
+    animation::{Clip, Frame, PlayMode},
+    glrs,
+    image_asset::ImageAsset,
+    renderer::{GLUtil, RelativeTo, Renderable},
+    sprite::{load_sprite_shaders, QuadStyle, FULL_QUAD_TRIS},
+};
+
+/// Name of the slice that holds each frame's pivot point.
+const PIVOT_SLICE: &str = "pivot";
+/// How long a frame shows if the sheet doesn't say.
+const DEFAULT_FRAME_DURATION: Duration = Duration::from_millis(100);
+/// What a sheet is called when something's wrong with it.
+pub const SHEET_WHAT: &str = "sprite sheet";
+
+#[derive(Debug, Clone, Copy)]
+pub struct SheetFrame {
+    /// Where the frame's pixels are in the sheet image.
+    pub rect: RectI,
+    /// Size of the frame before transparent borders were trimmed off.
+    pub source_size: Vec2I,
+    /// Where `rect` sits inside the untrimmed frame.
+    pub trim_offset: Vec2I,
+    /// Point in the untrimmed frame that gets drawn at the sprite's position.
+    pub pivot: Vec2I,
+    pub duration: Duration,
+}
+
+/// Everything in a sheet's JSON file.
+#[derive(Debug, Clone)]
+pub struct SheetMeta {
+    pub frames: Vec<SheetFrame>,
+    /// Animations made from the sheet's tags, by tag name.
+    pub clips: HashMap<String, Clip>,
+    /// File name of the sheet image, relative to the JSON file.
+    pub image: Option<String>,
+}
+
+impl SheetMeta {
+    /// Parses sheet metadata in Aseprite's JSON export format, with frames either as an array or
+    /// as a hash.
+    pub fn parse(src: &str) -> Result<Self, FileError> {
+        let json = parse_file(src, SHEET_WHAT)?;
+        let mut errors = vec![];
+
+        let frames_json: Vec<(String, &Json)> = match json.get("frames") {
+            Some(Json::Array(items)) => items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (format!("frames[{}]", i), v))
+                .collect(),
+            Some(Json::Object(entries)) => entries
+                .iter()
+                .map(|(k, v)| (format!("frames.\"{}\"", k), v))
+                .collect(),
+            Some(other) => {
+                errors.push(format!(
+                    "frames: expected an array or an object, found {}",
+                    other.type_name()
+                ));
+                vec![]
+            }
+            None => {
+                errors.push("frames: missing".to_string());
+                vec![]
+            }
+        };
+        let meta = json.get("meta");
+        let pivots = meta
+            .and_then(|m| m.get("slices"))
+            .and_then(|s| s.as_array())
+            .and_then(|s| {
+                s.iter()
+                    .find(|slice| slice.get("name").and_then(|n| n.as_str()) == Some(PIVOT_SLICE))
+            })
+            .map(|slice| pivot_keys(slice, &mut errors))
+            .unwrap_or_default();
+
+        let mut frames = vec![];
+        for (i, (path, frame)) in frames_json.into_iter().enumerate() {
+            if frame.get("rotated").and_then(|r| r.as_bool()) == Some(true) {
+                errors.push(format!(
+                    "{}: rotated frames aren't supported, export without \"rotate\"",
+                    path
+                ));
+            }
+            let frame_rect = rect(frame, "frame", &path, &mut errors);
+            let trim_offset = match frame.get("spriteSourceSize") {
+                Some(_) => rect(frame, "spriteSourceSize", &path, &mut errors).pos,
+                None => Vec2I::new(0, 0),
+            };
+            let source_size = match frame.get("sourceSize") {
+                Some(size) => dims(size, &format!("{}.sourceSize", path), &mut errors),
+                None => frame_rect.dim,
+            };
+            // a frame that takes no time would leave looping clips with nothing to show
+            let duration = match frame.get("duration") {
+                Some(ms) => match ms.as_f64().filter(|ms| *ms >= 1.0).and_then(millis) {
+                    Some(duration) => duration,
+                    None => {
+                        errors.push(format!(
+                            "{}.duration: expected milliseconds, from 1 up to {}",
+                            path, MAX_DURATION_MS
+                        ));
+                        DEFAULT_FRAME_DURATION
+                    }
+                },
+                None => DEFAULT_FRAME_DURATION,
+            };
+            let pivot = pivots
+                .iter()
+                .rev()
+                .find(|(from, _)| *from <= i)
+                .map_or(source_size / 2, |(_, pivot)| *pivot);
+            frames.push(SheetFrame {
+                rect: frame_rect,
+                source_size,
+                trim_offset,
+                pivot,
+                duration,
+            });
+        }
+
+        let mut clips = HashMap::new();
+        if let Some(tags) = meta.and_then(|m| m.get("frameTags")) {
+            match tags.as_array() {
+                Some(tags) => {
+                    for (i, tag) in tags.iter().enumerate() {
+                        let path = format!("meta.frameTags[{}]", i);
+                        if let Some((name, clip)) = tag_clip(tag, &path, &frames, &mut errors) {
+                            if clips.insert(name.clone(), clip).is_some() {
+                                errors.push(format!(
+                                    "{}: another tag is also named \"{}\"",
+                                    path, name
+                                ));
+                            }
+                        }
+                    }
+                }
+                None => errors.push(format!(
+                    "meta.frameTags: expected an array, found {}",
+                    tags.type_name()
+                )),
+            }
+        }
+
+        FileError::check(SHEET_WHAT, errors)?;
+        Ok(Self {
+            frames,
+            clips,
+            image: meta
+                .and_then(|m| m.get("image"))
+                .and_then(|i| i.as_str())
+                .map(str::to_string),
+        })
+    }
+}
+
+/// The pivot slice's keys as (first frame, pivot), in the order they appear.
+fn pivot_keys(slice: &Json, errors: &mut Vec<String>) -> Vec<(usize, Vec2I)> {
+    let keys = slice
+        .get("keys")
+        .and_then(|k| k.as_array())
+        .unwrap_or_default();
+    keys.iter()
+        .enumerate()
+        .map(|(i, key)| {
+            let path = format!("meta.slices.{}.keys[{}]", PIVOT_SLICE, i);
+            let frame = int(key, "frame", &path, errors).max(0) as usize;
+            let bounds = rect(key, "bounds", &path, errors);
+            let pivot = match key.get("pivot") {
+                Some(pivot) => Vec2I::new(
+                    int(pivot, "x", &format!("{}.pivot", path), errors),
+                    int(pivot, "y", &format!("{}.pivot", path), errors),
+                ),
+                None => bounds.dim / 2,
+            };
+            (frame, bounds.pos + pivot)
+        })
+        .collect()
+}
+
+fn tag_clip(
+    tag: &Json,
+    path: &str,
+    frames: &[SheetFrame],
+    errors: &mut Vec<String>,
+) -> Option<(String, Clip)> {
+    let name = tag.get("name").and_then(|n| n.as_str());
+    if name.is_none() {
+        errors.push(format!("{}.name: missing", path));
+    }
+    let from = int(tag, "from", path, errors);
+    let to = int(tag, "to", path, errors);
+    if from < 0 || to < from || to as usize >= frames.len() {
+        errors.push(format!(
+            "{}: frames {} to {} aren't in the sheet, which has {} frame(s)",
+            path,
+            from,
+            to,
+            frames.len()
+        ));
+        return None;
+    }
+    let (from, to) = (from as usize, to as usize);
+
+    let forward: Vec<usize> = (from..=to).collect();
+    let backward: Vec<usize> = (from..=to).rev().collect();
+    let mut order = match tag.get("direction").and_then(|d| d.as_str()) {
+        None | Some("forward") => forward,
+        Some("reverse") => backward,
+        // the turnaround frames only show once per bounce
+        Some("pingpong") => [&forward[..], &backward[1..backward.len().max(2) - 1]].concat(),
+        Some("pingpong_reverse") => [&backward[..], &forward[1..forward.len().max(2) - 1]].concat(),
+        Some(other) => {
+            errors.push(format!(
+                "{}.direction: unknown direction \"{}\"",
+                path, other
+            ));
+            forward
+        }
+    };
+
+    // Aseprite writes the repeat count as a string; no count means it loops forever.
+    let repeat = match tag.get("repeat") {
+        None => None,
+        Some(Json::String(s)) => s.parse::<usize>().ok(),
+        Some(Json::Number(n)) if *n >= 0.0 => Some(*n as usize),
+        Some(_) => None,
+    }
+    .filter(|&n| n > 0);
+    if tag.get("repeat").is_some() && repeat.is_none() {
+        errors.push(format!("{}.repeat: expected a positive whole number", path));
+    }
+    let mode = match repeat {
+        Some(n) => {
+            order = order.repeat(n);
+            PlayMode::Once
+        }
+        None => PlayMode::Loop,
+    };
+
+    let frames = order
+        .into_iter()
+        .map(|index| Frame {
+            index,
+            duration: frames[index].duration,
+        })
+        .collect();
+    Some((name?.to_string(), Clip::new(mode, frames)))
+}
+
+fn int(json: &Json, key: &str, path: &str, errors: &mut Vec<String>) -> i32 {
+    match json.get(key) {
+        Some(Json::Number(n)) if n.fract() == 0.0 => *n as i32,
+        Some(other) => {
+            errors.push(format!(
+                "{}.{}: expected a whole number, found {}",
+                path,
+                key,
+                other.type_name()
+            ));
+            0
+        }
+        None => {
+            errors.push(format!("{}.{}: missing", path, key));
+            0
+        }
+    }
+}
+fn rect(json: &Json, key: &str, path: &str, errors: &mut Vec<String>) -> RectI {
+    let Some(rect) = json.get(key) else {
+        errors.push(format!("{}.{}: missing", path, key));
+        return RectI {
+            pos: Vec2I::new(0, 0),
+            dim: Vec2I::new(0, 0),
+        };
+    };
+    let path = format!("{}.{}", path, key);
+    RectI {
+        pos: Vec2I::new(int(rect, "x", &path, errors), int(rect, "y", &path, errors)),
+        dim: dims(rect, &path, errors),
+    }
+}
+/// The `w` and `h` of `json`, which can't be negative.
+fn dims(json: &Json, path: &str, errors: &mut Vec<String>) -> Vec2I {
+    let dim = Vec2I::new(int(json, "w", path, errors), int(json, "h", path, errors));
+    if dim.x < 0 || dim.y < 0 {
+        errors.push(format!("{}: w and h can't be negative", path));
+        return Vec2I::new(0, 0);
+    }
+    dim
+}
+
+/// A sheet image together with its metadata, checked to fit each other.
+pub struct SheetAsset {
+    pub image: ImageAsset,
+    pub meta: SheetMeta,
+}
+impl SheetAsset {
+    pub fn new(image: ImageAsset, meta: SheetMeta) -> Result<Self, FileError> {
+        let dims = image.get_dimensions();
+        let problems: Vec<String> = meta
+            .frames
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| {
+                f.rect.pos.x < 0
+                    || f.rect.pos.y < 0
+                    || f.rect.pos.x + f.rect.dim.x > dims.x
+                    || f.rect.pos.y + f.rect.dim.y > dims.y
+            })
+            .map(|(i, _)| {
+                format!(
+                    "frames[{}]: goes outside the {}x{} sheet image",
+                    i, dims.x, dims.y
+                )
+            })
+            .collect();
+        FileError::check(SHEET_WHAT, problems)?;
+        Ok(Self { image, meta })
+    }
+
+    pub fn decode(image_bytes: &[u8], json_src: &str) -> Result<Self, FileError> {
+        Self::new(
+            ImageAsset::decode_bytes(image_bytes),
+            SheetMeta::parse(json_src)?,
+        )
+    }
+
+    /// Loads a sheet from its JSON file, with the image found next to it.
+    pub fn load_file(path: &Path) -> Result<Self, FileError> {
+        let meta = SheetMeta::parse(&read_file(path, SHEET_WHAT)?)?;
+        let invalid = |problem| FileError::new(SHEET_WHAT, FileErrorKind::Invalid(vec![problem]));
+        let Some(image_name) = &meta.image else {
+            return Err(invalid("meta.image: missing".to_string()));
+        };
+        let image_path = path.parent().unwrap_or(Path::new("")).join(image_name);
+        let image = ImageAsset::load_file(&image_path).map_err(|e| {
+            invalid(format!(
+                "meta.image: could not load \"{}\": {}",
+                image_name, e
+            ))
+        })?;
+        Self::new(image, meta)
+    }
+
+    /// Names of the animations tagged in the sheet.
+    pub fn animation_names(&self) -> Vec<&str> {
+        self.meta.clips.keys().map(String::as_str).collect()
+    }
+}
+
+/// Draws one frame at a time out of a sprite sheet.
+pub struct SpriteSheet {
+    vo: glrs::TriPosVO<2>,
+    shaders: glrs::GLShaderProgramLinked,
+    texture: glrs::GLTexture2d,
+    frames: Vec<SheetFrame>,
+    /// Where the current frame's pivot goes.
+    pub pos: (Vec2I, RelativeTo),
+    /// Only the part of the sprite inside this rect gets drawn, in the same coordinates as `pos`.
+    pub clip: Option<RectI>,
+    /// Parts of the sprite covered by these rects don't get drawn, in the same coordinates as `pos`.
+    pub occluders: Vec<RectI>,
+    /// Color the sprite gets shifted towards, with alpha as the strength of the shift.
+    pub tint: [f32; 4],
+    current_frame: usize,
+}
+impl SpriteSheet {
+    pub fn new(sheet: &SheetAsset) -> Self {
+        Self {
+            shaders: load_sprite_shaders(),
+            vo: glrs::TriPosVO::new(FULL_QUAD_TRIS),
+            texture: glrs::GLTexture2d::new(&sheet.image),
+            frames: sheet.meta.frames.clone(),
+            current_frame: 0,
+            pos: (Vec2I::new(0, 0), RelativeTo::Window),
+            clip: None,
+            occluders: vec![],
+            tint: [0.0; 4],
+        }
+    }
+
+    pub fn set_current_frame(&mut self, i: usize) {
+        if i < self.frames.len() {
+            self.current_frame = i;
+        } else {
+            eprintln!(
+                "attempted to show frame {} of a sprite sheet with {} frames",
+                i,
+                self.frames.len()
+            );
+        }
+    }
+    /// Size of the current frame, including any transparent border that was trimmed off.
+    pub fn current_dims(&self) -> Vec2I {
+        self.frames[self.current_frame].source_size
+    }
+    /// Where the current frame's pivot is, from the top left of the untrimmed frame.
+    pub fn current_pivot(&self) -> Vec2I {
+        self.frames[self.current_frame].pivot
+    }
+    /// Where the current frame's pixels actually get drawn, in the same coordinates as `pos`.
+    pub fn current_bounds(&self) -> RectI {
+        let frame = &self.frames[self.current_frame];
+        RectI {
+            pos: self.pos.0 - frame.pivot + frame.trim_offset,
+            dim: frame.rect.dim,
+        }
+    }
+}
+
+impl Renderable for SpriteSheet {
+    fn render(&self, glu: GLUtil) {
+        let frame = &self.frames[self.current_frame];
+        let sheet_dim = self.texture.get_dimensions();
+        let (sw, sh) = (sheet_dim.x as f32, sheet_dim.y as f32);
+        QuadStyle {
+            uv_rect: [
+                frame.rect.pos.x as f32 / sw,
+                frame.rect.pos.y as f32 / sh,
+                frame.rect.dim.x as f32 / sw,
+                frame.rect.dim.y as f32 / sh,
+            ],
+            clip: self.clip,
+            occluders: &self.occluders,
+            tint: self.tint,
+        }
+        .draw(
+            glu,
+            &self.shaders,
+            &self.vo,
+            &self.texture,
+            self.current_bounds(),
+            self.pos.1,
+        );
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(x: i32, duration: u32) -> String {
+        format!(
+            r#"{{"frame": {{"x": {}, "y": 0, "w": 10, "h": 20}}, "duration": {}}}"#,
+            x, duration
+        )
+    }
+    fn sheet(frames: &str, meta: &str) -> Result<SheetMeta, FileError> {
+        SheetMeta::parse(&format!(r#"{{"frames": {}, "meta": {}}}"#, frames, meta))
+    }
+    fn order(meta: &SheetMeta, tag: &str) -> Vec<usize> {
+        meta.clips[tag].frames.iter().map(|f| f.index).collect()
+    }
+    fn problems(result: Result<SheetMeta, FileError>) -> Vec<String> {
+        match result {
+            Err(FileError {
+                kind: FileErrorKind::Invalid(problems),
+                ..
+            }) => problems,
+            other => panic!("expected problems, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn array_and_hash_frames() {
+        let frames = [frame(0, 100), frame(10, 200)];
+        let array = sheet(&format!("[{}]", frames.join(",")), "{}").unwrap();
+        let hash = sheet(
+            &format!(r#"{{"a.png": {}, "b.png": {}}}"#, frames[0], frames[1]),
+            "{}",
+        )
+        .unwrap();
+        for meta in [array, hash] {
+            assert_eq!(meta.frames.len(), 2);
+            assert_eq!(meta.frames[1].rect.pos, Vec2I::new(10, 0));
+            assert_eq!(meta.frames[1].source_size, Vec2I::new(10, 20));
+            assert_eq!(meta.frames[1].duration, Duration::from_millis(200));
+            assert_eq!(meta.frames[0].pivot, Vec2I::new(5, 10));
+        }
+    }
+
+    #[test]
+    fn tags() {
+        let frames = format!(
+            "[{}]",
+            (0..4)
+                .map(|i| frame(i * 10, 100))
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        let meta = sheet(
+            &frames,
+            r#"{"frameTags": [
+                {"name": "forward", "from": 0, "to": 2},
+                {"name": "reverse", "from": 1, "to": 3, "direction": "reverse"},
+                {"name": "pingpong", "from": 0, "to": 3, "direction": "pingpong"},
+                {"name": "pingpong_reverse", "from": 0, "to": 2, "direction": "pingpong_reverse"},
+                {"name": "single", "from": 1, "to": 1, "direction": "pingpong"},
+                {"name": "twice", "from": 0, "to": 1, "repeat": "2"},
+                {"name": "thrice", "from": 2, "to": 3, "repeat": 3}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(order(&meta, "forward"), [0, 1, 2]);
+        assert_eq!(meta.clips["forward"].mode, PlayMode::Loop);
+        assert_eq!(order(&meta, "reverse"), [3, 2, 1]);
+        assert_eq!(order(&meta, "pingpong"), [0, 1, 2, 3, 2, 1]);
+        assert_eq!(order(&meta, "pingpong_reverse"), [2, 1, 0, 1]);
+        assert_eq!(order(&meta, "single"), [1]);
+        assert_eq!(order(&meta, "twice"), [0, 1, 0, 1]);
+        assert_eq!(meta.clips["twice"].mode, PlayMode::Once);
+        assert_eq!(order(&meta, "thrice"), [2, 3, 2, 3, 2, 3]);
+    }
+
+    #[test]
+    fn bad_tags() {
+        let frames = format!("[{}]", frame(0, 100));
+        let problems = problems(sheet(
+            &frames,
+            r#"{"frameTags": [
+                {"name": "a", "from": 0, "to": 1},
+                {"name": "b", "from": 0, "to": 0, "direction": "sideways"},
+                {"name": "c", "from": 0, "to": 0, "repeat": "0"},
+                {"name": "c", "from": 0, "to": 0}
+            ]}"#,
+        ));
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+    }
+
+    #[test]
+    fn pivot_keys() {
+        let frames = format!(
+            "[{}]",
+            (0..3)
+                .map(|i| frame(i * 10, 100))
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        let meta = sheet(
+            &frames,
+            r#"{"slices": [{"name": "pivot", "keys": [
+                {"frame": 0, "bounds": {"x": 2, "y": 4, "w": 1, "h": 1}, "pivot": {"x": 0, "y": 0}},
+                {"frame": 2, "bounds": {"x": 2, "y": 4, "w": 4, "h": 6}}
+            ]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(meta.frames[0].pivot, Vec2I::new(2, 4));
+        // keys hold until the next one
+        assert_eq!(meta.frames[1].pivot, Vec2I::new(2, 4));
+        // no pivot in the key means the middle of its bounds
+        assert_eq!(meta.frames[2].pivot, Vec2I::new(4, 7));
+    }
+
+    #[test]
+    fn negative_sizes() {
+        let problems = problems(sheet(
+            r#"[
+                {"frame": {"x": 0, "y": 0, "w": -10, "h": 20}},
+                {"frame": {"x": 0, "y": 0, "w": 10, "h": 20}, "sourceSize": {"w": 10, "h": -1}}
+            ]"#,
+            "{}",
+        ));
+        assert_eq!(
+            problems,
+            [
+                "frames[0].frame: w and h can't be negative",
+                "frames[1].sourceSize: w and h can't be negative",
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_durations() {
+        for duration in ["0", "-1", "1e30", "\"fast\""] {
+            let frames = format!(
+                r#"[{{"frame": {{"x": 0, "y": 0, "w": 10, "h": 20}}, "duration": {}}}]"#,
+                duration
+            );
+            assert_eq!(
+                problems(sheet(&frames, "{}")),
+                ["frames[0].duration: expected milliseconds, from 1 up to 86400000"]
+            );
+        }
+    }
+}
@@ -0,0 +1,496 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use ab_glyph::{point, Font, FontVec, GlyphId, PxScale, ScaleFont};
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use crate::util::{RectI, Vec2I};
+
+use super::{
+    glrs,
+    image_asset::ImageAsset,
+    renderer::{Anchor, GLUtil, RelativeTo, Renderable},
+    sprite::{load_sprite_shaders, QuadStyle, FULL_QUAD_TRIS},
+};
+
+/// Every glyph in `glyphs.png` and how wide it is, left to right. There's a column of space
+/// between each glyph and the next.
+const GLYPH_WIDTHS: [(char, u32); 44] = [
+    ('a', 8),
+    ('b', 9),
+    ('c', 7),
+    ('d', 8),
+    ('e', 7),
+    ('g', 7),
+    ('h', 8),
+    ('i', 7),
+    ('k', 7),
+    ('l', 7),
+    ('m', 8),
+    ('n', 8),
+    ('o', 7),
+    ('p', 7),
+    ('r', 8),
+    ('s', 7),
+    ('t', 7),
+    ('u', 7),
+    ('v', 7),
+    ('w', 8),
+    ('y', 8),
+    ('\'', 2),
+    ('!', 1),
+    ('?', 7),
+    ('0', 7),
+    ('1', 7),
+    ('2', 7),
+    ('3', 7),
+    ('4', 7),
+    ('5', 7),
+    ('6', 7),
+    ('7', 7),
+    ('8', 7),
+    ('9', 7),
+    (':', 2),
+    ('.', 2),
+    ('/', 7),
+    ('f', 6),
+    ('j', 4),
+    ('q', 7),
+    ('x', 7),
+    ('z', 7),
+    ('-', 5),
+    (',', 2),
+];
+/// Drawn for characters the font doesn't have.
+const MISSING_GLYPH: char = '?';
+/// Size of a spot in the glyph cache, big enough for the widest glyph.
+const CELL_W: usize = 18;
+const CELL_H: usize = 20;
+/// How many glyphs fit in the glyph cache, across and down.
+const CACHE_COLS: usize = 32;
+const CACHE_ROWS: usize = 8;
+/// Space between letters of the pixel font, in pixels.
+const LETTER_SPACING: i32 = 1;
+const SPACE_WIDTH: i32 = 10;
+/// How far apart lines are, from the top of one to the top of the next.
+const LINE_HEIGHT: i32 = 23;
+/// Space around the text in a box, in pixels.
+const PADDING: i32 = 5;
+/// Text boxes are at least as big as the ones that used to be baked into images.
+const MIN_DIM: Vec2I = Vec2I { x: 300, y: 50 };
+const BACKGROUND: [u8; 4] = [0, 0, 0, 148];
+/// Size fallback fonts are drawn at, in pixels, picked to sit well next to the pixel font.
+const FALLBACK_SIZE: f32 = 16.0;
+/// Fonts most Windows systems have, which are tried after the ones asked for. Between them they
+/// cover Latin, Greek, Cyrillic, Hebrew and Arabic.
+const SYSTEM_FONTS: [&str; 2] = ["segoeui.ttf", "arial.ttf"];
+
+/// The hand-drawn font that her speech is written in. It only has lowercase letters, digits and a
+/// bit of punctuation.
+#[derive(Clone)]
+struct PixelFont {
+    /// Each pixel's alpha is how much of it the glyph covers.
+    atlas: RgbaImage,
+    /// Where each glyph is in the atlas, as (x, width).
+    glyphs: HashMap<char, (u32, u32)>,
+}
+
+impl PixelFont {
+    fn bundled() -> Self {
+        let atlas = image::load_from_memory(include_bytes!("../assets/text/glyphs.png"))
+            .unwrap()
+            .into_rgba8();
+        let mut glyphs = HashMap::new();
+        let mut x = 0;
+        for (c, width) in GLYPH_WIDTHS {
+            glyphs.insert(c, (x, width));
+            x += width + 1;
+        }
+        Self { atlas, glyphs }
+    }
+
+    /// Whether `c` can be written with this font, uppercase letters included.
+    fn has(&self, c: char) -> bool {
+        c == ' ' || self.glyphs.contains_key(&c.to_ascii_lowercase())
+    }
+    /// The glyph `c` gets drawn with (or a space).
+    fn glyph_for(&self, c: char) -> char {
+        let c = c.to_ascii_lowercase();
+        if self.has(c) {
+            c
+        } else {
+            MISSING_GLYPH
+        }
+    }
+    fn width(&self, glyph: char) -> i32 {
+        self.glyphs[&glyph].1 as i32
+    }
+    /// The pixels of `glyph`, in the top left of a cache cell.
+    fn cell(&self, glyph: char) -> [[glrs::GLTexPixel; CELL_W]; CELL_H] {
+        let (x0, width) = self.glyphs[&glyph];
+        let mut cell = [[glrs::GLTexPixel::default(); CELL_W]; CELL_H];
+        for (y, row) in cell
+            .iter_mut()
+            .enumerate()
+            .take(self.atlas.height() as usize)
+        {
+            for (x, pixel) in row.iter_mut().enumerate().take(width as usize) {
+                let Rgba([r, g, b, a]) = *self.atlas.get_pixel(x0 + x as u32, y as u32);
+                *pixel = glrs::GLTexPixel { r, g, b, a };
+            }
+        }
+        cell
+    }
+}
+
+/// What text gets written with: the pixel font whenever it has every character, and otherwise
+/// fonts from files, which can have any script in them.
+#[derive(Clone)]
+pub struct Fonts {
+    pixel: PixelFont,
+    /// Tried in order for each character.
+    fallback: Rc<Vec<FontVec>>,
+}
+
+/// One thing in a line of text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Glyph {
+    /// A glyph of the pixel font.
+    Pixel(char),
+    /// A glyph of the fallback font at the index.
+    Vector(usize, GlyphId),
+    /// Empty space this wide.
+    Space(i32),
+}
+
+impl std::fmt::Debug for Fonts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Fonts {{ fallback: {} font(s) }}", self.fallback.len())
+    }
+}
+
+impl Fonts {
+    /// The pixel font, with whichever of `files` can be loaded to fall back on, then some fonts
+    /// the system probably has. Files can be paths, or just names of fonts installed on the
+    /// system. Files in `files` that can't be used get reported.
+    pub fn load(files: &[String]) -> Self {
+        let mut fallback = vec![];
+        for file in files {
+            match load_font(file) {
+                Ok(font) => fallback.push(font),
+                Err(e) => eprintln!("[font {}] {}", file, e),
+            }
+        }
+        fallback.extend(SYSTEM_FONTS.iter().filter_map(|file| load_font(file).ok()));
+        Self {
+            pixel: PixelFont::bundled(),
+            fallback: Rc::new(fallback),
+        }
+    }
+
+    /// The glyphs `line` is written with. Everything is written with the pixel font if it can be,
+    /// otherwise nothing is, so a line never mixes styles.
+    fn glyphs(&self, line: &str, pixel: bool) -> Vec<Glyph> {
+        if pixel || self.fallback.is_empty() {
+            return line
+                .chars()
+                .map(|c| match self.pixel.glyph_for(c) {
+                    ' ' => Glyph::Space(SPACE_WIDTH),
+                    c => Glyph::Pixel(c),
+                })
+                .collect();
+        }
+        line.chars()
+            .map(|c| {
+                if c.is_whitespace() {
+                    return Glyph::Space(self.advance(0, self.fallback[0].glyph_id(' ')));
+                }
+                let found = self.fallback.iter().enumerate().find_map(|(i, font)| {
+                    let id = font.glyph_id(c);
+                    (id.0 != 0).then_some((i, id))
+                });
+                match found {
+                    Some((i, id)) => Glyph::Vector(i, id),
+                    None => Glyph::Vector(0, self.fallback[0].glyph_id(MISSING_GLYPH)),
+                }
+            })
+            .collect()
+    }
+    /// How far along the line the next glyph goes.
+    fn width(&self, glyph: Glyph) -> i32 {
+        match glyph {
+            Glyph::Pixel(c) => self.pixel.width(c) + LETTER_SPACING,
+            Glyph::Vector(font, id) => self.advance(font, id),
+            Glyph::Space(width) => width,
+        }
+    }
+    fn advance(&self, font: usize, id: GlyphId) -> i32 {
+        let scaled = self.fallback[font].as_scaled(PxScale::from(FALLBACK_SIZE));
+        scaled.h_advance(id).ceil() as i32
+    }
+    /// The pixels of `glyph`, in the top left of a cache cell.
+    fn cell(&self, glyph: Glyph) -> [[glrs::GLTexPixel; CELL_W]; CELL_H] {
+        let (font, id) = match glyph {
+            Glyph::Pixel(c) => return self.pixel.cell(c),
+            Glyph::Vector(font, id) => (&self.fallback[font], id),
+            Glyph::Space(_) => return [[glrs::GLTexPixel::default(); CELL_W]; CELL_H],
+        };
+        let mut cell = [[glrs::GLTexPixel::default(); CELL_W]; CELL_H];
+        let ascent = font
+            .as_scaled(PxScale::from(FALLBACK_SIZE))
+            .ascent()
+            .round();
+        let positioned = id.with_scale_and_position(FALLBACK_SIZE, point(0.0, ascent));
+        let Some(outline) = font.outline_glyph(positioned) else {
+            return cell;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|x, y, coverage| {
+            let x = x as i32 + bounds.min.x as i32;
+            let y = y as i32 + bounds.min.y as i32;
+            if (0..CELL_W as i32).contains(&x) && (0..CELL_H as i32).contains(&y) {
+                cell[y as usize][x as usize] = glrs::GLTexPixel {
+                    r: 255,
+                    g: 255,
+                    b: 255,
+                    a: (coverage.clamp(0.0, 1.0) * 255.0) as u8,
+                };
+            }
+        });
+        cell
+    }
+}
+
+/// Reads the font in `file`, which is either a path or the name of a font installed on the
+/// system. Collections (`.ttc`) give their first font.
+fn load_font(file: &str) -> Result<FontVec, String> {
+    let path = Some(PathBuf::from(file))
+        .filter(|path| path.exists())
+        .or_else(|| {
+            system_font_dirs()
+                .into_iter()
+                .map(|dir| dir.join(file))
+                .find(|path| path.exists())
+        })
+        .ok_or_else(|| "could not find it, or in the system's fonts".to_string())?;
+    let data = fs::read(&path).map_err(|e| format!("could not read it: {}", e))?;
+    FontVec::try_from_vec_and_index(data, 0).map_err(|e| format!("could not use it: {}", e))
+}
+
+/// Where the system keeps fonts: the Windows folder's, and the user's own.
+fn system_font_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    if let Some(windows) = env::var_os("WINDIR").or_else(|| env::var_os("SystemRoot")) {
+        dirs.push(Path::new(&windows).join("Fonts"));
+    }
+    if let Some(local) = env::var_os("LOCALAPPDATA") {
+        dirs.push(Path::new(&local).join("Microsoft/Windows/Fonts"));
+    }
+    dirs
+}
+
+/// A box with text in it, drawn with [`Fonts`]. Glyphs get copied into a texture the first time
+/// they're needed, and every glyph is drawn straight from there.
+pub struct Text {
+    vo: glrs::TriPosVO<2>,
+    shaders: glrs::GLShaderProgramLinked,
+    fonts: Fonts,
+    /// Glyphs that have been needed so far, one per cell, left to right and then top to bottom.
+    cache: glrs::GLTexture2d,
+    /// Which cell each glyph is in.
+    cached: HashMap<Glyph, usize>,
+    /// Drawn behind the text to make a box around it, unless it's bare.
+    background: Option<glrs::GLTexture2d>,
+    text: String,
+    /// Cell and position (relative to the box) of every glyph to draw.
+    layout: Vec<(usize, Vec2I)>,
+    /// Size of the box (or of just the text, if it's bare), or 0 if there's no text.
+    dim: Vec2I,
+    pub pos: (Vec2I, RelativeTo, Anchor),
+}
+
+impl Text {
+    /// Has to be called while the context of the window it'll be drawn in is current, like any
+    /// other renderable.
+    pub fn new(fonts: Fonts) -> Self {
+        let background = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba(BACKGROUND)));
+        Self {
+            background: Some(glrs::GLTexture2d::new(&ImageAsset::from_dynamicimage(
+                background,
+            ))),
+            ..Self::bare(fonts)
+        }
+    }
+    /// Just the text, without a box around it, for drawing on top of something else.
+    pub fn bare(fonts: Fonts) -> Self {
+        let blank =
+            DynamicImage::new_rgba8((CELL_W * CACHE_COLS) as u32, (CELL_H * CACHE_ROWS) as u32);
+        Self {
+            vo: glrs::TriPosVO::new(FULL_QUAD_TRIS),
+            shaders: load_sprite_shaders(),
+            fonts,
+            cache: glrs::GLTexture2d::new(&ImageAsset::from_dynamicimage(blank)),
+            cached: HashMap::new(),
+            background: None,
+            text: String::new(),
+            layout: vec![],
+            dim: Vec2I::new(0, 0),
+            pos: (Vec2I::new(0, 0), RelativeTo::Window, Anchor::TopLeft),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    /// Shows `text`, one line per line, each one centered. An empty string shows nothing at all.
+    /// The window it's drawn in has to be current, in case there are glyphs to add to the cache.
+    pub fn set_text(&mut self, text: &str) {
+        if text == self.text {
+            return;
+        }
+        self.text = text.to_string();
+        self.layout.clear();
+        if text.is_empty() {
+            self.dim = Vec2I::new(0, 0);
+            return;
+        }
+
+        let pixel = text.chars().all(|c| c == '\n' || self.fonts.pixel.has(c));
+        let lines: Vec<Vec<Glyph>> = text
+            .lines()
+            .map(|line| self.fonts.glyphs(line, pixel))
+            .collect();
+        // Make room for this text's glyphs if they don't all fit next to the ones already there.
+        // Nothing else is drawn from the cache, so it's fine to start it over.
+        let mut new: Vec<Glyph> = lines
+            .iter()
+            .flatten()
+            .filter(|g| !matches!(g, Glyph::Space(_)) && !self.cached.contains_key(g))
+            .copied()
+            .collect();
+        new.sort();
+        new.dedup();
+        if self.cached.len() + new.len() > CACHE_COLS * CACHE_ROWS {
+            self.cached.clear();
+        }
+
+        let widths: Vec<i32> = lines
+            .iter()
+            .map(|line| {
+                let trailing = match line.last() {
+                    Some(Glyph::Pixel(_)) => LETTER_SPACING,
+                    _ => 0,
+                };
+                line.iter().map(|&g| self.fonts.width(g)).sum::<i32>() - trailing
+            })
+            .collect();
+        let widest = widths.iter().copied().max().unwrap_or(0);
+        let lines_height = (lines.len() as i32 - 1).max(0) * LINE_HEIGHT + CELL_H as i32;
+        self.dim = Vec2I::new(widest, lines_height);
+        if self.background.is_some() {
+            self.dim = Vec2I::new(
+                (widest + 2 * PADDING).max(MIN_DIM.x),
+                (lines_height + 2 * PADDING).max(MIN_DIM.y),
+            );
+        }
+
+        let top = (self.dim.y - lines_height) / 2;
+        for (i, (line, width)) in lines.iter().zip(widths).enumerate() {
+            let mut x = (self.dim.x - width) / 2;
+            for &glyph in line {
+                if !matches!(glyph, Glyph::Space(_)) {
+                    if let Some(cell) = self.cache_glyph(glyph) {
+                        self.layout
+                            .push((cell, Vec2I::new(x, top + i as i32 * LINE_HEIGHT)));
+                    }
+                }
+                x += self.fonts.width(glyph);
+            }
+        }
+    }
+    /// The cell `glyph` is in, after copying it into the cache if it isn't there yet. `None` if
+    /// the cache is full.
+    fn cache_glyph(&mut self, glyph: Glyph) -> Option<usize> {
+        if let Some(cell) = self.cached.get(&glyph) {
+            return Some(*cell);
+        }
+        let cell = self.cached.len();
+        if cell >= CACHE_COLS * CACHE_ROWS {
+            return None;
+        }
+        let (col, row) = (cell % CACHE_COLS, cell / CACHE_COLS);
+        self.cache
+            .update_partial(col * CELL_W, row * CELL_H, self.fonts.cell(glyph));
+        self.cached.insert(glyph, cell);
+        Some(cell)
+    }
+
+    pub fn current_dims(&self) -> Vec2I {
+        self.dim
+    }
+    /// Where the box is, in the same coordinates as `pos`.
+    pub fn current_bounds(&self) -> RectI {
+        RectI {
+            pos: self.pos.2.apply(self.pos.0, self.dim),
+            dim: self.dim,
+        }
+    }
+}
+
+impl Renderable for Text {
+    fn render(&self, glu: GLUtil) {
+        if self.layout.is_empty() {
+            return;
+        }
+        let bounds = self.current_bounds();
+        let style = |uv_rect| QuadStyle {
+            uv_rect,
+            clip: None,
+            occluders: &[],
+            tint: [0.0; 4],
+        };
+        if let Some(background) = &self.background {
+            style([0.0, 0.0, 1.0, 1.0]).draw(
+                glu,
+                &self.shaders,
+                &self.vo,
+                background,
+                bounds,
+                self.pos.1,
+            );
+        }
+        let (cache_w, cache_h) = ((CELL_W * CACHE_COLS) as f32, (CELL_H * CACHE_ROWS) as f32);
+        for &(cell, at) in &self.layout {
+            let (col, row) = (cell % CACHE_COLS, cell / CACHE_COLS);
+            style([
+                (col * CELL_W) as f32 / cache_w,
+                (row * CELL_H) as f32 / cache_h,
+                CELL_W as f32 / cache_w,
+                CELL_H as f32 / cache_h,
+            ])
+            .draw(
+                glu,
+                &self.shaders,
+                &self.vo,
+                &self.cache,
+                RectI {
+                    pos: bounds.pos + at,
+                    dim: Vec2I::new(CELL_W as i32, CELL_H as i32),
+                },
+                self.pos.1,
+            );
+        }
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,147 @@
+use std::time::{Duration, SystemTime};
+
+/// Points a find is worth if it's instant.
+const FIND_POINTS: u32 = 1000;
+/// Points a find loses for every second it takes.
+const POINTS_LOST_PER_SEC: f64 = 25.0;
+/// Points taken off for every miss.
+const MISS_PENALTY: u32 = 50;
+/// Points taken off for every level of hints she had to drop before being found.
+const HINT_PENALTY: u32 = 100;
+
+/// Names of the modes, as they're written in the config file.
+pub const MODES: [&str; 2] = ["endless", "time_attack"];
+
+/// How the game is played.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Hide and seek for as long as you like, with nothing keeping score.
+    Endless,
+    /// Rounds of finding her a set number of times as fast as you can.
+    TimeAttack,
+}
+
+impl Mode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "endless" => Some(Self::Endless),
+            "time_attack" => Some(Self::TimeAttack),
+            _ => None,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Endless => "endless",
+            Self::TimeAttack => "time_attack",
+        }
+    }
+}
+
+/// One time-attack round, from when she first hides until she's been found enough times.
+#[derive(Debug, Clone)]
+pub struct Round {
+    /// How many finds it takes to finish.
+    pub hides: u32,
+    pub t_begin: SystemTime,
+    /// How long each find took, oldest first.
+    pub find_times: Vec<Duration>,
+    pub misses: u32,
+    /// Levels of hints she dropped, over all the finds.
+    pub hints: u32,
+    /// How long the whole round took, once it's over.
+    pub time: Option<Duration>,
+}
+
+impl Round {
+    pub fn new(hides: u32) -> Self {
+        Self {
+            hides,
+            t_begin: SystemTime::now(),
+            find_times: vec![],
+            misses: 0,
+            hints: 0,
+            time: None,
+        }
+    }
+
+    /// She was found after `time`, once she'd dropped `hints` levels of hints. Finds after the
+    /// round is over don't count.
+    pub fn found(&mut self, time: Duration, hints: u32) {
+        if self.is_over() {
+            return;
+        }
+        self.find_times.push(time);
+        self.hints += hints;
+        if self.find_times.len() as u32 >= self.hides {
+            self.time = Some(self.elapsed());
+        }
+    }
+    pub fn missed(&mut self) {
+        if !self.is_over() {
+            self.misses += 1;
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.time.is_some()
+    }
+    /// How long the round has been going, or how long it took if it's over.
+    pub fn elapsed(&self) -> Duration {
+        self.time
+            .unwrap_or_else(|| self.t_begin.elapsed().unwrap_or(Duration::ZERO))
+    }
+
+    /// Every find is worth up to [`FIND_POINTS`], less the longer it took, and every miss and hint
+    /// costs [`MISS_PENALTY`] and [`HINT_PENALTY`].
+    pub fn score(&self) -> u32 {
+        let found: u32 = self
+            .find_times
+            .iter()
+            .map(|t| (FIND_POINTS as f64 - t.as_secs_f64() * POINTS_LOST_PER_SEC).max(0.0) as u32)
+            .sum();
+        found.saturating_sub(self.misses * MISS_PENALTY + self.hints * HINT_PENALTY)
+    }
+}
+
+/// `time` as minutes and seconds, like `1:05`.
+pub fn clock(time: Duration) -> String {
+    let secs = time.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn score() {
+        let mut round = Round::new(3);
+        round.found(secs(0), 0);
+        round.missed();
+        round.found(secs(10), 1);
+        round.missed();
+        // too slow to be worth anything, but not less than nothing
+        round.found(secs(60), 0);
+        assert_eq!(round.score(), 1000 + 750 - 2 * 50 - 100);
+
+        round.misses = 100;
+        assert_eq!(round.score(), 0);
+    }
+
+    #[test]
+    fn nothing_counts_once_its_over() {
+        let mut round = Round::new(2);
+        round.found(secs(1), 0);
+        assert!(!round.is_over());
+        round.found(secs(2), 0);
+        assert!(round.is_over());
+        round.found(secs(3), 2);
+        round.missed();
+        assert_eq!(round.find_times, [secs(1), secs(2)]);
+        assert_eq!((round.misses, round.hints), (0, 0));
+    }
+}
@@ -0,0 +1,536 @@
+use std::{
+    env,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    aware::needs::Needs,
+    stats::Stats,
+    util::{
+        json::{parse_file, read_file, Fields, FileError, FileErrorKind, Json},
+        Vec2I,
+    },
+};
+
+/// Version of the save file layout this build writes. Older save files get migrated up to it.
+const SAVE_VERSION: u32 = 2;
+/// Upgrades a save file from each old version to the next one. The first entry takes version 1 to
+/// version 2, and so on, so there's one for every version before [`SAVE_VERSION`].
+const MIGRATIONS: [fn(Json) -> Json; SAVE_VERSION as usize - 1] = [v1_to_v2];
+
+/// Folder in the platform's data directory that the game keeps its files in.
+const APP_DIR: &str = "silly_little_creature";
+const SAVE_FILE: &str = "save.json";
+const SAVE_WHAT: &str = "save file";
+
+/// Everything that's remembered between runs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Save {
+    /// Settings the game last ran with, laid out like the config file. Used when there is no
+    /// config file.
+    pub settings: Option<Json>,
+    /// One entry per creature there has ever been, in order.
+    pub creatures: Vec<CreatureSave>,
+    pub stats: Stats,
+    /// Ids of the achievements that have been unlocked, in the order they were.
+    pub achievements: Vec<String>,
+}
+
+/// What's remembered about one creature.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CreatureSave {
+    pub catch_count: u32,
+    /// Name of the last thing that happened to her that she hasn't talked about yet.
+    pub last_event: Option<String>,
+    /// How long the latest finds took, oldest first, for adaptive difficulty.
+    pub recent_finds: Vec<Duration>,
+    /// Name of the behavior state she was in.
+    pub state: Option<String>,
+    /// Where she was on screen.
+    pub pos: Option<Vec2I>,
+    /// How she was feeling.
+    pub needs: Option<Needs>,
+    /// When all of this was true, so she can catch up on the time she was away.
+    pub last_seen: Option<SystemTime>,
+    /// Day she was last seen on, by her clock, in days since 1970-01-01.
+    pub last_day: Option<i64>,
+}
+
+/// Where the game keeps its files: `%APPDATA%` on Windows, `~/Library/Application Support` on
+/// macOS, and `$XDG_DATA_HOME` (or `~/.local/share`) everywhere else.
+pub fn data_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        PathBuf::from(env::var_os("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(env::var_os("HOME")?).join("Library/Application Support")
+    } else {
+        match env::var_os("XDG_DATA_HOME").map(PathBuf::from) {
+            // relative paths are to be ignored, as per the spec
+            Some(dir) if dir.is_absolute() => dir,
+            _ => PathBuf::from(env::var_os("HOME")?).join(".local/share"),
+        }
+    };
+    Some(base.join(APP_DIR))
+}
+
+/// A fresh, empty folder named after `name` for a test to write files in.
+#[cfg(test)]
+pub fn test_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("{}-test-{}-{}", APP_DIR, name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes `contents` to `path`, creating the folders on the way if needed. It goes to a temporary
+/// file first, which then replaces the old one, so a crash halfway through never leaves a broken
+/// file behind.
+pub fn write_atomically(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let written = File::create(&tmp)
+        .and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    written
+}
+
+/// Loads the file at `path` with `load`, or starts fresh if there isn't one. A file that can't be
+/// used is reported and moved out of the way, so it doesn't get overwritten. `path` is `None` if
+/// there's no data directory to look in.
+pub fn load_or_default<T: Default>(
+    path: Option<PathBuf>,
+    what: &'static str,
+    load: impl FnOnce(&Path) -> Result<T, FileError>,
+) -> T {
+    let Some(path) = path else {
+        eprintln!("{}", FileError::new(what, FileErrorKind::NoDataDir));
+        return T::default();
+    };
+    if !path.exists() {
+        return T::default();
+    }
+    match load(&path) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("[{}] {}", path.display(), e);
+            let backup = path.with_extension("json.bak");
+            match fs::rename(&path, &backup) {
+                Ok(()) => eprintln!("moved it to {} and starting fresh", backup.display()),
+                Err(e) => eprintln!("starting fresh (could not move it aside: {})", e),
+            }
+            T::default()
+        }
+    }
+}
+
+/// Reads the JSON file at `path`, which says which version of its layout it was written with.
+/// Once the version is checked to be one this build knows, which means no newer than
+/// `known_version`, `parse` gets it along with the file's contents and somewhere to report
+/// problems.
+pub fn load_versioned<T>(
+    path: &Path,
+    what: &'static str,
+    known_version: u32,
+    parse: impl FnOnce(u32, Json, &mut Vec<String>) -> T,
+) -> Result<T, FileError> {
+    let error = |kind| FileError::new(what, kind);
+    let json = parse_file(&read_file(path, what)?, what)?;
+    let version = match json.get("version").and_then(Json::as_f64) {
+        Some(v) if v.fract() == 0.0 && v >= 1.0 => v as u32,
+        _ => {
+            return Err(error(FileErrorKind::Invalid(vec![
+                "version: expected a whole number of at least 1".to_string(),
+            ])))
+        }
+    };
+    if version > known_version {
+        return Err(error(FileErrorKind::TooNew(version, known_version)));
+    }
+    let mut errors = vec![];
+    let parsed = parse(version, json, &mut errors);
+    FileError::check(what, errors)?;
+    Ok(parsed)
+}
+
+/// Writes `json` to `path` the way [`load_versioned`] reads it back (see [`write_atomically`]).
+pub fn write_json(path: &Path, what: &'static str, json: &Json) -> Result<(), FileError> {
+    write_atomically(path, &format!("{:#}\n", json))
+        .map_err(|e| FileError::new(what, FileErrorKind::Write(e)))
+}
+
+impl Save {
+    pub fn path() -> Option<PathBuf> {
+        Some(data_dir()?.join(SAVE_FILE))
+    }
+
+    /// Loads the save file, or starts fresh if there isn't one (see [`load_or_default`]).
+    pub fn load() -> Self {
+        load_or_default(Self::path(), SAVE_WHAT, Self::load_file)
+    }
+
+    /// Reads a save file of any version up to [`SAVE_VERSION`], migrating it first if it's old.
+    pub fn load_file(path: &Path) -> Result<Self, FileError> {
+        load_versioned(path, SAVE_WHAT, SAVE_VERSION, Self::parse)
+    }
+
+    fn parse(version: u32, mut json: Json, errors: &mut Vec<String>) -> Self {
+        for migrate in &MIGRATIONS[version as usize - 1..] {
+            json = migrate(json);
+        }
+
+        let mut save = Self {
+            settings: json.get("settings").filter(|s| **s != Json::Null).cloned(),
+            ..Default::default()
+        };
+        if let Some(stats) = json.get("stats") {
+            let mut fields = Fields::new(stats, "stats".to_string(), errors);
+            save.stats = Stats::parse(&mut fields);
+            fields.finish();
+        }
+        match json.get("achievements").map(|a| (a, a.as_array())) {
+            Some((_, Some(ids))) => {
+                for id in ids {
+                    match id.as_str() {
+                        Some(id) => save.achievements.push(id.to_string()),
+                        None => errors.push(format!(
+                            "achievements: expected achievement ids, found {}",
+                            id.type_name()
+                        )),
+                    }
+                }
+            }
+            Some((other, None)) => errors.push(format!(
+                "achievements: expected an array, found {}",
+                other.type_name()
+            )),
+            None => {}
+        }
+        match json.get("creatures").map(|c| (c, c.as_array())) {
+            Some((_, Some(creatures))) => {
+                for (i, creature) in creatures.iter().enumerate() {
+                    let mut fields = Fields::new(creature, format!("creatures[{}]", i), errors);
+                    save.creatures.push(CreatureSave::parse(&mut fields));
+                    fields.finish();
+                }
+            }
+            Some((other, None)) => errors.push(format!(
+                "creatures: expected an array, found {}",
+                other.type_name()
+            )),
+            None => {}
+        }
+
+        save
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::Object(vec![
+            ("version".to_string(), Json::Number(SAVE_VERSION as f64)),
+            (
+                "settings".to_string(),
+                self.settings.clone().unwrap_or(Json::Null),
+            ),
+            (
+                "creatures".to_string(),
+                Json::Array(self.creatures.iter().map(CreatureSave::to_json).collect()),
+            ),
+            ("stats".to_string(), self.stats.to_json()),
+            (
+                "achievements".to_string(),
+                Json::Array(
+                    self.achievements
+                        .iter()
+                        .map(|id| Json::String(id.clone()))
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+
+    /// Writes the save file to the data directory.
+    pub fn write(&self) -> Result<(), FileError> {
+        self.write_file(
+            &Self::path().ok_or_else(|| FileError::new(SAVE_WHAT, FileErrorKind::NoDataDir))?,
+        )
+    }
+
+    /// Writes the save file to `path`, without ever leaving a broken one behind (see
+    /// [`write_atomically`]).
+    pub fn write_file(&self, path: &Path) -> Result<(), FileError> {
+        write_json(path, SAVE_WHAT, &self.to_json())
+    }
+}
+
+impl CreatureSave {
+    /// Reads a creature the way [`CreatureSave::to_json`] writes it, reporting any problems to
+    /// `fields`.
+    fn parse(fields: &mut Fields) -> Self {
+        let name = |fields: &mut Fields, key: &'static str, what: &str| match fields.optional(key) {
+            None | Some(Json::Null) => None,
+            Some(Json::String(name)) => Some(name.clone()),
+            Some(other) => {
+                fields.error(
+                    key,
+                    format!("expected {} or null, found {}", what, other.type_name()),
+                );
+                None
+            }
+        };
+        let last_event = name(fields, "last_event", "an event name");
+        let state = name(fields, "state", "a state name");
+        let pos = match fields.optional("pos") {
+            None | Some(Json::Null) => None,
+            Some(pos) => match pos.as_array() {
+                Some([x, y]) => x.as_f64().zip(y.as_f64()),
+                _ => None,
+            }
+            .map(|(x, y)| Vec2I::new(x as i32, y as i32))
+            .or_else(|| {
+                fields.error("pos", "expected [x, y] or null");
+                None
+            }),
+        };
+        let needs = match fields.optional("needs") {
+            None | Some(Json::Null) => None,
+            Some(needs) => Needs::from_json(needs).or_else(|| {
+                fields.error(
+                    "needs",
+                    "expected { \"energy\", \"boredom\", \"affection\" } from 0 to 1, or null",
+                );
+                None
+            }),
+        };
+        let last_seen = match fields.optional("last_seen") {
+            None | Some(Json::Null) => None,
+            Some(_) => fields.timestamp("last_seen"),
+        };
+        let last_day = match fields.optional("last_day").map(|day| (day, day.as_f64())) {
+            None | Some((Json::Null, _)) => None,
+            Some((_, Some(day))) if day.fract() == 0.0 && day.is_finite() => Some(day as i64),
+            Some(_) => {
+                fields.error("last_day", "expected days since 1970 or null");
+                None
+            }
+        };
+        Self {
+            catch_count: fields.count("catch_count").unwrap_or(0),
+            last_event,
+            recent_finds: fields.seconds_list("recent_finds").unwrap_or_default(),
+            state,
+            pos,
+            needs,
+            last_seen,
+            last_day,
+        }
+    }
+
+    fn to_json(&self) -> Json {
+        Json::Object(vec![
+            (
+                "catch_count".to_string(),
+                Json::Number(self.catch_count as f64),
+            ),
+            (
+                "last_event".to_string(),
+                self.last_event.clone().map_or(Json::Null, Json::String),
+            ),
+            (
+                "recent_finds".to_string(),
+                Json::Array(
+                    self.recent_finds
+                        .iter()
+                        .map(|t| Json::Number(t.as_secs_f64()))
+                        .collect(),
+                ),
+            ),
+            (
+                "state".to_string(),
+                self.state.clone().map_or(Json::Null, Json::String),
+            ),
+            (
+                "pos".to_string(),
+                self.pos.map_or(Json::Null, |p| {
+                    Json::Array(vec![Json::Number(p.x as f64), Json::Number(p.y as f64)])
+                }),
+            ),
+            (
+                "needs".to_string(),
+                self.needs.map_or(Json::Null, |needs| needs.to_json()),
+            ),
+            (
+                "last_seen".to_string(),
+                self.last_seen
+                    .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                    .map_or(Json::Null, |t| Json::Number(t.as_secs() as f64)),
+            ),
+            (
+                "last_day".to_string(),
+                self.last_day
+                    .map_or(Json::Null, |day| Json::Number(day as f64)),
+            ),
+        ])
+    }
+}
+
+/// Version 1 kept whether she'd talked since she was last found as `message_state` (0 if she
+/// hadn't), where version 2 keeps the last thing that happened to her by name.
+fn v1_to_v2(mut json: Json) -> Json {
+    let Json::Object(entries) = &mut json else {
+        return json;
+    };
+    let creatures = entries
+        .iter_mut()
+        .filter(|(key, _)| key == "creatures")
+        .filter_map(|(_, value)| match value {
+            Json::Array(creatures) => Some(creatures),
+            _ => None,
+        })
+        .flatten();
+    for creature in creatures {
+        let Json::Object(fields) = creature else {
+            continue;
+        };
+        let get = |key: &str| {
+            fields
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+        };
+        let caught = get("catch_count").and_then(|n| n.as_f64()).unwrap_or(0.0) > 0.0;
+        let talked = get("message_state").and_then(|n| n.as_f64()).unwrap_or(0.0) != 0.0;
+        let last_event = match (talked, caught) {
+            (true, _) => "nothing",
+            (false, true) => "found",
+            (false, false) => "start",
+        };
+        fields.retain(|(key, _)| key != "message_state");
+        fields.push((
+            "last_event".to_string(),
+            Json::String(last_event.to_string()),
+        ));
+    }
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(dir: &Path, contents: &str) -> Result<Save, FileError> {
+        let path = dir.join(SAVE_FILE);
+        fs::write(&path, contents).unwrap();
+        Save::load_file(&path)
+    }
+
+    #[test]
+    fn migrates_version_1() {
+        let dir = test_dir("save-v1");
+        let save = load(
+            &dir,
+            r#"{"version": 1, "settings": null, "creatures": [
+                {"catch_count": 2, "message_state": 0, "recent_finds": [1.5]},
+                {"catch_count": 2, "message_state": 1},
+                {"catch_count": 0, "message_state": 0}
+            ]}"#,
+        )
+        .unwrap();
+        let last_events: Vec<_> = save
+            .creatures
+            .iter()
+            .map(|c| c.last_event.as_deref())
+            .collect();
+        assert_eq!(last_events, [Some("found"), Some("nothing"), Some("start")]);
+        assert_eq!(save.creatures[0].catch_count, 2);
+        assert_eq!(
+            save.creatures[0].recent_finds,
+            [Duration::from_secs_f64(1.5)]
+        );
+        assert_eq!(save.settings, None);
+    }
+
+    #[test]
+    fn loads_version_2() {
+        let dir = test_dir("save-v2");
+        let save = load(
+            &dir,
+            r#"{"version": 2, "settings": {"creatures": 2}, "creatures": [
+                {"catch_count": 3, "last_event": "found", "recent_finds": [], "state": "idle",
+                 "pos": [10, 20], "needs": null, "last_seen": 1000, "last_day": 19000}
+            ], "stats": {"hides": 4, "finds": 3, "total_find_time": 6, "fastest_find": 1,
+                         "find_times": [1, 2, 3], "streak": 3, "longest_streak": 3},
+               "achievements": ["first_find"]}"#,
+        )
+        .unwrap();
+        let creature = &save.creatures[0];
+        assert_eq!(creature.last_event.as_deref(), Some("found"));
+        assert_eq!(creature.state.as_deref(), Some("idle"));
+        assert_eq!(creature.pos, Some(Vec2I::new(10, 20)));
+        assert_eq!(
+            creature.last_seen,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1000))
+        );
+        assert_eq!(creature.last_day, Some(19000));
+        assert_eq!(save.stats.finds, 3);
+        assert_eq!(save.stats.fastest_find, Some(Duration::from_secs(1)));
+        assert_eq!(save.achievements, ["first_find"]);
+
+        // and it comes back the same after being written out
+        let path = dir.join("again.json");
+        save.write_file(&path).unwrap();
+        assert_eq!(Save::load_file(&path).unwrap(), save);
+    }
+
+    #[test]
+    fn reports_bad_fields() {
+        let dir = test_dir("save-bad");
+        let e = load(
+            &dir,
+            r#"{"version": 2, "creatures": [{"last_seen": 1e20, "recent_finds": [-1]}]}"#,
+        )
+        .unwrap_err();
+        let FileErrorKind::Invalid(problems) = e.kind else {
+            panic!("{}", e);
+        };
+        assert_eq!(
+            problems,
+            [
+                "creatures[0].last_seen: expected a number of seconds",
+                "creatures[0].recent_finds: expected numbers of seconds, found -1",
+            ]
+        );
+    }
+
+    #[test]
+    fn unusable_files_fall_back_to_defaults() {
+        let dir = test_dir("save-fallback");
+        for contents in [r#"{"version": 99, "creatures": []}"#, "not json {"] {
+            let path = dir.join(SAVE_FILE);
+            fs::write(&path, contents).unwrap();
+            let save = load_or_default(Some(path.clone()), SAVE_WHAT, Save::load_file);
+            assert_eq!(save, Save::default());
+            // moved aside rather than left to be overwritten
+            assert!(!path.exists());
+            assert_eq!(
+                fs::read_to_string(path.with_extension("json.bak")).unwrap(),
+                contents
+            );
+        }
+        assert!(matches!(
+            load(&dir, r#"{"version": 99}"#).unwrap_err().kind,
+            FileErrorKind::TooNew(99, SAVE_VERSION)
+        ));
+    }
+}
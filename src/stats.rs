@@ -0,0 +1,306 @@
+use std::{collections::BTreeMap, fs, io, path::Path, time::Duration};
+
+use crate::{
+    achievements::Achievement,
+    util::json::{Fields, Json},
+};
+
+/// How many of the latest find times are kept. Achievements for finding her quickly a number of
+/// times only look back this far.
+pub const KEPT_FIND_TIMES: usize = 1000;
+
+/// Everything that's been counted about playing with her, across all creatures.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    /// Times she went into hiding, not counting sneaking off to a new spot.
+    pub hides: u32,
+    /// Clicks on her that didn't catch her because she wasn't peeking.
+    pub misses: u32,
+    pub finds: u32,
+    /// How long all the finds took together, each from when she went into hiding.
+    pub total_find_time: Duration,
+    pub fastest_find: Option<Duration>,
+    /// How long the latest finds took, up to [`KEPT_FIND_TIMES`] of them, oldest first.
+    pub find_times: Vec<Duration>,
+    /// How many times she was found in each program, by file name.
+    pub app_finds: BTreeMap<String, u32>,
+    /// Finds in a row without missing in between.
+    pub streak: u32,
+    pub longest_streak: u32,
+}
+
+impl Stats {
+    pub fn hid(&mut self) {
+        self.hides += 1;
+    }
+    /// She was found after `time`, while hiding in `app`.
+    pub fn found(&mut self, time: Duration, app: Option<&str>) {
+        self.finds += 1;
+        // a broken save could have a total too big to add to
+        self.total_find_time = self.total_find_time.saturating_add(time);
+        self.fastest_find = Some(self.fastest_find.map_or(time, |t| t.min(time)));
+        self.find_times.push(time);
+        if self.find_times.len() > KEPT_FIND_TIMES {
+            self.find_times.remove(0);
+        }
+        if let Some(app) = app {
+            *self.app_finds.entry(app.to_string()).or_default() += 1;
+        }
+        self.streak += 1;
+        self.longest_streak = self.longest_streak.max(self.streak);
+    }
+    pub fn missed(&mut self) {
+        self.misses += 1;
+        self.streak = 0;
+    }
+
+    pub fn mean_find_time(&self) -> Option<Duration> {
+        (self.finds > 0).then(|| self.total_find_time / self.finds)
+    }
+
+    /// Reads stats the way [`Stats::to_json`] writes them, reporting any problems to `fields`.
+    pub fn parse(fields: &mut Fields) -> Self {
+        let fastest_find = match fields.optional("fastest_find") {
+            None | Some(Json::Null) => None,
+            Some(_) => fields.seconds("fastest_find"),
+        };
+        let mut find_times = fields.seconds_list("find_times").unwrap_or_default();
+        let extra = find_times.len().saturating_sub(KEPT_FIND_TIMES);
+        find_times.drain(..extra);
+        Self {
+            hides: fields.count("hides").unwrap_or(0),
+            misses: fields.count("misses").unwrap_or(0),
+            finds: fields.count("finds").unwrap_or(0),
+            total_find_time: fields.seconds("total_find_time").unwrap_or_default(),
+            fastest_find,
+            find_times,
+            app_finds: fields
+                .counts("app_finds")
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            streak: fields.count("streak").unwrap_or(0),
+            longest_streak: fields.count("longest_streak").unwrap_or(0),
+        }
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::Object(vec![
+            ("hides".to_string(), Json::Number(self.hides as f64)),
+            ("misses".to_string(), Json::Number(self.misses as f64)),
+            ("finds".to_string(), Json::Number(self.finds as f64)),
+            (
+                "total_find_time".to_string(),
+                Json::Number(self.total_find_time.as_secs_f64()),
+            ),
+            (
+                "fastest_find".to_string(),
+                self.fastest_find
+                    .map_or(Json::Null, |t| Json::Number(t.as_secs_f64())),
+            ),
+            (
+                "find_times".to_string(),
+                Json::Array(
+                    self.find_times
+                        .iter()
+                        .map(|t| Json::Number(t.as_secs_f64()))
+                        .collect(),
+                ),
+            ),
+            ("app_finds".to_string(), self.app_finds_json()),
+            ("streak".to_string(), Json::Number(self.streak as f64)),
+            (
+                "longest_streak".to_string(),
+                Json::Number(self.longest_streak as f64),
+            ),
+        ])
+    }
+    fn app_finds_json(&self) -> Json {
+        Json::Object(
+            self.app_finds
+                .iter()
+                .map(|(app, finds)| (app.clone(), Json::Number(*finds as f64)))
+                .collect(),
+        )
+    }
+
+    /// A summary of the stats along with the achievements in `unlocked`, for reading outside the
+    /// game. Times are in seconds.
+    pub fn summary_json(&self, unlocked: &[&Achievement]) -> Json {
+        let secs = |t: Option<Duration>| t.map_or(Json::Null, |t| Json::Number(t.as_secs_f64()));
+        Json::Object(vec![
+            ("hides".to_string(), Json::Number(self.hides as f64)),
+            ("finds".to_string(), Json::Number(self.finds as f64)),
+            ("misses".to_string(), Json::Number(self.misses as f64)),
+            ("mean_find_time".to_string(), secs(self.mean_find_time())),
+            ("fastest_find".to_string(), secs(self.fastest_find)),
+            (
+                "longest_streak".to_string(),
+                Json::Number(self.longest_streak as f64),
+            ),
+            ("finds_per_app".to_string(), self.app_finds_json()),
+            (
+                "achievements".to_string(),
+                Json::Array(
+                    unlocked
+                        .iter()
+                        .map(|a| {
+                            Json::Object(vec![
+                                ("id".to_string(), Json::String(a.id.clone())),
+                                ("name".to_string(), Json::String(a.name.clone())),
+                                (
+                                    "description".to_string(),
+                                    Json::String(a.description.clone()),
+                                ),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+
+    /// The same summary as [`Stats::summary_json`], as `stat,value` rows.
+    pub fn summary_csv(&self, unlocked: &[&Achievement]) -> String {
+        let secs = |t: Option<Duration>| t.map_or(String::new(), |t| t.as_secs_f64().to_string());
+        let mut rows = vec![
+            ("hides".to_string(), self.hides.to_string()),
+            ("finds".to_string(), self.finds.to_string()),
+            ("misses".to_string(), self.misses.to_string()),
+            ("mean_find_time".to_string(), secs(self.mean_find_time())),
+            ("fastest_find".to_string(), secs(self.fastest_find)),
+            (
+                "longest_streak".to_string(),
+                self.longest_streak.to_string(),
+            ),
+        ];
+        for (app, finds) in &self.app_finds {
+            rows.push((format!("finds in {}", app), finds.to_string()));
+        }
+        for achievement in unlocked {
+            rows.push(("achievement".to_string(), achievement.name.clone()));
+        }
+
+        let mut csv = String::from("stat,value\n");
+        for (stat, value) in rows {
+            csv += &format!("{},{}\n", csv_field(&stat), csv_field(&value));
+        }
+        csv
+    }
+
+    /// Writes the summary to `path`, as CSV if it ends in `.csv` and as JSON otherwise.
+    pub fn export(&self, path: &Path, unlocked: &[&Achievement]) -> io::Result<()> {
+        let is_csv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        if is_csv {
+            fs::write(path, self.summary_csv(unlocked))
+        } else {
+            fs::write(path, format!("{:#}\n", self.summary_json(unlocked)))
+        }
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+    fn parse(src: &str) -> (Stats, Vec<String>) {
+        let json = Json::parse(src).unwrap();
+        let mut errors = vec![];
+        let mut fields = Fields::new(&json, "stats".to_string(), &mut errors);
+        let stats = Stats::parse(&mut fields);
+        fields.finish();
+        (stats, errors)
+    }
+
+    #[test]
+    fn counts_finds() {
+        let mut stats = Stats::default();
+        stats.found(secs(4), Some("notepad.exe"));
+        stats.found(secs(2), Some("notepad.exe"));
+        stats.missed();
+        stats.found(secs(6), None);
+        assert_eq!((stats.finds, stats.misses), (3, 1));
+        assert_eq!(stats.fastest_find, Some(secs(2)));
+        assert_eq!(stats.mean_find_time(), Some(secs(4)));
+        assert_eq!(stats.find_times, [secs(4), secs(2), secs(6)]);
+        assert_eq!(stats.app_finds["notepad.exe"], 2);
+        // a miss starts the streak over, but the longest one stays
+        assert_eq!((stats.streak, stats.longest_streak), (1, 2));
+        assert_eq!(Stats::default().mean_find_time(), None);
+    }
+
+    #[test]
+    fn keeps_the_latest_find_times() {
+        let mut stats = Stats::default();
+        for i in 0..=KEPT_FIND_TIMES as u64 {
+            stats.found(secs(i), None);
+        }
+        assert_eq!(stats.find_times.len(), KEPT_FIND_TIMES);
+        assert_eq!(stats.find_times[0], secs(1));
+        assert_eq!(stats.finds, KEPT_FIND_TIMES as u32 + 1);
+
+        stats.total_find_time = Duration::MAX;
+        stats.found(secs(1), None);
+        assert_eq!(stats.total_find_time, Duration::MAX);
+    }
+
+    #[test]
+    fn parses_what_it_writes() {
+        let mut stats = Stats::default();
+        stats.hid();
+        stats.found(Duration::from_millis(1500), Some("explorer.exe"));
+        stats.missed();
+        let (parsed, errors) = parse(&stats.to_json().to_string());
+        assert_eq!(errors, Vec::<String>::new());
+        assert_eq!(parsed, stats);
+
+        // everything is optional
+        let (parsed, errors) = parse("{}");
+        assert!(errors.is_empty());
+        assert_eq!(parsed, Stats::default());
+    }
+
+    #[test]
+    fn reports_bad_stats() {
+        let (stats, errors) = parse(
+            r#"{"hides": -1, "fastest_find": "fast", "find_times": [1, 1e20],
+                "app_finds": {"notepad.exe": 1.5}, "streak": 2, "colour": "red"}"#,
+        );
+        assert_eq!(
+            errors,
+            [
+                "stats.fastest_find: expected a number of seconds",
+                "stats.find_times: expected numbers of seconds, found 100000000000000000000",
+                "stats.hides: expected a whole number of at least 0",
+                "stats.app_finds.notepad.exe: expected a whole number of at least 0",
+                "stats.colour: unknown field",
+            ]
+        );
+        assert_eq!(stats.streak, 2);
+    }
+
+    #[test]
+    fn trims_find_times_when_parsing() {
+        let times = (0..KEPT_FIND_TIMES + 5)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let (stats, errors) = parse(&format!(r#"{{"find_times": [{}]}}"#, times));
+        assert!(errors.is_empty());
+        assert_eq!(stats.find_times.len(), KEPT_FIND_TIMES);
+        assert_eq!(stats.find_times[0], secs(5));
+    }
+}
@@ -1,3 +1,5 @@
+pub mod json;
+
 use std::{
     iter::Rev,
     ops::{Add, Div, Mul, Sub},
@@ -12,13 +14,27 @@ pub struct RectI {
     pub dim: Vec2I,
 }
 impl RectI {
-    pub fn extend_up(&mut self, dist: i32) {
-        self.pos.y -= dist;
-        self.dim.y += dist;
+    pub fn contains(self, point: Vec2I) -> bool {
+        (self.pos.x..self.pos.x + self.dim.x).contains(&point.x)
+            && (self.pos.y..self.pos.y + self.dim.y).contains(&point.y)
+    }
+    pub fn intersects(self, other: RectI) -> bool {
+        self.pos.x < other.pos.x + other.dim.x
+            && other.pos.x < self.pos.x + self.dim.x
+            && self.pos.y < other.pos.y + other.dim.y
+            && other.pos.y < self.pos.y + self.dim.y
     }
-    pub fn extend_lr(&mut self, dist: i32) {
-        self.pos.x -= dist;
-        self.dim.x += 2 * dist;
+    /// The smallest rect that covers both.
+    pub fn union(self, other: RectI) -> RectI {
+        let pos = Vec2I::new(self.pos.x.min(other.pos.x), self.pos.y.min(other.pos.y));
+        let end = Vec2I::new(
+            (self.pos.x + self.dim.x).max(other.pos.x + other.dim.x),
+            (self.pos.y + self.dim.y).max(other.pos.y + other.dim.y),
+        );
+        RectI {
+            pos,
+            dim: end - pos,
+        }
     }
 }
 
@@ -0,0 +1,794 @@
+//! Just enough JSON to read and write the game's data files, and to say what's wrong with them.
+
+use std::{
+    fmt::Display,
+    fs,
+    iter::Peekable,
+    path::Path,
+    str::Chars,
+    time::{Duration, SystemTime},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Keys are kept in the order they appear in the file.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(src: &str) -> Result<Self, JsonError> {
+        let mut parser = Parser {
+            chars: src.chars().peekable(),
+            line: 1,
+            col: 1,
+        };
+        parser.skip_whitespace();
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.chars.peek().is_some() {
+            return Err(parser.error("unexpected characters after the end of the document"));
+        }
+        Ok(value)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Self::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(v) => Some(*v),
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(v) => Some(v.as_str()),
+            _ => None,
+        }
+    }
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Self::Array(v) => Some(v.as_slice()),
+            _ => None,
+        }
+    }
+    pub fn as_object(&self) -> Option<&[(String, Json)]> {
+        match self {
+            Self::Object(v) => Some(v.as_slice()),
+            _ => None,
+        }
+    }
+    /// Short description of what kind of value this is, for error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Bool(_) => "a boolean",
+            Self::Number(_) => "a number",
+            Self::String(_) => "a string",
+            Self::Array(_) => "an array",
+            Self::Object(_) => "an object",
+        }
+    }
+}
+
+/// Writes the value out as JSON. The alternate form (`{:#}`) spreads it over several indented
+/// lines.
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write(f, f.alternate(), 0)
+    }
+}
+impl Json {
+    fn write(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        pretty: bool,
+        depth: usize,
+    ) -> std::fmt::Result {
+        const INDENT: &str = "  ";
+        let newline = |f: &mut std::fmt::Formatter<'_>, depth: usize| {
+            if pretty {
+                write!(f, "\n{}", INDENT.repeat(depth))
+            } else {
+                Ok(())
+            }
+        };
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Bool(v) => write!(f, "{}", v),
+            // JSON has no way to write infinities or NaN
+            Self::Number(v) if !v.is_finite() => write!(f, "null"),
+            Self::Number(v) => write!(f, "{}", v),
+            Self::String(v) => write_string(f, v),
+            Self::Array(items) if items.is_empty() => write!(f, "[]"),
+            Self::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    newline(f, depth + 1)?;
+                    item.write(f, pretty, depth + 1)?;
+                }
+                newline(f, depth)?;
+                write!(f, "]")
+            }
+            Self::Object(entries) if entries.is_empty() => write!(f, "{{}}"),
+            Self::Object(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    newline(f, depth + 1)?;
+                    write_string(f, key)?;
+                    write!(f, "{}", if pretty { ": " } else { ":" })?;
+                    value.write(f, pretty, depth + 1)?;
+                }
+                newline(f, depth)?;
+                write!(f, "}}")
+            }
+        }
+    }
+}
+fn write_string(f: &mut std::fmt::Formatter<'_>, s: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+#[derive(Debug, Clone)]
+pub struct JsonError {
+    pub line: usize,
+    pub col: usize,
+    pub message: String,
+}
+impl Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.col, self.message
+        )
+    }
+}
+impl std::error::Error for JsonError {}
+
+/// Something wrong with one of the game's data files.
+#[derive(Debug)]
+pub struct FileError {
+    /// What the file is, like "save file", for the message.
+    pub what: &'static str,
+    pub kind: FileErrorKind,
+}
+#[derive(Debug)]
+pub enum FileErrorKind {
+    /// There's nowhere to put the file on this system.
+    NoDataDir,
+    Read(std::io::Error),
+    Parse(JsonError),
+    /// The file was written by a newer version of the game, which wrote version `.0` where this
+    /// one only knows up to `.1`.
+    TooNew(u32, u32),
+    /// Everything wrong with the file, one problem per entry.
+    Invalid(Vec<String>),
+    Write(std::io::Error),
+}
+impl FileError {
+    pub fn new(what: &'static str, kind: FileErrorKind) -> Self {
+        Self { what, kind }
+    }
+    /// Fine if nothing's wrong, otherwise all of `problems`.
+    pub fn check(what: &'static str, problems: Vec<String>) -> Result<(), Self> {
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Self::new(what, FileErrorKind::Invalid(problems)))
+        }
+    }
+}
+impl Display for FileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let what = self.what;
+        match &self.kind {
+            FileErrorKind::NoDataDir => {
+                write!(f, "could not find a data directory to keep the {} in", what)
+            }
+            FileErrorKind::Read(e) => write!(f, "could not read {}: {}", what, e),
+            FileErrorKind::Parse(e) => write!(f, "{} is not valid JSON: {}", what, e),
+            FileErrorKind::TooNew(version, known) => write!(
+                f,
+                "{} is version {}, but this version of the game only knows up to {}",
+                what, version, known
+            ),
+            FileErrorKind::Invalid(problems) => {
+                write!(f, "{} has {} problem(s):", what, problems.len())?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            }
+            FileErrorKind::Write(e) => write!(f, "could not write {}: {}", what, e),
+        }
+    }
+}
+impl std::error::Error for FileError {}
+
+/// Everything in the file at `path`, which is the game's `what`.
+pub fn read_file(path: &Path, what: &'static str) -> Result<String, FileError> {
+    fs::read_to_string(path).map_err(|e| FileError::new(what, FileErrorKind::Read(e)))
+}
+
+/// Parses `src`, the contents of the game's `what`.
+pub fn parse_file(src: &str, what: &'static str) -> Result<Json, FileError> {
+    Json::parse(src).map_err(|e| FileError::new(what, FileErrorKind::Parse(e)))
+}
+
+/// Loads the file at `path` next to the game with `load` if there is one, and otherwise uses
+/// `fallback`. A file that can't be used gets reported, and `fallback` (which `instead` names, like
+/// "the bundled behavior") is used in its place.
+pub fn load_override<T>(
+    path: &str,
+    load: impl FnOnce(&Path) -> Result<T, FileError>,
+    instead: &str,
+    fallback: impl FnOnce() -> T,
+) -> T {
+    if !Path::new(path).exists() {
+        return fallback();
+    }
+    match load(Path::new(path)) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("[{}] {}", path, e);
+            eprintln!("using {} instead", instead);
+            fallback()
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    col: usize,
+}
+impl<'a> Parser<'a> {
+    fn error(&self, message: impl Into<String>) -> JsonError {
+        JsonError {
+            line: self.line,
+            col: self.col,
+            message: message.into(),
+        }
+    }
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(c)
+    }
+    fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(self.error(format!("expected '{}', found '{}'", expected, c))),
+            None => Err(self.error(format!("expected '{}', found end of file", expected))),
+        }
+    }
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.next();
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, JsonError> {
+        match self.chars.peek().copied() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('n') => self.keyword("null", Json::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) => Err(self.error(format!("unexpected character '{}'", c))),
+            None => Err(self.error("unexpected end of file")),
+        }
+    }
+    fn keyword(&mut self, word: &str, value: Json) -> Result<Json, JsonError> {
+        for expected in word.chars() {
+            if self.next() != Some(expected) {
+                return Err(self.error(format!("expected '{}'", word)));
+            }
+        }
+        Ok(value)
+    }
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let mut text = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+                text.push(c);
+                self.next();
+            } else {
+                break;
+            }
+        }
+        if !valid_number(&text) {
+            return Err(self.error(format!("invalid number '{}'", text)));
+        }
+        text.parse()
+            .map(Json::Number)
+            .map_err(|_| self.error(format!("invalid number '{}'", text)))
+    }
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(out),
+                Some('\\') => match self.next() {
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some('/') => out.push('/'),
+                    Some('b') => out.push('\u{8}'),
+                    Some('f') => out.push('\u{c}'),
+                    Some('n') => out.push('\n'),
+                    Some('r') => out.push('\r'),
+                    Some('t') => out.push('\t'),
+                    Some('u') => {
+                        let high = self.hex4()?;
+                        let code = if (0xD800..0xDC00).contains(&high) {
+                            // surrogate pair
+                            self.expect('\\')?;
+                            self.expect('u')?;
+                            let low = self.hex4()?;
+                            if !(0xDC00..0xE000).contains(&low) {
+                                return Err(self.error("invalid unicode escape"));
+                            }
+                            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                        } else {
+                            high
+                        };
+                        out.push(
+                            char::from_u32(code)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?,
+                        );
+                    }
+                    _ => return Err(self.error("invalid escape sequence")),
+                },
+                Some(c) => out.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("invalid unicode escape"))?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+    fn array(&mut self) -> Result<Json, JsonError> {
+        self.expect('[')?;
+        let mut items = vec![];
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&']') {
+            self.next();
+            return Ok(Json::Array(items));
+        }
+        loop {
+            self.skip_whitespace();
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Json::Array(items)),
+                _ => return Err(self.error("expected ',' or ']' in array")),
+            }
+        }
+    }
+    fn object(&mut self) -> Result<Json, JsonError> {
+        self.expect('{')?;
+        let mut entries: Vec<(String, Json)> = vec![];
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&'}') {
+            self.next();
+            return Ok(Json::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            if entries.iter().any(|(k, _)| *k == key) {
+                return Err(self.error(format!("duplicate key \"{}\"", key)));
+            }
+            self.skip_whitespace();
+            self.expect(':')?;
+            self.skip_whitespace();
+            entries.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Json::Object(entries)),
+                _ => return Err(self.error("expected ',' or '}' in object")),
+            }
+        }
+    }
+}
+
+/// Whether `text` is a number the way JSON writes them: no leading zeros or `+` sign, and digits
+/// on both sides of any `.` or `e`.
+fn valid_number(text: &str) -> bool {
+    let digits = |rest: &mut &[u8]| {
+        let n = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+        *rest = &rest[n..];
+        n
+    };
+    let mut rest = text.strip_prefix('-').unwrap_or(text).as_bytes();
+    match rest.first() {
+        Some(b'0') => rest = &rest[1..],
+        Some(b'1'..=b'9') => {
+            digits(&mut rest);
+        }
+        _ => return false,
+    }
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        rest = fraction;
+        if digits(&mut rest) == 0 {
+            return false;
+        }
+    }
+    if let Some(exponent) = rest.strip_prefix(b"e").or(rest.strip_prefix(b"E")) {
+        rest = exponent;
+        rest = rest
+            .strip_prefix(b"+")
+            .or(rest.strip_prefix(b"-"))
+            .unwrap_or(rest);
+        if digits(&mut rest) == 0 {
+            return false;
+        }
+    }
+    rest.is_empty()
+}
+
+/// Longest any time in a data file can be, in milliseconds: a day. Anything longer is much more
+/// likely a typo than meant, and far enough out would overflow the clock.
+pub(crate) const MAX_DURATION_MS: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// `ms` milliseconds, if that's from 0 to [`MAX_DURATION_MS`].
+pub(crate) fn millis(ms: f64) -> Option<Duration> {
+    (0.0..=MAX_DURATION_MS)
+        .contains(&ms)
+        .then(|| Duration::from_millis(ms as u64))
+}
+
+/// Reads the fields of one JSON object, keeping track of where in the file it is so problems can
+/// be reported precisely, and which fields were looked at so typos can be caught.
+pub(crate) struct Fields<'j, 'e> {
+    json: &'j Json,
+    path: String,
+    errors: &'e mut Vec<String>,
+    known: Vec<&'static str>,
+}
+impl<'j, 'e> Fields<'j, 'e> {
+    pub(crate) fn new(json: &'j Json, path: String, errors: &'e mut Vec<String>) -> Self {
+        if json.as_object().is_none() {
+            errors.push(format!(
+                "{}: expected an object, found {}",
+                if path.is_empty() { "<root>" } else { &path },
+                json.type_name()
+            ));
+        }
+        Self {
+            json,
+            path,
+            errors,
+            known: vec![],
+        }
+    }
+    pub(crate) fn is_broken(&self) -> bool {
+        self.json.as_object().is_none()
+    }
+    fn key_path(&self, key: &str) -> String {
+        if self.path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", self.path, key)
+        }
+    }
+    pub(crate) fn error(&mut self, key: &str, message: impl Display) {
+        let path = self.key_path(key);
+        self.errors.push(format!("{}: {}", path, message));
+    }
+
+    pub(crate) fn optional(&mut self, key: &'static str) -> Option<&'j Json> {
+        self.known.push(key);
+        self.json.get(key)
+    }
+    /// The fields of the object at `key`, if there is one.
+    pub(crate) fn nested(&mut self, key: &'static str) -> Option<Fields<'j, '_>> {
+        let value = self.optional(key)?;
+        Some(self.within(value, key))
+    }
+    /// The fields of `json`, an object found somewhere under `key`, like in an array there.
+    pub(crate) fn within(&mut self, json: &'j Json, key: &str) -> Fields<'j, '_> {
+        let path = self.key_path(key);
+        Fields::new(json, path, self.errors)
+    }
+    pub(crate) fn required(&mut self, key: &'static str) -> Option<&'j Json> {
+        let value = self.optional(key);
+        if value.is_none() && !self.is_broken() {
+            self.error(key, "missing");
+        }
+        value
+    }
+
+    pub(crate) fn string(&mut self, key: &'static str) -> Option<String> {
+        let value = self.required(key)?;
+        match value.as_str() {
+            Some(s) => Some(s.to_string()),
+            None => {
+                self.error(
+                    key,
+                    format!("expected a string, found {}", value.type_name()),
+                );
+                None
+            }
+        }
+    }
+    pub(crate) fn positive_number(&mut self, key: &'static str) -> Option<f64> {
+        let value = self.required(key)?;
+        match value.as_f64() {
+            Some(n) if n > 0.0 => Some(n),
+            _ => {
+                self.error(key, "expected a number greater than 0");
+                None
+            }
+        }
+    }
+    pub(crate) fn whole_number(&mut self, key: &'static str) -> Option<u64> {
+        let value = self.required(key)?;
+        match value.as_f64() {
+            Some(n) if n >= 1.0 && n.fract() == 0.0 => Some(n as u64),
+            _ => {
+                self.error(key, "expected a whole number of at least 1");
+                None
+            }
+        }
+    }
+    pub(crate) fn fraction(&mut self, key: &'static str) -> Option<f64> {
+        let value = self.required(key)?;
+        match value.as_f64() {
+            Some(n) if (0.0..=1.0).contains(&n) => Some(n),
+            _ => {
+                self.error(key, "expected a number from 0 to 1");
+                None
+            }
+        }
+    }
+    /// A number of milliseconds, up to [`MAX_DURATION_MS`].
+    pub(crate) fn duration(&mut self, key: &'static str) -> Option<Duration> {
+        let value = self.required(key)?;
+        match value.as_f64().and_then(millis) {
+            Some(duration) => Some(duration),
+            None => {
+                self.error(
+                    key,
+                    format!("expected milliseconds, up to {}", MAX_DURATION_MS),
+                );
+                None
+            }
+        }
+    }
+    /// A whole number of at least 0, if it's there.
+    pub(crate) fn count(&mut self, key: &'static str) -> Option<u32> {
+        let value = self.optional(key)?;
+        match value.as_f64() {
+            Some(n) if n >= 0.0 && n.fract() == 0.0 => Some(n as u32),
+            _ => {
+                self.error(key, "expected a whole number of at least 0");
+                None
+            }
+        }
+    }
+    /// An object of whole numbers of at least 0 by name, if it's there.
+    pub(crate) fn counts(&mut self, key: &'static str) -> Option<Vec<(String, u32)>> {
+        let value = self.optional(key)?;
+        let Some(entries) = value.as_object() else {
+            self.error(
+                key,
+                format!("expected an object, found {}", value.type_name()),
+            );
+            return None;
+        };
+        let mut counts = vec![];
+        for (name, n) in entries {
+            match n.as_f64() {
+                Some(n) if n >= 0.0 && n.fract() == 0.0 => counts.push((name.clone(), n as u32)),
+                _ => self.error(
+                    &format!("{}.{}", key, name),
+                    "expected a whole number of at least 0",
+                ),
+            }
+        }
+        Some(counts)
+    }
+    /// A number of seconds, if it's there.
+    pub(crate) fn seconds(&mut self, key: &'static str) -> Option<Duration> {
+        let value = self.optional(key)?;
+        // too big to be a Duration, like 1e20, counts as wrong rather than panicking
+        match value
+            .as_f64()
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        {
+            Some(secs) => Some(secs),
+            None => {
+                self.error(key, "expected a number of seconds");
+                None
+            }
+        }
+    }
+    /// A moment, in seconds since 1970, if it's there.
+    pub(crate) fn timestamp(&mut self, key: &'static str) -> Option<SystemTime> {
+        let since = self.seconds(key)?;
+        let time = SystemTime::UNIX_EPOCH.checked_add(since);
+        if time.is_none() {
+            self.error(key, "too far in the future");
+        }
+        time
+    }
+    /// An array of numbers of seconds, if it's there.
+    pub(crate) fn seconds_list(&mut self, key: &'static str) -> Option<Vec<Duration>> {
+        let value = self.optional(key)?;
+        let Some(items) = value.as_array() else {
+            self.error(
+                key,
+                format!("expected an array, found {}", value.type_name()),
+            );
+            return None;
+        };
+        let mut times = vec![];
+        for item in items {
+            match item
+                .as_f64()
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+            {
+                Some(secs) => times.push(secs),
+                None => {
+                    self.error(key, format!("expected numbers of seconds, found {}", item));
+                    return None;
+                }
+            }
+        }
+        Some(times)
+    }
+
+    /// Reports any fields that were never looked at.
+    pub(crate) fn finish(self) {
+        for (key, _) in self.json.as_object().unwrap_or_default() {
+            if !self.known.contains(&key.as_str()) {
+                let path = self.key_path(key);
+                self.errors.push(format!("{}: unknown field", path));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_at(src: &str) -> (usize, usize) {
+        let e = Json::parse(src).unwrap_err();
+        (e.line, e.col)
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            Json::parse(r#""a\"\\\/\b\f\n\r\t\u00e9""#).unwrap(),
+            Json::String("a\"\\/\u{8}\u{c}\n\r\t\u{e9}".to_string())
+        );
+        assert!(Json::parse(r#""\x""#).is_err());
+        assert!(Json::parse(r#""\u12""#).is_err());
+        assert!(Json::parse(r#""unterminated"#).is_err());
+    }
+
+    #[test]
+    fn surrogates() {
+        assert_eq!(
+            Json::parse(r#""\ud83d\ude00""#).unwrap(),
+            Json::String("\u{1f600}".to_string())
+        );
+        // high surrogate followed by something that isn't a low one
+        assert!(Json::parse(r#""\ud83d\u0041""#).is_err());
+        assert!(Json::parse(r#""\ud83d\ud83d""#).is_err());
+        assert!(Json::parse(r#""\ud83d""#).is_err());
+        // low surrogate on its own
+        assert!(Json::parse(r#""\ude00""#).is_err());
+    }
+
+    #[test]
+    fn numbers() {
+        for (src, n) in [
+            ("0", 0.0),
+            ("-0", 0.0),
+            ("12", 12.0),
+            ("-3.25", -3.25),
+            ("1e3", 1000.0),
+            ("2.5E-1", 0.25),
+            ("1e+2", 100.0),
+        ] {
+            assert_eq!(Json::parse(src).unwrap(), Json::Number(n), "{}", src);
+        }
+        for src in [
+            "01", "-01", "+1", "1.", ".5", "1e", "1e+", "--1", "1-2", "-", "1.2.3",
+        ] {
+            assert!(Json::parse(src).is_err(), "{}", src);
+        }
+    }
+
+    #[test]
+    fn nesting() {
+        let json =
+            Json::parse(r#" { "a": [1, {"b": null}, []], "c": {}, "d": [true, false] } "#).unwrap();
+        assert_eq!(
+            json,
+            Json::Object(vec![
+                (
+                    "a".to_string(),
+                    Json::Array(vec![
+                        Json::Number(1.0),
+                        Json::Object(vec![("b".to_string(), Json::Null)]),
+                        Json::Array(vec![]),
+                    ])
+                ),
+                ("c".to_string(), Json::Object(vec![])),
+                (
+                    "d".to_string(),
+                    Json::Array(vec![Json::Bool(true), Json::Bool(false)])
+                ),
+            ])
+        );
+        // writing it out and reading it back gives the same thing, either way it's written
+        assert_eq!(Json::parse(&json.to_string()).unwrap(), json);
+        assert_eq!(Json::parse(&format!("{:#}", json)).unwrap(), json);
+    }
+
+    #[test]
+    fn error_positions() {
+        assert_eq!(error_at("[1,\n  01]"), (2, 5));
+        assert_eq!(error_at("{\n\"a\": 1,\n\"a\": 2}"), (3, 4));
+        assert_eq!(error_at("[1 2]"), (1, 5));
+        assert_eq!(error_at("{\"a\" 1}"), (1, 7));
+        assert_eq!(error_at("[1] x"), (1, 5));
+        assert_eq!(error_at("[1,"), (1, 4));
+    }
+}